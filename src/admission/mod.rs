@@ -0,0 +1 @@
+pub mod webhook;
\ No newline at end of file
@@ -0,0 +1,386 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+use thiserror::Error;
+
+use crate::controlplane::controlplane::{Component, ControlPlane, ControlPlaneError};
+use crate::controlplane::report::{ComponentReport, ComponentStatus, ProjectVerificationReport};
+use crate::models::policy::PolicyRef;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::policy_repository::PolicyRepository;
+use crate::storage::project_registry::ProjectRegistry;
+use crate::verification::policy_verifier::PolicyVerifier;
+
+pub const ADMISSION_API_VERSION: &str = "admission.k8s.io/v1";
+
+/// Errors that keep the webhook from answering a review at all.
+#[derive(Error, Debug)]
+pub enum AdmissionError {
+    #[error("AdmissionReview has no request")]
+    MissingRequest,
+    #[error("Could not serialize the verification report: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionReview {
+    pub api_version: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request: Option<AdmissionRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<AdmissionResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupVersionKind {
+    #[serde(default)]
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+}
+
+/// The parts of an admission request the webhook reads; other fields are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionRequest {
+    pub uid: String,
+    pub kind: GroupVersionKind,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub operation: String,
+    #[serde(default)]
+    pub object: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdmissionStatus {
+    pub code: u16,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionResponse {
+    pub uid: String,
+    pub allowed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<AdmissionStatus>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub audit_annotations: BTreeMap<String, String>,
+}
+
+/// A container image reference, e.g. `registry.example/app:1.2@sha256:...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    pub repository: String,
+    pub tag: Option<String>,
+    /// The `(algorithm, hex)` digest the image is pinned to, if any.
+    pub digest: Option<(String, String)>,
+}
+
+impl ImageRef {
+    pub fn parse(image: &str) -> Self {
+        let (name, digest) = match image.split_once('@') {
+            Some((name, digest)) => (name, digest.split_once(':').map(|(alg, hex)| (alg.to_string(), hex.to_lowercase()))),
+            None => (image, None),
+        };
+
+        // A colon after the last slash separates the tag; an earlier one belongs to a registry port
+        let (repository, tag) = match name.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => (repository, Some(tag.to_string())),
+            _ => (name, None),
+        };
+
+        Self {
+            repository: repository.to_string(),
+            tag,
+            digest,
+        }
+    }
+}
+
+/// Finds the pod spec of the workload kinds that run containers.
+pub fn pod_spec<'a>(kind: &str, object: &'a Value) -> Option<&'a Value> {
+    match kind {
+        "Pod" => object.get("spec"),
+        "Deployment" | "ReplicaSet" | "StatefulSet" | "DaemonSet" | "Job" => object.pointer("/spec/template/spec"),
+        "CronJob" => object.pointer("/spec/jobTemplate/spec/template/spec"),
+        _ => None,
+    }
+}
+
+/// Lists the distinct images of a pod spec's init, regular and ephemeral containers.
+pub fn images(pod_spec: &Value) -> Vec<String> {
+    let mut images: Vec<String> = Vec::new();
+    for field in ["initContainers", "containers", "ephemeralContainers"] {
+        for container in pod_spec[field].as_array().into_iter().flatten() {
+            if let Some(image) = container["image"].as_str() {
+                if !images.iter().any(|i| i == image) {
+                    images.push(image.to_string());
+                }
+            }
+        }
+    }
+    images
+}
+
+/// Answers Kubernetes `ValidatingAdmissionWebhook` reviews by verifying every image a
+/// workload would run.
+///
+/// Images must be pinned by `sha256` digest. Each digest is mapped to the registered
+/// component pinned to it, whose policy then applies. Digests no project knows about
+/// are verified against the default policy, or denied when there is none.
+pub struct AdmissionWebhook<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier, R: ProjectRegistry> {
+    control_plane: Arc<ControlPlane<P, A, V, R>>,
+    default_policy: Option<PolicyRef>,
+}
+
+impl<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier, R: ProjectRegistry> AdmissionWebhook<P, A, V, R> {
+    pub fn new(control_plane: Arc<ControlPlane<P, A, V, R>>) -> Self {
+        Self {
+            control_plane,
+            default_policy: None,
+        }
+    }
+
+    pub fn with_default_policy(mut self, policy: PolicyRef) -> Self {
+        self.default_policy = Some(policy);
+        self
+    }
+
+    /// Reviews a request and returns the response review. Verification errors deny the
+    /// request rather than failing the call, so the cluster always gets an answer.
+    pub async fn review(&self, review: AdmissionReview) -> Result<AdmissionReview, AdmissionError> {
+        let request = review.request.ok_or(AdmissionError::MissingRequest)?;
+
+        let response = match self.verify_request(&request).await {
+            Ok(None) => AdmissionResponse {
+                uid: request.uid.clone(),
+                allowed: true,
+                status: None,
+                audit_annotations: BTreeMap::new(),
+            },
+            Ok(Some(report)) => {
+                let message = report
+                    .components
+                    .iter()
+                    .map(|c| match &c.status {
+                        ComponentStatus::Passed => format!("{}: passed", c.component),
+                        ComponentStatus::Failed { reason } => format!("{}: {}", c.component, reason),
+                        ComponentStatus::Error { message } => format!("{}: {}", c.component, message),
+                        ComponentStatus::Skipped => format!("{}: skipped", c.component),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; ");
+
+                AdmissionResponse {
+                    uid: request.uid.clone(),
+                    allowed: report.passed,
+                    status: Some(AdmissionStatus {
+                        code: if report.passed { 200 } else { 403 },
+                        message,
+                    }),
+                    audit_annotations: BTreeMap::from([("sisyphus/report".to_string(), serde_json::to_string(&report)?)]),
+                }
+            }
+            Err(e) => AdmissionResponse {
+                uid: request.uid.clone(),
+                allowed: false,
+                status: Some(AdmissionStatus {
+                    code: 500,
+                    message: format!("Verification failed: {}", e),
+                }),
+                audit_annotations: BTreeMap::new(),
+            },
+        };
+
+        Ok(AdmissionReview {
+            api_version: ADMISSION_API_VERSION.to_string(),
+            kind: "AdmissionReview".to_string(),
+            request: None,
+            response: Some(response),
+        })
+    }
+
+    /// Verifies the images of the request's workload. Requests without a workload,
+    /// such as deletions or kinds that run no containers, need no verification.
+    async fn verify_request(&self, request: &AdmissionRequest) -> Result<Option<ProjectVerificationReport>, ControlPlaneError> {
+        let spec = match request.object.as_ref().and_then(|object| pod_spec(&request.kind.kind, object)) {
+            Some(spec) => spec,
+            None => return Ok(None),
+        };
+
+        let started_at = Utc::now();
+        let start = Instant::now();
+        let projects = self.control_plane.registry().list_projects().await?;
+
+        let mut components = Vec::new();
+        for image in images(spec) {
+            let image_ref = ImageRef::parse(&image);
+            let sha256 = match &image_ref.digest {
+                Some((algorithm, hex)) if algorithm == "sha256" => hex.clone(),
+                _ => {
+                    components.push(unverified(&image, "image is not pinned by sha256 digest"));
+                    continue;
+                }
+            };
+
+            let registered = projects
+                .iter()
+                .flat_map(|p| p.components.iter())
+                .find(|c| c.digests.get("sha256") == Some(&sha256))
+                .cloned();
+
+            let component = match (registered, &self.default_policy) {
+                (Some(component), _) => component,
+                (None, Some(policy)) => Component {
+                    name: image_ref.repository.clone(),
+                    version: image_ref.tag.clone().unwrap_or_else(|| sha256.clone()),
+                    policy: policy.clone(),
+                    digests: BTreeMap::from([("sha256".to_string(), sha256)]),
+                    selection: None,
+                },
+                (None, None) => {
+                    components.push(unverified(&image, "no registered component is pinned to this digest"));
+                    continue;
+                }
+            };
+
+            let mut report = self.control_plane.verify_component(&component).await?;
+            report.component = image;
+            components.push(report);
+        }
+
+        let workload = format!(
+            "{}/{}/{}",
+            request.namespace.as_deref().unwrap_or_default(),
+            request.kind.kind,
+            request.name.as_deref().unwrap_or_default()
+        );
+
+        Ok(Some(ProjectVerificationReport {
+            project: workload,
+            passed: components.iter().all(|c| c.status.is_passed()),
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            components,
+        }))
+    }
+}
+
+fn unverified(image: &str, reason: &str) -> ComponentReport {
+    let image_ref = ImageRef::parse(image);
+    ComponentReport {
+        component: image.to_string(),
+        version: image_ref.tag.unwrap_or_default(),
+        status: ComponentStatus::Failed { reason: reason.to_string() },
+        policy: None,
+        selection: None,
+        decided_by: Vec::new(),
+        verifications: Vec::new(),
+        started_at: Utc::now(),
+        duration_ms: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::controlplane::controlplane::SDLCProject;
+    use crate::models::attestation::Attestation;
+    use crate::models::policy::{Policy, PolicyRules};
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+    use crate::storage::policy_repository::InMemoryPolicyRepository;
+    use crate::storage::project_registry::InMemoryProjectRegistry;
+    use crate::verification::policy_verifier::SimplePolicyVerifier;
+
+    type TestWebhook = AdmissionWebhook<InMemoryPolicyRepository, InMemoryAttestationStorage, SimplePolicyVerifier, InMemoryProjectRegistry>;
+
+    fn image_attestation(id: &str, digest: &str, critical: u32) -> Arc<Attestation> {
+        Arc::new(Attestation {
+            id: id.to_string(),
+            issuer: "build-server".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": id, "digest": { "sha256": digest } }],
+                "vulnerabilities": { "critical": critical, "high": 0, "medium": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        })
+    }
+
+    async fn review(webhook: &TestWebhook, fixture: &str) -> AdmissionResponse {
+        let review: AdmissionReview = serde_json::from_str(fixture).unwrap();
+        webhook.review(review).await.unwrap().response.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admission_reviews_from_fixtures() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let control_plane = Arc::new(ControlPlane::new(policy_repo.clone(), attestation_storage.clone(), Arc::new(SimplePolicyVerifier)));
+
+        for purl in ["pkg:oci/shop", "pkg:oci/tools"] {
+            policy_repo.add_policy(Policy::new(
+                purl.to_string(),
+                "1.0.0".to_string(),
+                PolicyRules {
+                    allowed_issuers: vec!["build-server".to_string()].into_iter().collect(),
+                    max_age_days: 30,
+                    max_critical_vulnerabilities: 0,
+                    max_high_medium_vulnerabilities: 5,
+                },
+            ).unwrap()).await.unwrap();
+        }
+
+        let (frontend, api, report) = ("1".repeat(64), "2".repeat(64), "3".repeat(64));
+        let component = |name: &str, version: &str, digest: &str| Component {
+            name: name.to_string(),
+            version: version.to_string(),
+            policy: PolicyRef::latest("pkg:oci/shop"),
+            digests: BTreeMap::from([("sha256".to_string(), digest.to_string())]),
+            selection: None,
+        };
+        control_plane.add_project(SDLCProject {
+            name: "shop".to_string(),
+            components: vec![component("frontend", "1.2.3", &frontend), component("api", "2.0.1", &api)],
+        }).await.unwrap();
+
+        attestation_storage.store_attestation(image_attestation("frontend", &frontend, 0)).await.unwrap();
+        attestation_storage.store_attestation(image_attestation("api", &api, 2)).await.unwrap();
+        attestation_storage.store_attestation(image_attestation("report", &report, 0)).await.unwrap();
+
+        let webhook = AdmissionWebhook::new(control_plane).with_default_policy(PolicyRef::latest("pkg:oci/tools"));
+
+        // The pinned frontend passes but the tag-only init container is denied
+        let pod = review(&webhook, include_str!("../../examples/admission/pod.json")).await;
+        assert_eq!(pod.uid, "705ab4f5-6393-11e8-b7cc-42010a800002");
+        assert!(!pod.allowed);
+        let message = pod.status.unwrap().message;
+        assert!(message.contains("migrate:1.4: image is not pinned by sha256 digest"));
+        assert!(message.contains("frontend:1.2.3@sha256:1111111111111111111111111111111111111111111111111111111111111111: passed"));
+
+        let deployment = review(&webhook, include_str!("../../examples/admission/deployment.json")).await;
+        assert!(!deployment.allowed);
+        assert!(deployment.status.unwrap().message.contains("max_critical_vulnerabilities"));
+        let audited: ProjectVerificationReport = serde_json::from_str(&deployment.audit_annotations["sisyphus/report"]).unwrap();
+        assert_eq!(audited.project, "shop/Deployment/frontend");
+        assert_eq!(audited.failed_components().count(), 1);
+
+        // An image no project pins falls back to the default policy
+        let job = review(&webhook, include_str!("../../examples/admission/job.json")).await;
+        assert!(job.allowed);
+        assert_eq!(ImageRef::parse("localhost:5000/tools/report:7").tag.as_deref(), Some("7"));
+        assert_eq!(ImageRef::parse("localhost:5000/tools/report").tag, None);
+    }
+}
@@ -0,0 +1,41 @@
+//! Git post-commit hook that signs a source attestation for the new commit and submits
+//! it to the attestation store. Install it as `.git/hooks/post-commit` and configure it
+//! through the `sisyphus.*` git config keys described on `SigningConfig`.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use sisyphus::git::commit_attestation::{CommitAttestor, CommitFacts, DevEnvironment};
+use sisyphus::git::config::SigningConfig;
+use sisyphus::storage::attestation_storage::FileAttestationStorage;
+
+async fn run() -> Result<String, Box<dyn Error + Send + Sync>> {
+    let config = SigningConfig::load(".").await?;
+
+    let mut attestor = CommitAttestor::from_key_file(&config.signing_key).await?;
+    if let Some(issuer) = config.issuer {
+        attestor = attestor.with_issuer(issuer);
+    }
+
+    let facts = CommitFacts::collect(".", "HEAD").await?;
+    let mut storage = FileAttestationStorage::open(&config.attestations).await?;
+    if let Some(base_uri) = config.base_uri {
+        storage = storage.with_base_uri(base_uri);
+    }
+    attestor.submit(&storage, &facts, &DevEnvironment::current()).await
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(uri) => {
+            println!("sisyphus: attested commit as {}", uri);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            // The commit already exists; the hook can only report that it is unattested
+            eprintln!("sisyphus: commit not attested: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Git pre-receive hook that rejects pushes containing commits without a valid source
+//! attestation. Install it as `hooks/pre-receive` and configure it through the
+//! `sisyphus.*` git config keys described on `HookConfig`.
+
+use std::error::Error;
+use std::process::ExitCode;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+use sisyphus::controlplane::controlplane::ControlPlane;
+use sisyphus::git::config::HookConfig;
+use sisyphus::git::pre_receive::{GitRevList, PushVerifier, RefUpdate};
+use sisyphus::models::dsse::Keyring;
+use sisyphus::storage::attestation_storage::FileAttestationStorage;
+use sisyphus::storage::policy_repository::FilePolicyRepository;
+use sisyphus::verification::dsse_verifier::DssePolicyVerifier;
+use sisyphus::verification::policy_verifier::SourcePolicyVerifier;
+
+async fn run() -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let mut input = String::new();
+    tokio::io::stdin().read_to_string(&mut input).await?;
+    let updates = RefUpdate::parse_lines(&input)?;
+
+    // Git runs the hook from the repository directory
+    let config = HookConfig::load(".").await?;
+    let keyring = Keyring::from_file(&config.keyring).await?;
+    if keyring.is_empty() {
+        return Err(format!("No trusted keys in {}", config.keyring.display()).into());
+    }
+
+    let control_plane = Arc::new(ControlPlane::new(
+        Arc::new(FilePolicyRepository::open(&config.policies).await?),
+        Arc::new(FileAttestationStorage::open(&config.attestations).await?),
+        Arc::new(DssePolicyVerifier::new(keyring, SourcePolicyVerifier)),
+    ));
+
+    let verifier = PushVerifier::new(control_plane, config.policy);
+    let report = match verifier.verify_push(&updates, &GitRevList::new(".")).await {
+        Ok(report) => report,
+        Err(e) if e.is_retryable() => return Err(format!("{} (this may be temporary; try the push again)", e).into()),
+        Err(e) => return Err(e.into()),
+    };
+
+    for rejection in report.rejections() {
+        eprintln!("sisyphus: rejected {}", rejection);
+    }
+    Ok(report.accepted)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            // Fail closed: a push that cannot be verified is not accepted
+            eprintln!("sisyphus: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! The `sisyphus` command line tool. See `sisyphus --help` for the commands and
+//! `sisyphus::cli::ExitStatus` for the exit codes.
+
+use clap::Parser;
+use std::process::ExitCode;
+
+use sisyphus::cli::{self, Cli};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli::run(&cli).await {
+        Ok(outcome) => {
+            println!("{}", outcome.render(cli.output));
+            ExitCode::from(outcome.status.code())
+        }
+        Err(e) => {
+            match cli.output {
+                cli::OutputFormat::Human => eprintln!("{}", cli::render_error(&e, cli.output)),
+                cli::OutputFormat::Json => println!("{}", cli::render_error(&e, cli.output)),
+            }
+            ExitCode::from(e.exit_status().code())
+        }
+    }
+}
@@ -1,14 +1,48 @@
-use std::error::Error;
 use std::sync::Arc;
+use thiserror::Error;
 use tokio::sync::mpsc;
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use tracing::{debug, error, info, info_span, Instrument};
+use crate::metrics;
 use crate::models::events::{CDEvent, CDEventType, EventSubject, SubjectType};
-use crate::models::policy::Policy;
+use crate::models::policy::{Policy, PolicyError};
 use crate::models::attestation::Attestation;
+use crate::models::canonical::canonical_sha256;
+use crate::models::summary_scai::SummaryScaiPredicateAttributesItemAttribute;
 use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
 use crate::storage::policy_repository::PolicyRepository;
-use crate::verification::policy_verifier::PolicyVerifier;
+use crate::verification::policy_verifier::{PolicyVerifier, VerificationError};
+
+/// Errors from handling a CD event.
+#[derive(Error, Debug)]
+pub enum EventError {
+    #[error("Attestation {attestation_id} has no subject name")]
+    MissingSubject { attestation_id: String },
+    #[error("No attestation for subject {0} names its digest")]
+    MissingSubjectDigest(String),
+    #[error("No pending attestations for subject {0}")]
+    NoPendingAttestations(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error(transparent)]
+    Verification(#[from] VerificationError),
+}
+
+impl EventError {
+    /// Whether handling the event again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            EventError::Storage(e) => e.is_retryable(),
+            EventError::Policy(e) => e.is_retryable(),
+            EventError::Verification(e) => e.is_retryable(),
+            EventError::MissingSubject { .. } | EventError::MissingSubjectDigest(_) | EventError::NoPendingAttestations(_) => false,
+        }
+    }
+}
 
 pub struct CBPManager<P, A>
 where
@@ -44,13 +78,15 @@ where
 
     pub async fn run(&mut self) {
         while let Some(event) = self.event_receiver.recv().await {
-            if let Err(e) = self.handle_event(event).await {
-                eprintln!("Error handling event: {}", e);
+            metrics::record_event_lag(event.timestamp);
+            let span = info_span!("event", id = %event.id, subject = %event.subject.id);
+            if let Err(e) = self.handle_event(event).instrument(span.clone()).await {
+                span.in_scope(|| error!(error = %e, retryable = e.is_retryable(), "Could not handle event"));
             }
         }
     }
 
-    async fn handle_event(&mut self, event: CDEvent) -> Result<(), Box<dyn Error + Send + Sync>> {
+    async fn handle_event(&mut self, event: CDEvent) -> Result<(), EventError> {
         match event.event_type {
             CDEventType::AttestationCreated { attestation_id, attestation_uri } => {
                 self.handle_attestation_created(attestation_id, attestation_uri).await?;
@@ -62,8 +98,8 @@ where
         Ok(())
     }
 
-    async fn handle_attestation_created(&mut self, attestation_id: String, attestation_uri: String) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let attestation = self.attestation_storage.get_attestation(&attestation_uri).await?;
+    async fn handle_attestation_created(&mut self, _attestation_id: String, attestation_uri: String) -> Result<(), EventError> {
+        let attestation = metrics::timed("attestation", "get", self.attestation_storage.get_attestation(&attestation_uri)).await?;
         let subject = self.get_subject_from_attestation(&attestation)?;
 
         self.pending_attestations
@@ -80,38 +116,57 @@ where
         Ok(())
     }
 
-    async fn is_subject_complete(&self, subject: &str) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    async fn is_subject_complete(&self, subject: &str) -> Result<bool, EventError> {
         // This method should check if all required attestations for the subject are present
         // For now, we'll assume that if we have at least one attestation, it's complete
-        Ok(self.pending_attestations.get(subject).map_or(false, |atts| !atts.is_empty()))
+        Ok(self.pending_attestations.get(subject).is_some_and(|atts| !atts.is_empty()))
     }
 
-    async fn generate_summary_attestation(&self, subject: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let attestation_uris = self.pending_attestations.get(subject).ok_or("No pending attestations found")?;
+    async fn generate_summary_attestation(&self, subject: &str) -> Result<(), EventError> {
+        let attestation_uris = self
+            .pending_attestations
+            .get(subject)
+            .ok_or_else(|| EventError::NoPendingAttestations(subject.to_string()))?;
         let mut attributes = Vec::new();
+        let mut subject_digest = None;
 
         for uri in attestation_uris {
-            let attestation = self.attestation_storage.get_attestation(uri).await?;
+            let attestation = metrics::timed("attestation", "get", self.attestation_storage.get_attestation(uri)).await?;
             let policies = self.get_relevant_policies(&attestation).await?;
+            let span = info_span!("attestation", id = %attestation.id, issuer = %attestation.issuer, %uri);
+            subject_digest = subject_digest.or_else(|| named_subject_digest(&attestation, subject));
 
             for policy in policies {
-                let is_valid = self.policy_verifier.verify_attestation(&attestation, &policy).await?;
-                let attribute = self.determine_attribute(&attestation, &policy, is_valid)?;
-                let evidence = self.create_evidence(&attestation)?;
+                let is_valid = self.policy_verifier.verify_attestation(&attestation, &policy).instrument(span.clone()).await?;
+                span.in_scope(|| debug!(policy = %policy.purl, is_valid, "Evaluated attestation"));
+                // A summary lists only attributes that hold, so one failing attestation
+                // means none is produced rather than one that leaves the failure out
+                let Some(attribute) = self.determine_attribute(&attestation, &policy, is_valid)? else {
+                    span.in_scope(|| info!(policy = %policy.purl, subject, "Attestation fails its policy, so no summary is produced"));
+                    return Ok(());
+                };
+                let evidence = self.create_evidence(uri, &attestation)?;
 
                 attributes.push(json!({
                     "attribute": attribute,
                     "evidence": evidence,
+                    "conditions": { "policy": format!("{}@{}", policy.purl, policy.version) },
                 }));
             }
         }
 
+        if attributes.is_empty() {
+            debug!(subject, "No policy applies to the attestations, so no summary is produced");
+            return Ok(());
+        }
+        let subject_digest = subject_digest.ok_or_else(|| EventError::MissingSubjectDigest(subject.to_string()))?;
+
         let summary_content = json!({
             "_type": "https://in-toto.io/Statement/v1",
             "subject": [
                 {
                     "name": subject,
-                    "digest": { "sha256": self.calculate_digest(subject) },
+                    "digest": subject_digest,
                 }
             ],
             "predicateType": "https://in-toto.io/attestation/scai/attribute-report/v0.2",
@@ -132,12 +187,14 @@ where
             issuer: "CBPManager".to_string(),
             timestamp: chrono::Utc::now(),
             content: summary_content,
+            envelope: None,
+            verification_material: None,
         };
 
-        let summary_uri = self.attestation_storage.store_attestation(Arc::new(summary_attestation.clone())).await?;
+        let summary_uri = metrics::timed("attestation", "store", self.attestation_storage.store_attestation(Arc::new(summary_attestation.clone()))).await?;
 
         // Create and emit a new CDEvent for the summary attestation
-        let summary_event = CDEvent::new(
+        let _summary_event = CDEvent::new(
             CDEventType::AttestationCreated {
                 attestation_id: summary_attestation.id.clone(),
                 attestation_uri: summary_uri,
@@ -154,41 +211,41 @@ where
         Ok(())
     }
 
-    fn get_subject_from_attestation(&self, attestation: &Attestation) -> Result<String, Box<dyn Error + Send + Sync>> {
+    fn get_subject_from_attestation(&self, attestation: &Attestation) -> Result<String, EventError> {
         // Extract the subject from the attestation
         // This is a placeholder implementation; adjust according to your attestation structure
         attestation.content["subject"][0]["name"]
             .as_str()
             .map(String::from)
-            .ok_or_else(|| "Unable to extract subject from attestation".into())
+            .ok_or_else(|| EventError::MissingSubject { attestation_id: attestation.id.clone() })
     }
 
-    async fn get_relevant_policies(&self, attestation: &Attestation) -> Result<Vec<Arc<Policy>>, Box<dyn Error + Send + Sync>> {
+    async fn get_relevant_policies(&self, attestation: &Attestation) -> Result<Vec<Arc<Policy>>, EventError> {
         // This method should return all policies that apply to the given attestation
         // For now, we'll just return a single policy based on the PURL
         let purl = attestation.content["purl"].as_str().unwrap_or("");
-        let policy = self.policy_repo.get_policy(purl, None).await?;
+        let policy = metrics::timed("policy", "get", self.policy_repo.get_policy(purl, None)).await?;
         Ok(vec![policy])
     }
 
-    fn determine_attribute(&self, attestation: &Attestation, policy: &Policy, is_valid: bool) -> Result<String, Box<dyn Error + Send + Sync>> {
+    /// The attribute a passing attestation supports, or `None` if it supports none. Summary
+    /// attributes are the SCAI `PASSED_<stage>` values `ScaiVerifier` accepts.
+    fn determine_attribute(&self, _attestation: &Attestation, _policy: &Policy, is_valid: bool) -> Result<Option<SummaryScaiPredicateAttributesItemAttribute>, EventError> {
         // This method should determine the appropriate attribute based on the attestation, policy, and validation result
-        // This is a placeholder implementation
-        if is_valid {
-            Ok(format!("VALID_{}", policy.purl.to_uppercase()))
-        } else {
-            Ok(format!("INVALID_{}", policy.purl.to_uppercase()))
-        }
+        // This is a placeholder implementation: the manager attests builds
+        Ok(is_valid.then_some(SummaryScaiPredicateAttributesItemAttribute::PassedBuild))
     }
 
-    fn create_evidence(&self, attestation: &Attestation) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    /// Pins the attestation by its storage URI and the digest of its canonical JSON, as
+    /// `ScaiVerifier` checks them.
+    fn create_evidence(&self, uri: &str, attestation: &Attestation) -> Result<Value, EventError> {
         let evidence = json!({
-            "name": format!("{}.jsonl", attestation.id),
-            "uri": format!("https://example.com/scai/{}.jsonl", attestation.id),
+            "name": attestation.id,
+            "uri": uri,
             "digest": {
-                "sha256": self.calculate_digest(&attestation.id),
+                "sha256": canonical_sha256(attestation).map_err(StorageError::from)?,
             },
-            "mediaType": "application/x.dsse+json"
+            "mediaType": "application/json"
         });
 
         Ok(evidence)
@@ -202,19 +259,31 @@ where
     }
 }
 
+/// The digest of the attestation's subject with the given name.
+fn named_subject_digest(attestation: &Attestation, name: &str) -> Option<serde_json::Map<String, Value>> {
+    attestation.content["subject"]
+        .as_array()?
+        .iter()
+        .find(|subject| subject["name"] == name)?["digest"]
+        .as_object()
+        .cloned()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::storage::attestation_storage::InMemoryAttestationStorage;
     use crate::storage::policy_repository::InMemoryPolicyRepository;
     use crate::models::policy::PolicyRules;
+    use crate::verification::policy_verifier::{SourcePolicyVerifier, VerificationResult};
+    use crate::verification::scai_verifier::{ScaiVerifier, StoredEvidence};
 
     struct MockPolicyVerifier;
 
     #[async_trait::async_trait]
     impl PolicyVerifier for MockPolicyVerifier {
-        async fn verify_attestation(&self, _attestation: &Attestation, _policy: &Policy) -> Result<bool, Box<dyn Error + Send + Sync>> {
-            Ok(true)
+        async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+            Ok(VerificationResult::new(attestation, policy, Vec::new()))
         }
     }
 
@@ -243,6 +312,7 @@ mod tests {
                 max_critical_vulnerabilities: 0,
                 max_high_medium_vulnerabilities: 5,
             },
+            selection: None,
         };
         policy_repo.add_policy(test_policy).await.unwrap();
 
@@ -260,6 +330,8 @@ mod tests {
                 ],
                 "purl": "test-purl"
             }),
+            envelope: None,
+            verification_material: None,
         };
 
         // Store the test attestation
@@ -317,7 +389,7 @@ mod tests {
         
         let attributes = content["predicate"]["attributes"].as_array().unwrap();
         assert_eq!(attributes.len(), 1, "Expected 1 attribute, found {}", attributes.len());
-        assert_eq!(attributes[0]["attribute"], "VALID_TEST-PURL");
+        assert_eq!(attributes[0]["attribute"], "PASSED_BUILD");
         
         assert_eq!(content["predicate"]["producer"]["uri"], "https://example.com/cbp/build");
         assert_eq!(content["predicate"]["producer"]["name"], "CBP Build Attestor");
@@ -325,4 +397,95 @@ mod tests {
         // Print the actual content for debugging
         println!("Summary attestation content: {}", serde_json::to_string_pretty(&content).unwrap());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_summary_evidence_verifies() {
+        let (_tx, rx) = mpsc::channel(1);
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let mut manager = CBPManager::new(Arc::new(MockPolicyVerifier), policy_repo.clone(), attestation_storage.clone(), rx);
+
+        policy_repo.add_policy(Policy {
+            purl: "pkg:github/acme/app".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules::new(["ci".to_string()].into(), 7, 0, 5),
+            selection: None,
+        }).await.unwrap();
+        let build = Attestation {
+            id: "build".to_string(),
+            issuer: "ci".to_string(),
+            timestamp: chrono::Utc::now(),
+            content: json!({
+                "subject": [{ "name": "app", "digest": { "sha256": "ab".repeat(32) } }],
+                "predicateType": "https://slsa.dev/provenance/v1",
+                "purl": "pkg:github/acme/app",
+            }),
+            ..Default::default()
+        };
+        let uri = attestation_storage.store_attestation(Arc::new(build)).await.unwrap();
+
+        manager.handle_event(CDEvent::new(
+            CDEventType::AttestationCreated { attestation_id: "build".to_string(), attestation_uri: uri.clone() },
+            EventSubject { id: "build".to_string(), subject_type: SubjectType::Attestation },
+        )).await.unwrap();
+
+        let (summary_uri, summary) = attestation_storage.list_attestation_entries().await.unwrap()
+            .into_iter()
+            .find(|(entry, _)| *entry != uri)
+            .unwrap();
+        assert_eq!(summary.content["predicate"]["attributes"][0]["evidence"]["uri"], uri);
+
+        let evidence = StoredEvidence::new(attestation_storage.clone(), policy_repo.clone());
+        let summary_policy = Policy::new(
+            "pkg:github/acme/summaries".to_string(),
+            "1.0.0".to_string(),
+            PolicyRules::new(["CBPManager".to_string()].into(), 7, 0, 0),
+        ).unwrap();
+        let report = ScaiVerifier::new(SourcePolicyVerifier).verify(&evidence, &summary_uri, &summary_policy).await.unwrap();
+        assert!(report.passed, "{:?}", report);
+    }
+
+    #[tokio::test]
+    async fn test_failing_attestation_yields_no_summary() {
+        let (_tx, rx) = mpsc::channel(1);
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let mut manager = CBPManager::new(Arc::new(SourcePolicyVerifier), policy_repo.clone(), attestation_storage.clone(), rx);
+
+        policy_repo.add_policy(Policy::new(
+            "pkg:github/acme/app".to_string(),
+            "1.0.0".to_string(),
+            PolicyRules::new(["ci".to_string()].into(), 7, 0, 0),
+        ).unwrap()).await.unwrap();
+        let build = |id: &str, issuer: &str| Attestation {
+            id: id.to_string(),
+            issuer: issuer.to_string(),
+            timestamp: chrono::Utc::now(),
+            content: json!({
+                // The subject the summary is about is not listed first
+                "subject": [
+                    { "name": "lib", "digest": { "sha256": "cd".repeat(32) } },
+                    { "name": "app", "digest": { "sha256": "ab".repeat(32) } }
+                ],
+                "predicateType": "https://slsa.dev/provenance/v1",
+                "purl": "pkg:github/acme/app",
+            }),
+            ..Default::default()
+        };
+        let passing = attestation_storage.store_attestation(Arc::new(build("ci-build", "ci"))).await.unwrap();
+        let failing = attestation_storage.store_attestation(Arc::new(build("other-build", "mallory"))).await.unwrap();
+
+        manager.pending_attestations.insert("app".to_string(), vec![passing.clone(), failing]);
+        manager.generate_summary_attestation("app").await.unwrap();
+        assert_eq!(attestation_storage.list_attestations().await.unwrap().len(), 2, "a partial summary was stored");
+
+        manager.pending_attestations.insert("app".to_string(), vec![passing]);
+        manager.generate_summary_attestation("app").await.unwrap();
+        let summaries: Vec<_> = attestation_storage.list_attestations().await.unwrap()
+            .into_iter()
+            .filter(|att| att.issuer == "CBPManager")
+            .collect();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].content["subject"][0]["digest"]["sha256"], "ab".repeat(32));
+    }
+}
@@ -1 +1 @@
-mod manager;
\ No newline at end of file
+pub mod manager;
\ No newline at end of file
@@ -0,0 +1,1056 @@
+//! The `sisyphus` command line tool: manages policies, attestations and projects in a
+//! file-backed store and verifies them, so that CI pipelines can gate on the outcome
+//! without a running server.
+//!
+//! Every command prints either a short human-readable summary or JSON, and finishes
+//! with one of the `ExitStatus` codes.
+
+use async_trait::async_trait;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::AsyncReadExt;
+use url::Url;
+
+use crate::controlplane::controlplane::{Component, ControlPlane, ControlPlaneError, SDLCProject};
+use crate::controlplane::report::{ComponentReport, ComponentStatus, ProjectVerificationReport, VerificationOptions};
+use crate::controlplane::ingest::{AttestationIngestor, IngestError, IngestReport};
+use crate::controlplane::summary::summary_statement;
+use crate::models::attestation::Attestation;
+use crate::models::dsse::Keyring;
+use crate::models::policy::{Policy, PolicyError, PolicyRef};
+use crate::models::retention::Deletion;
+use crate::models::summary_scai::SummaryScaiPredicateAttributesItemAttribute;
+use crate::storage::attestation_storage::{AttestationStorage, FileAttestationStorage};
+use crate::storage::error::StorageError;
+use crate::storage::migration::{export_attestations, AttestationMigration, PolicyMigration};
+use crate::storage::policy_repository::{FilePolicyRepository, PolicyRepository};
+use crate::storage::project_registry::{FileProjectRegistry, ProjectRegistry};
+use crate::verification::offline::{BundleError, OfflineVerifier, VerificationBundle};
+use crate::verification::scai_verifier::{EvidenceCheck, EvidenceError, ScaiVerifier, StoredEvidence, DEFAULT_MAX_DEPTH};
+use crate::verification::policy_verifier::{
+    PolicyVerifier, SimplePolicyVerifier, SourcePolicyVerifier, VerificationError, VerificationResult,
+};
+use crate::verification::sigstore_verifier::TrustRoot;
+
+#[derive(Parser, Debug)]
+#[command(name = "sisyphus", version, about = "Manage and verify software supply chain attestations")]
+pub struct Cli {
+    /// Directory holding policies.json, attestations.json and projects.json.
+    #[arg(long, global = true, env = "SISYPHUS_STORE", default_value = ".sisyphus")]
+    pub store: PathBuf,
+    /// Base URI that stored attestations are addressed under.
+    #[arg(long, global = true, env = "SISYPHUS_BASE_URI")]
+    pub base_uri: Option<Url>,
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+    /// How attestations are evaluated against policies.
+    #[arg(long, global = true, value_enum, default_value_t = VerifierKind::Simple)]
+    pub verifier: VerifierKind,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VerifierKind {
+    /// Issuer, age and vulnerability thresholds.
+    Simple,
+    /// Issuer and age only, for source attestations without vulnerability data.
+    Source,
+}
+
+#[async_trait]
+impl PolicyVerifier for VerifierKind {
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        match self {
+            VerifierKind::Simple => SimplePolicyVerifier.evaluate_attestation(attestation, policy).await,
+            VerifierKind::Source => SourcePolicyVerifier.evaluate_attestation(attestation, policy).await,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Manage policies.
+    Policy {
+        #[command(subcommand)]
+        command: PolicyCommand,
+    },
+    /// Manage attestations.
+    Attest {
+        #[command(subcommand)]
+        command: AttestCommand,
+    },
+    /// Manage projects.
+    Project {
+        #[command(subcommand)]
+        command: ProjectCommand,
+    },
+    /// Verify projects, components or single attestations against their policies.
+    Verify {
+        #[command(subcommand)]
+        command: VerifyCommand,
+    },
+    /// Generate SCAI summary attestations.
+    Summary {
+        #[command(subcommand)]
+        command: SummaryCommand,
+    },
+    /// Export and check verification bundles for environments without store access.
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommand,
+    },
+    /// Copy every attestation and policy into another store, verifying each copy.
+    Migrate {
+        /// Directory of the store to copy into.
+        to: PathBuf,
+        /// Base URI the copies are addressed under; the target's default otherwise.
+        #[arg(long)]
+        to_base_uri: Option<Url>,
+        /// Fail attestations whose copy is stored under a different URI.
+        #[arg(long, conflicts_with = "to_base_uri")]
+        preserve_uris: bool,
+        /// Record progress here, and resume from it if an earlier migration was interrupted.
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+    },
+}
+
+/// Files may be given as `-` to read from standard input.
+#[derive(Subcommand, Debug)]
+pub enum PolicyCommand {
+    /// Validate a policy file and store it.
+    Add { file: PathBuf },
+    /// List every version of a policy.
+    List { purl: String },
+    /// Show a policy, by default its newest version.
+    Get {
+        purl: String,
+        /// A semver requirement, such as `^1.2`, or an exact version.
+        #[arg(long)]
+        version: Option<String>,
+    },
+    /// Delete a policy version.
+    Delete { purl: String, version: String },
+    /// Check a policy file without storing it.
+    Validate { file: PathBuf },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AttestCommand {
+    /// Store an attestation file and print its URI.
+    Store { file: PathBuf },
+    /// Store every attestation, DSSE envelope or Sigstore bundle in a JSON Lines file.
+    Ingest {
+        file: PathBuf,
+        /// Keyring of trusted keys to verify bare DSSE envelopes against, one hex Ed25519
+        /// public key per line, optionally followed by the issuer it signs for.
+        #[arg(long)]
+        keyring: Option<PathBuf>,
+        /// When bare DSSE envelopes were produced, for statements that do not record it.
+        #[arg(long)]
+        timestamp: Option<chrono::DateTime<chrono::Utc>>,
+        /// Sigstore trust root to verify bundles against.
+        #[arg(long)]
+        trust_root: Option<PathBuf>,
+        /// Skip the lines an interrupted ingest already covered.
+        #[arg(long, default_value_t = 0)]
+        resume_after: u64,
+    },
+    /// Show an attestation.
+    Get { uri: String },
+    /// Print every attestation as JSON Lines, in the form `attest ingest` reads.
+    Export {
+        /// Write the attestations to this file rather than printing them.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// List stored attestations.
+    List(AttestationFilter),
+    /// Delete an attestation, recording who deleted it and why where the store keeps tombstones.
+    Delete {
+        uri: String,
+        #[arg(long, requires = "reason")]
+        actor: Option<String>,
+        #[arg(long, requires = "actor")]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Args, Debug, Default)]
+pub struct AttestationFilter {
+    #[arg(long)]
+    pub issuer: Option<String>,
+    /// Only attestations with a statement subject of this name.
+    #[arg(long)]
+    pub subject: Option<String>,
+    #[arg(long)]
+    pub predicate_type: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProjectCommand {
+    /// Validate a project file and register it.
+    Add { file: PathBuf },
+    /// Show a project and its components.
+    Show { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum VerifyCommand {
+    /// Verify every component of a registered project.
+    Project {
+        name: String,
+        /// Stop at the first component that does not pass.
+        #[arg(long)]
+        fail_fast: bool,
+        #[arg(long, default_value_t = VerificationOptions::default().max_concurrency)]
+        max_concurrency: usize,
+    },
+    /// Verify a component described in a file.
+    Component { file: PathBuf },
+    /// Check a SCAI summary against a policy and follow its evidence, including nested
+    /// summaries.
+    Summary {
+        uri: String,
+        /// The policy the summary itself, its issuer and age, must satisfy.
+        #[arg(long)]
+        policy: String,
+        /// A semver requirement for the policy version; the newest version by default.
+        #[arg(long)]
+        version: Option<String>,
+        #[arg(long, default_value_t = DEFAULT_MAX_DEPTH)]
+        max_depth: usize,
+    },
+    /// Evaluate a single attestation against a policy.
+    Attestation {
+        uri: String,
+        #[arg(long)]
+        policy: String,
+        /// A semver requirement for the policy version; the newest version by default.
+        #[arg(long)]
+        version: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SummaryCommand {
+    /// Verify a project and, if it passes, print a SCAI attribute report for it.
+    Generate {
+        project: String,
+        /// The stage the project passed.
+        #[arg(long, value_enum)]
+        attribute: Stage,
+        /// Identifies the producer of the summary.
+        #[arg(long, default_value = "https://github.com/sisyphus/sisyphus")]
+        producer: String,
+        /// Also store the summary as an attestation from this issuer.
+        #[arg(long, value_name = "ISSUER")]
+        save: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BundleCommand {
+    /// Write a summary, its evidence and policies into a self-contained bundle.
+    Export {
+        summary_uri: String,
+        /// A Sigstore trusted_root.json to include for signed attestations.
+        #[arg(long)]
+        trust_root: Option<PathBuf>,
+        /// Write the bundle to this file rather than printing it.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Re-check a bundle without access to the store.
+    Verify {
+        file: PathBuf,
+        /// A policy file the summary itself, its issuer and age, must satisfy.
+        #[arg(long)]
+        summary_policy: PathBuf,
+        /// A policy file the evidence may name; repeat for each. Bundled policies must
+        /// match one of these.
+        #[arg(long = "policy")]
+        policies: Vec<PathBuf>,
+        /// The Sigstore trusted_root.json to verify signatures with. A bundled trust root
+        /// must match it.
+        #[arg(long)]
+        trust_root: Option<PathBuf>,
+        /// Accept a summary without a Sigstore signature, trusting the issuer it claims.
+        #[arg(long)]
+        allow_unsigned: bool,
+        /// Also require the summary to be about this artifact, given as `sha256:<hex>`.
+        #[arg(long)]
+        digest: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Stage {
+    DevelopmentEnvironment,
+    Source,
+    Build,
+    Package,
+    Deploy,
+}
+
+impl From<Stage> for SummaryScaiPredicateAttributesItemAttribute {
+    fn from(stage: Stage) -> Self {
+        match stage {
+            Stage::DevelopmentEnvironment => Self::PassedDevelopmentEnvironment,
+            Stage::Source => Self::PassedSource,
+            Stage::Build => Self::PassedBuild,
+            Stage::Package => Self::PassedPackage,
+            Stage::Deploy => Self::PassedDeploy,
+        }
+    }
+}
+
+/// Process exit codes. Clap exits with `Usage` on its own for malformed arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// The command succeeded and everything verified passed.
+    Success = 0,
+    /// Verification ran and denied at least one component or attestation.
+    Denied = 1,
+    /// The arguments or input files are invalid.
+    Usage = 2,
+    /// A policy, attestation or project does not exist.
+    NotFound = 3,
+    /// The store could not be read or written; retrying may succeed.
+    Unavailable = 4,
+    /// Anything else, including verifications that could not reach a verdict.
+    Error = 5,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Could not read {path}: {source}")]
+    Read { path: String, source: std::io::Error },
+    #[error("{path} is not valid: {source}")]
+    Parse { path: String, source: serde_json::Error },
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error(transparent)]
+    Verification(#[from] VerificationError),
+    #[error(transparent)]
+    Evidence(#[from] EvidenceError),
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+    #[error(transparent)]
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl CliError {
+    pub fn exit_status(&self) -> ExitStatus {
+        match self {
+            CliError::Invalid(_) | CliError::Read { .. } | CliError::Parse { .. } => ExitStatus::Usage,
+            CliError::Storage(e) => storage_exit_status(e),
+            CliError::Policy(e) => policy_exit_status(e),
+            CliError::Verification(e) => verification_exit_status(e),
+            CliError::Evidence(e) => evidence_exit_status(e),
+            CliError::Bundle(BundleError::Storage(e)) => storage_exit_status(e),
+            CliError::Bundle(BundleError::Policy(e)) => policy_exit_status(e),
+            CliError::Bundle(BundleError::Evidence(e)) => evidence_exit_status(e),
+            CliError::Bundle(BundleError::UnsupportedMediaType(_) | BundleError::MissingEvidenceUri { .. }) => ExitStatus::Usage,
+            // A bundle whose summary signature, trust root or policies do not hold is denied outright
+            CliError::Bundle(
+                BundleError::SummarySignature(_)
+                | BundleError::SummaryTampered(_)
+                | BundleError::MissingTrustRoot
+                | BundleError::UnsignedSummary(_)
+                | BundleError::UntrustedTrustRoot
+                | BundleError::UntrustedPolicy(_),
+            ) => ExitStatus::Denied,
+            CliError::Other(_) => ExitStatus::Error,
+        }
+    }
+}
+
+fn policy_exit_status(error: &PolicyError) -> ExitStatus {
+    match error {
+        PolicyError::Storage(e) => storage_exit_status(e),
+        PolicyError::Invalid(_) => ExitStatus::Usage,
+        _ => ExitStatus::NotFound,
+    }
+}
+
+fn verification_exit_status(error: &VerificationError) -> ExitStatus {
+    match error {
+        VerificationError::Storage(e) => storage_exit_status(e),
+        _ => ExitStatus::Error,
+    }
+}
+
+fn evidence_exit_status(error: &EvidenceError) -> ExitStatus {
+    match error {
+        EvidenceError::NotASummary(..) => ExitStatus::Usage,
+        EvidenceError::Storage(e) => storage_exit_status(e),
+        EvidenceError::Policy(e) => policy_exit_status(e),
+        EvidenceError::Verification(e) => verification_exit_status(e),
+    }
+}
+
+fn storage_exit_status(error: &StorageError) -> ExitStatus {
+    match error {
+        StorageError::NotFound { .. } => ExitStatus::NotFound,
+        StorageError::AlreadyExists { .. } | StorageError::Conflict { .. } | StorageError::Invalid(_) => ExitStatus::Usage,
+        StorageError::Immutable { .. } => ExitStatus::Denied,
+        e if e.is_retryable() => ExitStatus::Unavailable,
+        _ => ExitStatus::Error,
+    }
+}
+
+impl From<ControlPlaneError> for CliError {
+    fn from(error: ControlPlaneError) -> Self {
+        match error {
+            ControlPlaneError::Storage(e) => CliError::Storage(e),
+            ControlPlaneError::Policy(e) => CliError::Policy(e),
+            ControlPlaneError::Verification(e) => CliError::Verification(e),
+            e => CliError::Other(Box::new(e)),
+        }
+    }
+}
+
+/// The result of a command, rendered in either output format.
+#[derive(Debug)]
+pub struct Outcome {
+    pub status: ExitStatus,
+    pub json: Value,
+    pub human: String,
+}
+
+impl Outcome {
+    fn new(json: Value, human: impl Into<String>) -> Self {
+        Self {
+            status: ExitStatus::Success,
+            json,
+            human: human.into(),
+        }
+    }
+
+    fn with_status(mut self, status: ExitStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Human => self.human.trim_end().to_string(),
+            OutputFormat::Json => serde_json::to_string_pretty(&self.json).unwrap_or_default(),
+        }
+    }
+}
+
+/// Renders an error the way the format expects: JSON output stays machine-readable.
+pub fn render_error(error: &CliError, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Human => format!("sisyphus: {}", error),
+        OutputFormat::Json => json!({ "error": error.to_string(), "exit_code": error.exit_status().code() }).to_string(),
+    }
+}
+
+type FileControlPlane = ControlPlane<FilePolicyRepository, FileAttestationStorage, VerifierKind, FileProjectRegistry>;
+
+async fn open(cli: &Cli) -> Result<FileControlPlane, CliError> {
+    tokio::fs::create_dir_all(&cli.store).await.map_err(StorageError::from)?;
+
+    let mut attestations = FileAttestationStorage::open(cli.store.join("attestations.json")).await?;
+    if let Some(base_uri) = &cli.base_uri {
+        attestations = attestations.with_base_uri(base_uri.clone());
+    }
+
+    Ok(ControlPlane::with_registry(
+        Arc::new(FileProjectRegistry::open(cli.store.join("projects.json")).await?),
+        Arc::new(FilePolicyRepository::open(cli.store.join("policies.json")).await?),
+        Arc::new(attestations),
+        Arc::new(cli.verifier),
+    ))
+}
+
+pub async fn run(cli: &Cli) -> Result<Outcome, CliError> {
+    // Commands that must work without a store
+    match &cli.command {
+        Command::Policy { command: PolicyCommand::Validate { file } } => {
+            let policy: Policy = read_json(file).await?;
+            policy.validate().map_err(CliError::Invalid)?;
+            return Ok(Outcome::new(to_json(&policy), format!("{}@{} is valid", policy.purl, policy.version)));
+        }
+        Command::Bundle {
+            command: BundleCommand::Verify {
+                file,
+                summary_policy,
+                policies,
+                trust_root,
+                allow_unsigned,
+                digest,
+            },
+        } => {
+            return verify_bundle(cli.verifier, file, summary_policy, policies, trust_root.as_deref(), *allow_unsigned, digest.as_deref()).await;
+        }
+        _ => {}
+    }
+
+    let control_plane = open(cli).await?;
+    match &cli.command {
+        Command::Policy { command } => policy(&control_plane, command).await,
+        Command::Attest { command } => attest(&control_plane, command).await,
+        Command::Project { command } => project(&control_plane, command).await,
+        Command::Verify { command } => verify(&control_plane, command).await,
+        Command::Summary { command } => summary(&control_plane, command).await,
+        Command::Bundle { command } => export_bundle(&control_plane, command).await,
+        Command::Migrate { to, to_base_uri, preserve_uris, checkpoint } => {
+            migrate(&control_plane, to, to_base_uri.as_ref(), *preserve_uris, checkpoint.as_deref()).await
+        }
+    }
+}
+
+async fn policy(control_plane: &FileControlPlane, command: &PolicyCommand) -> Result<Outcome, CliError> {
+    let repo = control_plane.policy_repo();
+    match command {
+        PolicyCommand::Add { file } => {
+            let policy: Policy = read_json(file).await?;
+            policy.validate().map_err(CliError::Invalid)?;
+            repo.add_policy(policy.clone()).await?;
+            Ok(Outcome::new(to_json(&policy), format!("Added {}@{}", policy.purl, policy.version)))
+        }
+        PolicyCommand::List { purl } => {
+            let policies = repo.list_policies(purl).await?;
+            let human = policies.iter().map(|p| format!("{}@{}\n", p.purl, p.version)).collect::<String>();
+            let policies: Vec<&Policy> = policies.iter().map(Arc::as_ref).collect();
+            Ok(Outcome::new(to_json(&policies), human))
+        }
+        PolicyCommand::Get { purl, version } => {
+            let policy_ref = match version {
+                Some(req) => PolicyRef::matching(purl, req).map_err(CliError::Invalid)?,
+                None => PolicyRef::latest(purl),
+            };
+            let policy = repo.resolve_policy(&policy_ref).await?;
+            Ok(Outcome::new(to_json(&*policy), pretty(&*policy)))
+        }
+        PolicyCommand::Delete { purl, version } => {
+            repo.delete_policy(purl, version).await?;
+            Ok(Outcome::new(json!({ "deleted": format!("{}@{}", purl, version) }), format!("Deleted {}@{}", purl, version)))
+        }
+        PolicyCommand::Validate { .. } => unreachable!("validated without opening the store"),
+    }
+}
+
+async fn attest(control_plane: &FileControlPlane, command: &AttestCommand) -> Result<Outcome, CliError> {
+    let storage = control_plane.attestation_storage();
+    match command {
+        AttestCommand::Store { file } => {
+            let attestation: Attestation = read_json(file).await?;
+            let uri = storage.store_attestation(Arc::new(attestation)).await?;
+            Ok(Outcome::new(json!({ "uri": uri }), uri))
+        }
+        AttestCommand::Ingest { file, keyring, timestamp, trust_root, resume_after } => {
+            let mut ingestor = AttestationIngestor::new(storage.clone()).resume_after(*resume_after);
+            if let Some(path) = keyring {
+                let keyring = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|source| CliError::Read { path: path.display().to_string(), source })?;
+                ingestor = ingestor.with_keyring(Keyring::parse(&keyring).map_err(CliError::Invalid)?);
+            }
+            if let Some(timestamp) = timestamp {
+                ingestor = ingestor.with_timestamp(*timestamp);
+            }
+            if let Some(path) = trust_root {
+                ingestor = ingestor.with_trust_root(read_json(path).await?);
+            }
+
+            let display = file.display().to_string();
+            let progress = |report: &IngestReport| eprintln!("{} lines, {} stored, {} failed", report.lines, report.stored, report.failed);
+            let report = if display == "-" {
+                ingestor.ingest(tokio::io::stdin(), progress).await
+            } else {
+                let input = tokio::fs::File::open(file).await.map_err(|source| CliError::Read { path: display.clone(), source })?;
+                ingestor.ingest(input, progress).await
+            }
+            .map_err(|IngestError::Read { source, .. }| CliError::Read { path: display, source })?;
+
+            let mut human = format!("Stored {} attestations from {} lines, {} failed\n", report.stored, report.lines, report.failed);
+            for error in &report.errors {
+                let _ = writeln!(human, "  line {}: {}", error.line, error.reason);
+            }
+            let status = if report.failed > 0 { ExitStatus::Error } else { ExitStatus::Success };
+            Ok(Outcome::new(to_json(&report), human).with_status(status))
+        }
+        AttestCommand::Get { uri } => {
+            let attestation = storage.get_attestation(uri).await?;
+            Ok(Outcome::new(to_json(&*attestation), pretty(&*attestation)))
+        }
+        AttestCommand::Export { out } => match out {
+            Some(path) => {
+                let file = tokio::fs::File::create(path).await.map_err(StorageError::from)?;
+                let count = export_attestations(storage.as_ref(), file).await?;
+                Ok(Outcome::new(json!({ "path": path, "attestations": count }), format!("Exported {} attestations into {}", count, path.display())))
+            }
+            None => {
+                let attestations: Vec<Value> = storage.list_attestations().await?.iter().map(|att| to_json(&**att)).collect();
+                let human = attestations.iter().map(|att| format!("{}\n", att)).collect::<String>();
+                Ok(Outcome::new(Value::Array(attestations), human))
+            }
+        },
+        AttestCommand::List(filter) => {
+            let entries: Vec<Value> = storage
+                .list_attestation_entries()
+                .await?
+                .into_iter()
+                .filter(|(_, att)| filter.matches(att))
+                .map(|(uri, att)| json!({
+                    "uri": uri,
+                    "id": att.id,
+                    "issuer": att.issuer,
+                    "timestamp": att.timestamp,
+                    "predicateType": att.content["predicateType"],
+                }))
+                .collect();
+            let mut human = String::new();
+            for entry in &entries {
+                let _ = writeln!(human, "{}  {}  {}", entry["uri"].as_str().unwrap_or_default(), entry["issuer"].as_str().unwrap_or_default(), entry["predicateType"].as_str().unwrap_or("-"));
+            }
+            Ok(Outcome::new(Value::Array(entries), human))
+        }
+        AttestCommand::Delete { uri, actor, reason } => {
+            match (actor, reason) {
+                (Some(actor), Some(reason)) => {
+                    let deletion = Deletion::new(actor.as_str(), reason.as_str()).map_err(CliError::Invalid)?;
+                    storage.delete_attestation_by(uri, &deletion).await?
+                }
+                _ => storage.delete_attestation(uri).await?,
+            }
+            Ok(Outcome::new(json!({ "deleted": uri }), format!("Deleted {}", uri)))
+        }
+    }
+}
+
+impl AttestationFilter {
+    fn matches(&self, attestation: &Attestation) -> bool {
+        let subject_matches = |name: &String| {
+            attestation.content["subject"]
+                .as_array()
+                .is_some_and(|subjects| subjects.iter().any(|s| s["name"].as_str() == Some(name)))
+        };
+
+        self.issuer.as_ref().is_none_or(|issuer| &attestation.issuer == issuer)
+            && self.subject.as_ref().is_none_or(subject_matches)
+            && self.predicate_type.as_ref().is_none_or(|t| attestation.content["predicateType"].as_str() == Some(t))
+    }
+}
+
+async fn project(control_plane: &FileControlPlane, command: &ProjectCommand) -> Result<Outcome, CliError> {
+    match command {
+        ProjectCommand::Add { file } => {
+            let project: SDLCProject = read_json(file).await?;
+            control_plane.add_project(project.clone()).await?;
+            Ok(Outcome::new(to_json(&project), format!("Added project {} with {} components", project.name, project.components.len())))
+        }
+        ProjectCommand::Show { name } => {
+            let project = control_plane.registry().get_project(name).await?;
+            let mut human = format!("{}\n", project.name);
+            for component in &project.components {
+                let _ = writeln!(human, "  {} {}  {}", component.name, component.version, component.policy);
+            }
+            Ok(Outcome::new(to_json(&*project), human))
+        }
+    }
+}
+
+async fn verify(control_plane: &FileControlPlane, command: &VerifyCommand) -> Result<Outcome, CliError> {
+    match command {
+        VerifyCommand::Project { name, fail_fast, max_concurrency } => {
+            let options = VerificationOptions {
+                max_concurrency: *max_concurrency,
+                fail_fast: *fail_fast,
+                ..Default::default()
+            };
+            let report = control_plane.verify_project_with_options(name, &options).await?;
+            Ok(Outcome::new(to_json(&report), project_summary(&report)).with_status(report_status(&report.components)))
+        }
+        VerifyCommand::Component { file } => {
+            let component: Component = read_json(file).await?;
+            component.validate().map_err(CliError::Invalid)?;
+            let report = control_plane.verify_component(&component).await?;
+            let human = component_line(&report);
+            let status = report_status(std::slice::from_ref(&report));
+            Ok(Outcome::new(to_json(&report), human).with_status(status))
+        }
+        VerifyCommand::Summary { uri, policy, version, max_depth } => {
+            let policy_ref = match version {
+                Some(req) => PolicyRef::matching(policy, req).map_err(CliError::Invalid)?,
+                None => PolicyRef::latest(policy),
+            };
+            let summary_policy = control_plane.policy_repo().resolve_policy(&policy_ref).await?;
+            let source = StoredEvidence::new(control_plane.attestation_storage().clone(), control_plane.policy_repo().clone());
+            let report = ScaiVerifier::new(control_plane.policy_verifier().as_ref())
+                .with_max_depth(*max_depth)
+                .verify(&source, uri, &summary_policy)
+                .await?;
+
+            let mut human = format!("{}: summary {}\n", if report.passed { "PASSED" } else { "FAILED" }, report.summary);
+            for failure in &report.failures {
+                let _ = writeln!(human, "  FAIL  {}", failure);
+            }
+            evidence_lines(&report.evidence, 1, &mut human);
+            let status = if report.passed { ExitStatus::Success } else { ExitStatus::Denied };
+            Ok(Outcome::new(to_json(&report), human).with_status(status))
+        }
+        VerifyCommand::Attestation { uri, policy, version } => {
+            let policy_ref = match version {
+                Some(req) => PolicyRef::matching(policy, req).map_err(CliError::Invalid)?,
+                None => PolicyRef::latest(policy),
+            };
+            let policy = control_plane.policy_repo().resolve_policy(&policy_ref).await?;
+            let attestation = control_plane.attestation_storage().get_attestation(uri).await?;
+            let result = control_plane.policy_verifier().evaluate_attestation(&attestation, &policy).await?;
+
+            let mut human = format!("{} {} against {}@{}\n", if result.is_valid() { "PASS" } else { "FAIL" }, attestation.id, policy.purl, policy.version);
+            for outcome in &result.outcomes {
+                let _ = writeln!(human, "  {} {}: {}", if outcome.passed { "ok  " } else { "fail" }, outcome.rule, outcome.reason);
+            }
+            let status = if result.is_valid() { ExitStatus::Success } else { ExitStatus::Denied };
+            Ok(Outcome::new(to_json(&result), human).with_status(status))
+        }
+    }
+}
+
+async fn summary(control_plane: &FileControlPlane, command: &SummaryCommand) -> Result<Outcome, CliError> {
+    let SummaryCommand::Generate { project, attribute, producer, save } = command;
+
+    let report = control_plane.verify_project(project).await?;
+    if !report.passed {
+        let human = format!("No summary generated\n{}", project_summary(&report));
+        return Ok(Outcome::new(to_json(&report), human).with_status(report_status(&report.components)));
+    }
+
+    let sdlc_project = control_plane.registry().get_project(project).await?;
+    let attestations = control_plane.attestation_storage().list_attestation_entries().await?;
+    let statement = summary_statement(&sdlc_project, &report, &attestations, (*attribute).into(), producer)
+        .map_err(|e| CliError::Other(e.into()))?;
+
+    if let Some(issuer) = save {
+        let attestation = Attestation {
+            id: uuid::Uuid::new_v4().to_string(),
+            issuer: issuer.clone(),
+            timestamp: chrono::Utc::now(),
+            content: statement.clone(),
+            ..Default::default()
+        };
+        let uri = control_plane.attestation_storage().store_attestation(Arc::new(attestation)).await?;
+        eprintln!("sisyphus: stored summary as {}", uri);
+    }
+
+    Ok(Outcome::new(statement.clone(), pretty(&statement)))
+}
+
+async fn export_bundle(control_plane: &FileControlPlane, command: &BundleCommand) -> Result<Outcome, CliError> {
+    let BundleCommand::Export { summary_uri, trust_root, out } = command else {
+        unreachable!("verified without opening the store");
+    };
+
+    let trust_root = match trust_root {
+        Some(path) => Some(read_json::<TrustRoot>(path).await?),
+        None => None,
+    };
+    let bundle = VerificationBundle::export(
+        control_plane.attestation_storage().as_ref(),
+        control_plane.policy_repo().as_ref(),
+        summary_uri,
+        trust_root,
+    )
+    .await?;
+
+    let json = to_json(&bundle);
+    let human = format!("Bundled {} evidence attestations and {} policies", bundle.evidence.len(), bundle.policies.len());
+    match out {
+        Some(path) => {
+            tokio::fs::write(path, pretty(&bundle)).await.map_err(StorageError::from)?;
+            Ok(Outcome::new(json!({ "path": path, "evidence": bundle.evidence.len(), "policies": bundle.policies.len() }), format!("{} into {}", human, path.display())))
+        }
+        None => Ok(Outcome::new(json.clone(), pretty(&json))),
+    }
+}
+
+async fn migrate(
+    control_plane: &FileControlPlane,
+    to: &Path,
+    to_base_uri: Option<&Url>,
+    preserve_uris: bool,
+    checkpoint: Option<&Path>,
+) -> Result<Outcome, CliError> {
+    tokio::fs::create_dir_all(to).await.map_err(StorageError::from)?;
+    let mut attestations = FileAttestationStorage::open(to.join("attestations.json")).await?;
+    if let Some(base_uri) = to_base_uri {
+        attestations = attestations.with_base_uri(base_uri.clone());
+    }
+    let policies = FilePolicyRepository::open(to.join("policies.json")).await?;
+
+    let mut migration = AttestationMigration::new(control_plane.attestation_storage().as_ref(), &attestations);
+    if preserve_uris {
+        migration = migration.preserving_uris();
+    }
+    if let Some(path) = checkpoint {
+        migration = migration.with_checkpoint(path);
+    }
+    let attestation_report = migration.run().await?;
+    let policy_report = PolicyMigration::new(control_plane.policy_repo().as_ref(), &policies).run().await?;
+
+    let mut human = format!(
+        "Attestations: {} of {} copied ({} resumed, {} under the same URI), {} failed\n",
+        attestation_report.uri_map.len(),
+        attestation_report.source_count,
+        attestation_report.resumed,
+        attestation_report.preserved(),
+        attestation_report.failed.len(),
+    );
+    for (uri, failure) in &attestation_report.failed {
+        let _ = writeln!(human, "  {}: {}", uri, failure.reason);
+    }
+    for uri in &attestation_report.missing_in_target {
+        let _ = writeln!(human, "  {}: missing from the target", uri);
+    }
+    let _ = writeln!(
+        human,
+        "Policies: {} copied, {} already present, {} failed",
+        policy_report.copied.len(),
+        policy_report.already_present.len(),
+        policy_report.failed.len(),
+    );
+    for (key, failure) in &policy_report.failed {
+        let _ = writeln!(human, "  {}: {}", key, failure.reason);
+    }
+
+    let reconciled = attestation_report.is_reconciled() && policy_report.is_reconciled();
+    let status = if reconciled { ExitStatus::Success } else { ExitStatus::Error };
+    let json = json!({ "attestations": attestation_report, "policies": policy_report });
+    Ok(Outcome::new(json, human).with_status(status))
+}
+
+async fn verify_bundle(
+    verifier: VerifierKind,
+    file: &Path,
+    summary_policy: &Path,
+    policy_files: &[PathBuf],
+    trust_root: Option<&Path>,
+    allow_unsigned: bool,
+    digest: Option<&str>,
+) -> Result<Outcome, CliError> {
+    let bundle: VerificationBundle = read_json(file).await?;
+    let summary_policy: Policy = read_json(summary_policy).await?;
+    summary_policy.validate().map_err(CliError::Invalid)?;
+    let mut policies = Vec::with_capacity(policy_files.len());
+    for path in policy_files {
+        let policy: Policy = read_json(path).await?;
+        policy.validate().map_err(CliError::Invalid)?;
+        policies.push(policy);
+    }
+
+    let mut offline = OfflineVerifier::new(verifier, summary_policy, policies);
+    if let Some(path) = trust_root {
+        offline = offline.with_trust_root(read_json(path).await?);
+    }
+    if allow_unsigned {
+        offline = offline.allow_unsigned_summaries();
+    }
+    let report = offline.verify(&bundle).await?;
+
+    let covered = match digest {
+        Some(digest) => {
+            let (algorithm, value) = digest
+                .split_once(':')
+                .ok_or_else(|| CliError::Invalid(format!("Digest {} is not of the form <algorithm>:<hex>", digest)))?;
+            report.covers(algorithm, value)
+        }
+        None => true,
+    };
+    let passed = report.passed && covered;
+
+    let mut human = format!(
+        "{}: summary from {}{}\n",
+        if passed { "PASSED" } else { "FAILED" },
+        report.summary_issuer,
+        if report.summary_signed { " (signed)" } else { "" },
+    );
+    for failure in &report.failures {
+        let _ = writeln!(human, "  FAIL  {}", failure);
+    }
+    evidence_lines(&report.evidence, 1, &mut human);
+    if !covered {
+        let _ = writeln!(human, "  FAIL  the summary is not about {}", digest.unwrap_or_default());
+    }
+
+    let mut json = to_json(&report);
+    if digest.is_some() {
+        json["digest_matched"] = json!(covered);
+    }
+    let status = if passed { ExitStatus::Success } else { ExitStatus::Denied };
+    Ok(Outcome::new(json, human).with_status(status))
+}
+
+fn evidence_lines(checks: &[EvidenceCheck], depth: usize, human: &mut String) {
+    let indent = "  ".repeat(depth);
+    for check in checks {
+        let uri = check.uri.as_deref().unwrap_or("-");
+        if check.failures.is_empty() {
+            let _ = writeln!(human, "{}PASS  {} {}", indent, check.attribute, uri);
+        } else {
+            let _ = writeln!(human, "{}FAIL  {} {}: {}", indent, check.attribute, uri, check.failures.join("; "));
+        }
+        evidence_lines(&check.nested, depth + 1, human);
+    }
+}
+
+/// Denied if any component failed, an error if any could not be checked.
+fn report_status(components: &[ComponentReport]) -> ExitStatus {
+    if components.iter().any(|c| matches!(c.status, ComponentStatus::Failed { .. })) {
+        ExitStatus::Denied
+    } else if components.iter().all(|c| c.status.is_passed()) {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Error
+    }
+}
+
+fn project_summary(report: &ProjectVerificationReport) -> String {
+    let mut human = format!(
+        "{}: {} ({} components, {} ms)\n",
+        report.project,
+        if report.passed { "PASSED" } else { "FAILED" },
+        report.components.len(),
+        report.duration_ms,
+    );
+    for component in &report.components {
+        let _ = writeln!(human, "  {}", component_line(component));
+    }
+    human
+}
+
+fn component_line(report: &ComponentReport) -> String {
+    let name = format!("{} {}", report.component, report.version);
+    match &report.status {
+        ComponentStatus::Passed => format!("PASS  {} ({})", name, report.policy.as_deref().unwrap_or("-")),
+        ComponentStatus::Failed { reason } => format!("FAIL  {}: {}", name, reason),
+        ComponentStatus::Error { message } => format!("ERROR {}: {}", name, message),
+        ComponentStatus::Skipped => format!("SKIP  {}", name),
+    }
+}
+
+async fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, CliError> {
+    let display = path.display().to_string();
+    let bytes = if display == "-" {
+        let mut bytes = Vec::new();
+        tokio::io::stdin().read_to_end(&mut bytes).await.map(|_| bytes)
+    } else {
+        tokio::fs::read(path).await
+    }
+    .map_err(|source| CliError::Read { path: display.clone(), source })?;
+
+    serde_json::from_slice(&bytes).map_err(|source| CliError::Parse { path: display, source })
+}
+
+fn to_json(value: &impl Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn pretty(value: &impl Serialize) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    async fn sisyphus(store: &Path, args: &[&str]) -> Result<Outcome, CliError> {
+        let mut argv = vec!["sisyphus", "--store", store.to_str().unwrap()];
+        argv.extend_from_slice(args);
+        run(&Cli::try_parse_from(argv).unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_cli_exit_statuses() {
+        Cli::command().debug_assert();
+
+        let dir = std::env::temp_dir().join(format!("sisyphus-cli-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let file = |name: &str, value: Value| {
+            let path = dir.join(name);
+            std::fs::write(&path, value.to_string()).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+
+        let policy = file("policy.json", json!({
+            "purl": "pkg:github/acme/frontend",
+            "version": "1.0.0",
+            "rules": {
+                "allowed_issuers": ["trusted_issuer"],
+                "max_age_days": 30,
+                "max_critical_vulnerabilities": 0,
+                "max_high_medium_vulnerabilities": 5,
+            },
+        }));
+        let project = file("project.json", json!({
+            "name": "ACMEAppX",
+            "components": [{ "name": "frontend", "version": "1.2.3", "policy": { "purl": "pkg:github/acme/frontend" } }],
+        }));
+        let attestation = file("attestation.json", json!({
+            "id": "att1",
+            "issuer": "trusted_issuer",
+            "timestamp": chrono::Utc::now(),
+            "content": {
+                "subject": [{ "name": "frontend", "version": "1.2.3", "digest": { "sha256": "ab".repeat(32) } }],
+                "vulnerabilities": { "critical": 1 },
+            },
+        }));
+
+        let store = dir.join("store");
+        assert_eq!(sisyphus(&store, &["policy", "add", &policy]).await.unwrap().status, ExitStatus::Success);
+        assert_eq!(sisyphus(&store, &["project", "add", &project]).await.unwrap().status, ExitStatus::Success);
+
+        let error = sisyphus(&store, &["project", "show", "missing"]).await.unwrap_err();
+        assert_eq!(error.exit_status(), ExitStatus::NotFound);
+        let error = sisyphus(&store, &["project", "add", &project]).await.unwrap_err();
+        assert_eq!(error.exit_status(), ExitStatus::Usage);
+
+        let stored = sisyphus(&store, &["attest", "store", &attestation]).await.unwrap();
+        let uri = stored.json["uri"].as_str().unwrap().to_string();
+        let listed = sisyphus(&store, &["attest", "list", "--issuer", "trusted_issuer"]).await.unwrap();
+        assert_eq!(listed.json[0]["uri"], uri.as_str());
+
+        // The attestation reports a critical vulnerability, so the project is denied
+        let verified = sisyphus(&store, &["verify", "project", "ACMEAppX"]).await.unwrap();
+        assert_eq!(verified.status, ExitStatus::Denied);
+        assert_eq!(verified.json["passed"], false);
+        let verified = sisyphus(&store, &["--verifier", "source", "verify", "attestation", &uri, "--policy", "pkg:github/acme/frontend"]).await.unwrap();
+        assert_eq!(verified.status, ExitStatus::Success);
+
+        let summary = sisyphus(&store, &["--verifier", "source", "summary", "generate", "ACMEAppX", "--attribute", "source"]).await.unwrap();
+        assert_eq!(summary.json["predicate"]["attributes"][0]["attribute"], "PASSED_SOURCE");
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
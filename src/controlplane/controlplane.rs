@@ -1,348 +1,1066 @@
-use std::collections::HashMap;
-use chrono::{Duration, Utc};
-use serde_json::json;
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use std::time::Instant;
+use thiserror::Error;
+use tracing::{debug, info, info_span, warn, Instrument};
 
+use crate::controlplane::report::{ComponentReport, ComponentStatus, ProjectVerificationReport, VerificationOptions};
+use crate::metrics::{self, Metrics, POLICY_RULE_FAILURES, VERIFICATION_FAILURES};
 use crate::models::attestation::Attestation;
-use crate::models::policy::{Policy, PolicyRules};
-use crate::storage::policy_repository::{InMemoryPolicyRepository, PolicyRepository};
-use crate::storage::attestation_storage::{AttestationStorage, InMemoryAttestationStorage};
-use crate::verification::policy_verifier::{PolicyVerifier, SimplePolicyVerifier};
+use crate::models::audit::{AuditRecord, AuditSubject, AuditedAttestation};
+use crate::models::policy::{Policy, PolicyError, PolicyRule, SelectionStrategy};
+use crate::storage::policy_repository::PolicyRepository;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::audit_log::AuditLog;
+use crate::storage::error::StorageError;
+use crate::storage::project_registry::{InMemoryProjectRegistry, ProjectRegistry};
+use crate::verification::policy_verifier::{PolicyVerifier, VerificationError, VerificationResult};
 use std::sync::Arc;
 
-pub struct Component {
-    pub name: String,
-    pub version: String,
-    pub policy: Arc<Policy>,
+pub use crate::models::project::{Component, SDLCProject, SubjectMatch};
+
+#[derive(Error, Debug)]
+pub enum ControlPlaneError {
+    #[error("Digest mismatch for component {component} in attestation {attestation_id}: {reason}")]
+    DigestMismatch {
+        component: String,
+        attestation_id: String,
+        reason: String,
+    },
+    #[error("No matching attestation found for component {component}")]
+    NoMatchingAttestation {
+        component: String,
+    },
+    #[error("No attestation for component {component} is from an allowed issuer and within the maximum age")]
+    NoValidAttestation {
+        component: String,
+    },
+    #[error("Component {component} failed policy rules in {attestations}: {rules}")]
+    PolicyViolation {
+        component: String,
+        attestations: String,
+        rules: String,
+    },
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error(transparent)]
+    Verification(#[from] VerificationError),
 }
 
-pub struct SDLCProject {
-    pub name: String,
-    pub components: Vec<Component>,
+impl ControlPlaneError {
+    /// A short, stable name for the error, used as a metric label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ControlPlaneError::DigestMismatch { .. } => "digest_mismatch",
+            ControlPlaneError::NoMatchingAttestation { .. } => "no_matching_attestation",
+            ControlPlaneError::NoValidAttestation { .. } => "no_valid_attestation",
+            ControlPlaneError::PolicyViolation { .. } => "policy_violation",
+            ControlPlaneError::Storage(_) => "storage",
+            ControlPlaneError::Policy(_) => "policy",
+            ControlPlaneError::Verification(_) => "verification",
+        }
+    }
+
+    /// Whether verifying again may reach a verdict, as opposed to one that denies.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ControlPlaneError::Storage(e) => e.is_retryable(),
+            ControlPlaneError::Policy(e) => e.is_retryable(),
+            ControlPlaneError::Verification(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
 }
 
-pub struct ControlPlane<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier> {
-    projects: HashMap<String, SDLCProject>,
+/// Counts the failure and turns it into the component's status.
+fn failed(error: ControlPlaneError) -> ComponentStatus {
+    Metrics::global().increment(VERIFICATION_FAILURES, &[("reason", error.kind())]);
+    ComponentStatus::Failed { reason: error.to_string() }
+}
+
+pub struct ControlPlane<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier, R: ProjectRegistry = InMemoryProjectRegistry> {
+    registry: Arc<R>,
     policy_repo: Arc<P>,
     attestation_storage: Arc<A>,
     policy_verifier: Arc<V>,
+    audit_log: Option<Arc<dyn AuditLog>>,
 }
 
-impl<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier> ControlPlane<P, A, V> {
+impl<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier> ControlPlane<P, A, V, InMemoryProjectRegistry> {
     pub fn new(policy_repo: Arc<P>, attestation_storage: Arc<A>, policy_verifier: Arc<V>) -> Self {
+        Self::with_registry(Arc::new(InMemoryProjectRegistry::new()), policy_repo, attestation_storage, policy_verifier)
+    }
+}
+
+impl<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier, R: ProjectRegistry> ControlPlane<P, A, V, R> {
+    pub fn with_registry(registry: Arc<R>, policy_repo: Arc<P>, attestation_storage: Arc<A>, policy_verifier: Arc<V>) -> Self {
         Self {
-            projects: HashMap::new(),
+            registry,
             policy_repo,
             attestation_storage,
             policy_verifier,
+            audit_log: None,
         }
     }
 
-    pub async fn add_project(&mut self, project: SDLCProject) {
-        self.projects.insert(project.name.clone(), project);
+    /// Records every component verdict in the audit log. A verdict that cannot be
+    /// recorded is reported as an error rather than a pass.
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
     }
 
-    pub async fn verify_project(&self, project_name: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let project = self.projects.get(project_name).ok_or("Project not found")?;
-        
-        for component in &project.components {
-            println!("Verifying component: {}", component.name);
-            let attestations = self.attestation_storage.list_attestations().await?;
-            println!("Total attestations: {}", attestations.len());
-            
-            let matching_attestation = attestations.iter().find(|att| {
-                let matches = att.content["subject"].as_array()
-                    .and_then(|subjects| subjects.first())
-                    .and_then(|subject| {
-                        let name_match = subject["name"].as_str() == Some(&component.name);
-                        let version_match = subject["version"].as_str() == Some(&component.version);
-                        if name_match && version_match {
-                            Some(())
-                        } else {
-                            None
-                        }
-                    })
-                    .is_some();
-                println!("Attestation {} matches component: {}", att.id, matches);
-                matches
-            });
-
-            if let Some(attestation) = matching_attestation {
-                println!("Found matching attestation: {}", attestation.id);
-                let is_valid = self.policy_verifier.verify_attestation(attestation, &component.policy).await?;
-                println!("Attestation verification result: {}", is_valid);
-                if !is_valid {
-                    println!("Component {} failed verification", component.name);
-                    return Ok(false);
-                }
-            } else {
-                println!("No matching attestation found for component {}", component.name);
-                return Ok(false);
+    pub fn registry(&self) -> &Arc<R> {
+        &self.registry
+    }
+
+    pub fn policy_repo(&self) -> &Arc<P> {
+        &self.policy_repo
+    }
+
+    pub fn attestation_storage(&self) -> &Arc<A> {
+        &self.attestation_storage
+    }
+
+    pub fn policy_verifier(&self) -> &Arc<V> {
+        &self.policy_verifier
+    }
+
+    pub fn audit_log(&self) -> Option<&Arc<dyn AuditLog>> {
+        self.audit_log.as_ref()
+    }
+
+    pub async fn add_project(&self, project: SDLCProject) -> Result<(), StorageError> {
+        self.registry.create_project(project).await
+    }
+
+    pub async fn verify_project(&self, project_name: &str) -> Result<ProjectVerificationReport, ControlPlaneError> {
+        self.verify_project_with_options(project_name, &VerificationOptions::default()).await
+    }
+
+    /// Verifies every component of a project, up to `max_concurrency` at a time, and
+    /// reports each component's outcome. With `fail_fast` the remaining components are
+    /// skipped as soon as one does not pass.
+    #[tracing::instrument(skip(self, options), fields(project = project_name))]
+    pub async fn verify_project_with_options(&self, project_name: &str, options: &VerificationOptions) -> Result<ProjectVerificationReport, ControlPlaneError> {
+        let project = metrics::timed("project", "get", self.registry.get_project(project_name)).await?;
+        let started_at = Utc::now();
+        let start = Instant::now();
+
+        let attestations = metrics::timed("attestation", "list", self.attestation_storage.list_attestation_entries()).await?;
+        debug!(attestations = attestations.len(), "Loaded attestations");
+
+        let attestations = &attestations;
+        let project_name = project.name.as_str();
+        let requested_by = options.requested_by.as_deref();
+        // Collected up front: a lazy iterator adaptor here keeps the future from being provably Send
+        let checks: Vec<_> = project.components.iter().enumerate().map(|(i, component)| async move {
+            (i, self.check_component(Some(project_name), requested_by, component, attestations).await)
+        }).collect();
+        let mut checks = stream::iter(checks).buffer_unordered(options.max_concurrency.max(1));
+
+        let mut reports: Vec<Option<ComponentReport>> = vec![None; project.components.len()];
+        while let Some((i, report)) = checks.next().await {
+            let passed = report.status.is_passed();
+            reports[i] = Some(report);
+            if !passed && options.fail_fast {
+                info!("Stopping verification after the first failure");
+                break;
             }
         }
+        drop(checks);
+
+        let components: Vec<ComponentReport> = reports
+            .into_iter()
+            .zip(&project.components)
+            .map(|(report, component)| report.unwrap_or_else(|| ComponentReport::new(component, started_at)))
+            .collect();
+
+        let passed = components.iter().all(|c| c.status.is_passed());
+        let duration_ms = start.elapsed().as_millis() as u64;
+        info!(passed, duration_ms, "Verified project");
 
-        println!("All components verified successfully");
-        Ok(true)
+        Ok(ProjectVerificationReport {
+            project: project.name.clone(),
+            passed,
+            started_at,
+            duration_ms,
+            components,
+        })
     }
-}
 
-#[tokio::test]
-async fn test_acme_app_x_project() {
-    // Initialize repositories and verifier
-    let policy_repo = Arc::new(InMemoryPolicyRepository::new());
-    let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
-    let policy_verifier = Arc::new(SimplePolicyVerifier);
-
-    // Create a control plane
-    let mut control_plane = ControlPlane::new(
-        policy_repo.clone(),
-        attestation_storage.clone(),
-        policy_verifier,
-    );
-
-    // Create policies for components
-    let frontend_policy = Policy {
-        purl: "pkg:github/acme/frontend".to_string(),
-        version: "1.0.0".to_string(),
-        rules: PolicyRules {
-            allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
-            max_age_days: 30,
-            max_critical_vulnerabilities: 0,
-            max_high_medium_vulnerabilities: 5,
-        },
-    };
-    let backend_policy = Policy {
-        purl: "pkg:github/acme/backend".to_string(),
-        version: "1.0.0".to_string(),
-        rules: PolicyRules {
-            allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
-            max_age_days: 30,
-            max_critical_vulnerabilities: 0,
-            max_high_medium_vulnerabilities: 3,
-        },
-    };
-
-    // Add policies to the repository
-    policy_repo.add_policy(frontend_policy.clone()).await.unwrap();
-    policy_repo.add_policy(backend_policy.clone()).await.unwrap();
-
-    // Create ACMEAppX project
-    let acme_app_x = SDLCProject {
-        name: "ACMEAppX".to_string(),
-        components: vec![
-            Component {
-                name: "frontend".to_string(),
-                version: "1.2.3".to_string(),
-                policy: Arc::new(frontend_policy),
-            },
-            Component {
-                name: "backend".to_string(),
-                version: "2.3.4".to_string(),
-                policy: Arc::new(backend_policy),
-            },
-        ],
-    };
-
-    // Add project to the control plane
-    control_plane.add_project(acme_app_x).await;
-
-    // Create valid attestations for components
-    let frontend_attestation = Attestation {
-        id: "frontend-att".to_string(),
-        issuer: "trusted_issuer".to_string(),
-        timestamp: Utc::now(),
-        content: json!({
-            "subject": [
-                {
-                    "name": "frontend",
-                    "version": "1.2.3"
+    pub async fn verify_component(&self, component: &Component) -> Result<ComponentReport, ControlPlaneError> {
+        let attestations = metrics::timed("attestation", "list", self.attestation_storage.list_attestation_entries()).await?;
+        Ok(self.check_component(None, None, component, &attestations).await)
+    }
+
+    /// Verifies a component on behalf of an authenticated principal, who is recorded with
+    /// the verdict in the audit log.
+    pub async fn verify_component_as(&self, component: &Component, requested_by: &str) -> Result<ComponentReport, ControlPlaneError> {
+        let attestations = metrics::timed("attestation", "list", self.attestation_storage.list_attestation_entries()).await?;
+        Ok(self.check_component(None, Some(requested_by), component, &attestations).await)
+    }
+
+    #[tracing::instrument(skip_all, fields(project = project.unwrap_or_default(), component = %component.name, version = %component.version))]
+    async fn check_component(&self, project: Option<&str>, requested_by: Option<&str>, component: &Component, attestations: &[(String, Arc<Attestation>)]) -> ComponentReport {
+        let mut report = ComponentReport::new(component, Utc::now());
+        let start = Instant::now();
+
+        report.status = match self.evaluate_component(component, attestations, &mut report).await {
+            Ok(status) => status,
+            Err(e) => ComponentStatus::Error { message: e.to_string() },
+        };
+        report.duration_ms = start.elapsed().as_millis() as u64;
+
+        if let Some(audit_log) = &self.audit_log {
+            let mut record = audit_record(project, component, attestations, &report);
+            record.requested_by = requested_by.map(String::from);
+            if let Err(e) = metrics::timed("audit", "append", audit_log.append(record)).await {
+                warn!(error = %e, "Could not record the verdict in the audit log");
+                report.status = ComponentStatus::Error {
+                    message: format!("Could not record the verdict in the audit log: {}", e),
+                };
+            }
+        }
+
+        metrics::record_verification(&report.status, start.elapsed().as_secs_f64());
+        match &report.status {
+            ComponentStatus::Passed => info!(duration_ms = report.duration_ms, "Component passed"),
+            status => warn!(?status, duration_ms = report.duration_ms, "Component did not pass"),
+        }
+        report
+    }
+
+    async fn evaluate_component(&self, component: &Component, attestations: &[(String, Arc<Attestation>)], report: &mut ComponentReport) -> Result<ComponentStatus, ControlPlaneError> {
+        let mut candidates = Vec::new();
+        for (_, att) in attestations {
+            let subject_match = component.match_subjects(&att.content);
+            debug!(attestation = %att.id, ?subject_match, "Matched attestation subjects");
+            match subject_match {
+                SubjectMatch::Matched => candidates.push(att),
+                SubjectMatch::DigestMismatch(reason) => {
+                    report.decided_by = vec![att.id.clone()];
+                    return Ok(failed(ControlPlaneError::DigestMismatch {
+                        component: component.name.clone(),
+                        attestation_id: att.id.clone(),
+                        reason,
+                    }));
                 }
-            ],
-            "vulnerabilities": {
-                "critical": 0,
-                "high": 2,
-                "medium": 2,
-                "low": 10
+                SubjectMatch::NotMatched => {}
+            }
+        }
+
+        if candidates.is_empty() {
+            return Ok(failed(ControlPlaneError::NoMatchingAttestation {
+                component: component.name.clone(),
+            }));
+        }
+
+        // Newest first, ties broken by ID so the verdict never depends on storage order
+        candidates.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.id.cmp(&b.id)));
+
+        let policy = metrics::timed("policy", "resolve", self.policy_repo.resolve_policy(&component.policy)).await?;
+        report.policy = Some(format!("{}@{}", policy.purl, policy.version));
+
+        let strategy = component.selection.or(policy.selection).unwrap_or_default();
+        report.selection = Some(strategy);
+
+        let mut decisive = Vec::new();
+        match strategy {
+            SelectionStrategy::NewestOverall => {
+                let result = self.evaluate(candidates[0], &policy).await?;
+                decisive.push(report.verifications.len());
+                report.verifications.push(result);
             }
-        }),
-    };
-
-    let backend_attestation = Attestation {
-        id: "backend-att".to_string(),
-        issuer: "trusted_issuer".to_string(),
-        timestamp: Utc::now(),
-        content: json!({
-            "subject": [
-                {
-                    "name": "backend",
-                    "version": "2.3.4"
+            SelectionStrategy::NewestValid => {
+                for attestation in &candidates {
+                    let result = self.evaluate(attestation, &policy).await?;
+                    let authentic = is_authentic_and_current(&result);
+                    report.verifications.push(result);
+                    if authentic {
+                        decisive.push(report.verifications.len() - 1);
+                        break;
+                    }
+                }
+
+                if decisive.is_empty() {
+                    return Ok(failed(ControlPlaneError::NoValidAttestation {
+                        component: component.name.clone(),
+                    }));
                 }
-            ],
-            "vulnerabilities": {
-                "critical": 0,
-                "high": 1,
-                "medium": 1,
-                "low": 5
             }
-        }),
-    };
-
-    // Store attestations and keep the URIs
-    let frontend_uri = attestation_storage.store_attestation(Arc::new(frontend_attestation)).await.unwrap();
-    let backend_uri = attestation_storage.store_attestation(Arc::new(backend_attestation)).await.unwrap();
-
-    println!("Frontend attestation URI: {}", frontend_uri);
-    println!("Backend attestation URI: {}", backend_uri);
-
-    // Verify the project
-    let is_valid = control_plane.verify_project("ACMEAppX").await.unwrap();
-    assert!(is_valid, "ACMEAppX should be valid");
-
-    // Test with an invalid attestation
-    let invalid_backend_attestation = Attestation {
-        id: "invalid-backend-att".to_string(),
-        issuer: "trusted_issuer".to_string(),
-        timestamp: Utc::now(),
-        content: json!({
-            "subject": [
-                {
-                    "name": "backend",
-                    "version": "2.3.4"
+            SelectionStrategy::AllMustPass => {
+                for attestation in &candidates {
+                    let result = self.evaluate(attestation, &policy).await?;
+                    report.verifications.push(result);
+                }
+
+                decisive = (0..report.verifications.len()).filter(|&i| !report.verifications[i].is_valid()).collect();
+                if decisive.is_empty() {
+                    decisive = (0..report.verifications.len()).collect();
                 }
-            ],
-            "vulnerabilities": {
-                "critical": 1,
-                "high": 3,
-                "medium": 2,
-                "low": 5
             }
-        }),
-    };
+            SelectionStrategy::AnyMayPass => {
+                for attestation in &candidates {
+                    let result = self.evaluate(attestation, &policy).await?;
+                    let valid = result.is_valid();
+                    report.verifications.push(result);
+                    if valid {
+                        decisive.push(report.verifications.len() - 1);
+                        break;
+                    }
+                }
 
-    // Replace the valid backend attestation with the invalid one
-    attestation_storage.delete_attestation(&backend_uri).await.unwrap();
-    let new_backend_uri = attestation_storage.store_attestation(Arc::new(invalid_backend_attestation)).await.unwrap();
+                if decisive.is_empty() {
+                    decisive = (0..report.verifications.len()).collect();
+                }
+            }
+        }
+
+        let decisive: Vec<&VerificationResult> = decisive.into_iter().map(|i| &report.verifications[i]).collect();
+        let status = if decisive.iter().all(|r| r.is_valid()) {
+            ComponentStatus::Passed
+        } else {
+            let mut rules: Vec<PolicyRule> = decisive.iter().flat_map(|r| r.failed_rules()).collect();
+            rules.sort();
+            rules.dedup();
+            for rule in &rules {
+                Metrics::global().increment(POLICY_RULE_FAILURES, &[("rule", &rule.to_string())]);
+            }
 
-    println!("New backend attestation URI: {}", new_backend_uri);
+            failed(ControlPlaneError::PolicyViolation {
+                component: component.name.clone(),
+                attestations: decisive.iter().map(|r| r.attestation_id.as_str()).collect::<Vec<_>>().join(", "),
+                rules: rules.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", "),
+            })
+        };
 
-    // List all attestations for debugging
-    let all_attestations = attestation_storage.list_attestations().await.unwrap();
-    println!("All attestations after replacement:");
-    for att in all_attestations {
-        println!("ID: {}, Subject: {:?}", att.id, att.content["subject"]);
+        report.decided_by = decisive.iter().map(|r| r.attestation_id.clone()).collect();
+        Ok(status)
     }
 
-    // Verify the project again
-    let is_valid = control_plane.verify_project("ACMEAppX").await.unwrap();
-    assert!(!is_valid, "ACMEAppX should be invalid due to the backend component");
+    async fn evaluate(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, ControlPlaneError> {
+        let span = info_span!("attestation", id = %attestation.id, issuer = %attestation.issuer);
+        async {
+            let result = self.policy_verifier.evaluate_attestation(attestation, policy).await?;
+            debug!(valid = result.is_valid(), "Evaluated attestation");
+            Ok(result)
+        }
+        .instrument(span)
+        .await
+    }
 }
 
-#[tokio::test]
-async fn test_project_with_failing_policy() {
-    // Initialize repositories and verifier
-    let policy_repo = Arc::new(InMemoryPolicyRepository::new());
-    let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
-    let policy_verifier = Arc::new(SimplePolicyVerifier);
-
-    // Create a control plane
-    let mut control_plane = ControlPlane::new(
-        policy_repo.clone(),
-        attestation_storage.clone(),
-        policy_verifier,
-    );
-
-    // Create a strict policy for the component
-    let strict_policy = Policy {
-        purl: "pkg:github/acme/strict-component".to_string(),
-        version: "1.0.0".to_string(),
-        rules: PolicyRules {
-            allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
-            max_age_days: 7, // Strict: Only 7 days old attestations allowed
-            max_critical_vulnerabilities: 0,
-            max_high_medium_vulnerabilities: 2, // Strict: Only 2 high/medium vulnerabilities allowed
+/// Describes a component verdict for the audit log, pinning every attestation the report
+/// names by URI and digest.
+fn audit_record(project: Option<&str>, component: &Component, attestations: &[(String, Arc<Attestation>)], report: &ComponentReport) -> AuditRecord {
+    let mut ids: Vec<&str> = report.verifications.iter().map(|v| v.attestation_id.as_str()).collect();
+    ids.extend(report.decided_by.iter().map(String::as_str));
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    AuditRecord {
+        decided_at: Utc::now(),
+        subject: AuditSubject {
+            project: project.map(String::from),
+            component: component.name.clone(),
+            version: component.version.clone(),
+            digests: component.digests.clone(),
         },
-    };
+        policy: report.policy.clone(),
+        attestations: ids
+            .into_iter()
+            .filter_map(|id| attestations.iter().find(|(_, att)| att.id == id))
+            .map(|(uri, att)| AuditedAttestation::new(uri, att))
+            .collect(),
+        decided_by: report.decided_by.clone(),
+        verdict: report.status.clone(),
+        requested_by: None,
+    }
+}
 
-    // Add policy to the repository
-    policy_repo.add_policy(strict_policy.clone()).await.unwrap();
+/// Whether an attestation comes from an allowed issuer and is recent enough to count.
+fn is_authentic_and_current(result: &VerificationResult) -> bool {
+    result.outcomes
+        .iter()
+        .filter(|o| matches!(o.rule, PolicyRule::AllowedIssuers | PolicyRule::MaxAgeDays))
+        .all(|o| o.passed)
+}
 
-    // Create project with the strict component
-    let strict_project = SDLCProject {
-        name: "StrictProject".to_string(),
-        components: vec![
-            Component {
-                name: "strict-component".to_string(),
-                version: "1.0.0".to_string(),
-                policy: Arc::new(strict_policy),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    use crate::models::attestation::Attestation;
+    use crate::models::policy::{Policy, PolicyRef, PolicyRules};
+    use crate::storage::policy_repository::InMemoryPolicyRepository;
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+    use crate::verification::policy_verifier::SimplePolicyVerifier;
+
+    #[tokio::test]
+    async fn test_acme_app_x_project() {
+        // Initialize repositories and verifier
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let policy_verifier = Arc::new(SimplePolicyVerifier);
+
+        // Create a control plane
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            policy_verifier,
+        );
+
+        // Create policies for components
+        let frontend_policy = Policy {
+            purl: "pkg:github/acme/frontend".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: None,
+        };
+        let backend_policy = Policy {
+            purl: "pkg:github/acme/backend".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 3,
             },
-        ],
-    };
-
-    // Add project to the control plane
-    control_plane.add_project(strict_project).await;
-
-    // Create an attestation that violates the policy
-    let violating_attestation = Attestation {
-        id: "violating-att".to_string(),
-        issuer: "trusted_issuer".to_string(),
-        timestamp: Utc::now() - Duration::days(10), // Older than allowed
-        content: json!({
-            "subject": [
-                {
-                    "name": "strict-component",
-                    "version": "1.0.0"
+            selection: None,
+        };
+
+        // Add policies to the repository
+        policy_repo.add_policy(frontend_policy).await.unwrap();
+        policy_repo.add_policy(backend_policy).await.unwrap();
+
+        // Create ACMEAppX project
+        let acme_app_x = SDLCProject {
+            name: "ACMEAppX".to_string(),
+            components: vec![
+                Component {
+                    name: "frontend".to_string(),
+                    version: "1.2.3".to_string(),
+                    policy: PolicyRef::latest("pkg:github/acme/frontend"),
+                    digests: Default::default(),
+                    selection: None,
+                },
+                Component {
+                    name: "backend".to_string(),
+                    version: "2.3.4".to_string(),
+                    policy: PolicyRef::latest("pkg:github/acme/backend"),
+                    digests: Default::default(),
+                    selection: None,
+                },
+            ],
+        };
+
+        // Add project to the control plane
+        control_plane.add_project(acme_app_x).await.unwrap();
+
+        // Create valid attestations for components
+        let frontend_attestation = Attestation {
+            id: "frontend-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [
+                    {
+                        "name": "frontend",
+                        "version": "1.2.3"
+                    }
+                ],
+                "vulnerabilities": {
+                    "critical": 0,
+                    "high": 2,
+                    "medium": 2,
+                    "low": 10
                 }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        let backend_attestation = Attestation {
+            id: "backend-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [
+                    {
+                        "name": "backend",
+                        "version": "2.3.4"
+                    }
+                ],
+                "vulnerabilities": {
+                    "critical": 0,
+                    "high": 1,
+                    "medium": 1,
+                    "low": 5
+                }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        // Store attestations and keep the URIs
+        let frontend_uri = attestation_storage.store_attestation(Arc::new(frontend_attestation)).await.unwrap();
+        let backend_uri = attestation_storage.store_attestation(Arc::new(backend_attestation)).await.unwrap();
+
+        println!("Frontend attestation URI: {}", frontend_uri);
+        println!("Backend attestation URI: {}", backend_uri);
+
+        // Verify the project
+        let is_valid = control_plane.verify_project("ACMEAppX").await.unwrap().passed;
+        assert!(is_valid, "ACMEAppX should be valid");
+
+        // Test with an invalid attestation
+        let invalid_backend_attestation = Attestation {
+            id: "invalid-backend-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [
+                    {
+                        "name": "backend",
+                        "version": "2.3.4"
+                    }
+                ],
+                "vulnerabilities": {
+                    "critical": 1,
+                    "high": 3,
+                    "medium": 2,
+                    "low": 5
+                }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        // Replace the valid backend attestation with the invalid one
+        attestation_storage.delete_attestation(&backend_uri).await.unwrap();
+        let new_backend_uri = attestation_storage.store_attestation(Arc::new(invalid_backend_attestation)).await.unwrap();
+
+        println!("New backend attestation URI: {}", new_backend_uri);
+
+        // List all attestations for debugging
+        let all_attestations = attestation_storage.list_attestations().await.unwrap();
+        println!("All attestations after replacement:");
+        for att in all_attestations {
+            println!("ID: {}, Subject: {:?}", att.id, att.content["subject"]);
+        }
+
+        // Verify the project again
+        let is_valid = control_plane.verify_project("ACMEAppX").await.unwrap().passed;
+        assert!(!is_valid, "ACMEAppX should be invalid due to the backend component");
+    }
+
+    #[tokio::test]
+    async fn test_project_with_failing_policy() {
+        // Initialize repositories and verifier
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let policy_verifier = Arc::new(SimplePolicyVerifier);
+
+        // Create a control plane
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            policy_verifier,
+        );
+
+        // Create a strict policy for the component
+        let strict_policy = Policy {
+            purl: "pkg:github/acme/strict-component".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 7, // Strict: Only 7 days old attestations allowed
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 2, // Strict: Only 2 high/medium vulnerabilities allowed
+            },
+            selection: None,
+        };
+
+        // Add policy to the repository
+        policy_repo.add_policy(strict_policy).await.unwrap();
+
+        // Create project with the strict component
+        let strict_project = SDLCProject {
+            name: "StrictProject".to_string(),
+            components: vec![
+                Component {
+                    name: "strict-component".to_string(),
+                    version: "1.0.0".to_string(),
+                    policy: PolicyRef::latest("pkg:github/acme/strict-component"),
+                    digests: Default::default(),
+                    selection: None,
+                },
             ],
-            "vulnerabilities": {
-                "critical": 0,
-                "high": 2,
-                "medium": 1, // Total high+medium is 3, which exceeds the limit
-                "low": 5
-            }
-        }),
-    };
-
-    // Store the violating attestation
-    let violating_uri = attestation_storage.store_attestation(Arc::new(violating_attestation)).await.unwrap();
-    println!("Stored violating attestation with URI: {}", violating_uri);
-
-    // Verify the project
-    let is_valid = control_plane.verify_project("StrictProject").await.unwrap();
-    assert!(!is_valid, "StrictProject should be invalid due to policy violations");
-
-    // Create a valid attestation
-    let valid_attestation = Attestation {
-        id: "valid-att".to_string(),
-        issuer: "trusted_issuer".to_string(),
-        timestamp: Utc::now(), // Current timestamp
-        content: json!({
-            "subject": [
-                {
-                    "name": "strict-component",
-                    "version": "1.0.0"
+        };
+
+        // Add project to the control plane
+        control_plane.add_project(strict_project).await.unwrap();
+
+        // Create an attestation that violates the policy
+        let violating_attestation = Attestation {
+            id: "violating-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now() - Duration::days(10), // Older than allowed
+            content: json!({
+                "subject": [
+                    {
+                        "name": "strict-component",
+                        "version": "1.0.0"
+                    }
+                ],
+                "vulnerabilities": {
+                    "critical": 0,
+                    "high": 2,
+                    "medium": 1, // Total high+medium is 3, which exceeds the limit
+                    "low": 5
+                }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        // Store the violating attestation
+        let violating_uri = attestation_storage.store_attestation(Arc::new(violating_attestation)).await.unwrap();
+        println!("Stored violating attestation with URI: {}", violating_uri);
+
+        // Verify the project
+        let metrics = Metrics::global();
+        let violations = metrics.counter(VERIFICATION_FAILURES, &[("reason", "policy_violation")]);
+        let is_valid = control_plane.verify_project("StrictProject").await.unwrap().passed;
+        assert!(!is_valid, "StrictProject should be invalid due to policy violations");
+        // Counters are process-wide and other tests run concurrently, so only check they moved
+        assert!(metrics.counter(VERIFICATION_FAILURES, &[("reason", "policy_violation")]) > violations);
+        assert!(metrics.counter(POLICY_RULE_FAILURES, &[("rule", "max_age_days")]) > 0);
+
+        // Create a valid attestation
+        let valid_attestation = Attestation {
+            id: "valid-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(), // Current timestamp
+            content: json!({
+                "subject": [
+                    {
+                        "name": "strict-component",
+                        "version": "1.0.0"
+                    }
+                ],
+                "vulnerabilities": {
+                    "critical": 0,
+                    "high": 1,
+                    "medium": 1, // Total high+medium is 2, which meets the limit
+                    "low": 5
                 }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        // Replace the violating attestation with the valid one
+        attestation_storage.delete_attestation(&violating_uri).await.unwrap();
+        let valid_uri = attestation_storage.store_attestation(Arc::new(valid_attestation)).await.unwrap();
+        println!("Stored valid attestation with URI: {}", valid_uri);
+
+        // Print all stored attestations
+        let all_attestations = attestation_storage.list_attestations().await.unwrap();
+        println!("All stored attestations:");
+        for att in all_attestations {
+            println!("ID: {}, Subject: {:?}", att.id, att.content["subject"]);
+        }
+
+        // Verify the project again
+        let is_valid = control_plane.verify_project("StrictProject").await.unwrap().passed;
+        assert!(is_valid, "StrictProject should be valid after replacing with a compliant attestation");
+    }
+
+    #[tokio::test]
+    async fn test_policy_updates_reach_existing_projects() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            Arc::new(SimplePolicyVerifier),
+        );
+
+        let policy = |version: &str, max_high_medium_vulnerabilities| Policy {
+            purl: "pkg:github/acme/service".to_string(),
+            version: version.to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities,
+            },
+            selection: None,
+        };
+        policy_repo.add_policy(policy("1.0.0", 5)).await.unwrap();
+
+        control_plane.add_project(SDLCProject {
+            name: "Floating".to_string(),
+            components: vec![Component {
+                name: "service".to_string(),
+                version: "1.0.0".to_string(),
+                policy: PolicyRef::latest("pkg:github/acme/service"),
+                digests: Default::default(),
+                selection: None,
+            }],
+        }).await.unwrap();
+        control_plane.add_project(SDLCProject {
+            name: "Pinned".to_string(),
+            components: vec![Component {
+                name: "service".to_string(),
+                version: "1.0.0".to_string(),
+                policy: PolicyRef::matching("pkg:github/acme/service", "~1.0").unwrap(),
+                digests: Default::default(),
+                selection: None,
+            }],
+        }).await.unwrap();
+
+        attestation_storage.store_attestation(Arc::new(Attestation {
+            id: "service-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": "service", "version": "1.0.0" }],
+                "vulnerabilities": { "critical": 0, "high": 2, "medium": 2, "low": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        })).await.unwrap();
+
+        assert!(control_plane.verify_project("Floating").await.unwrap().passed);
+        assert!(control_plane.verify_project("Pinned").await.unwrap().passed);
+
+        // Publishing a stricter policy takes effect without rebuilding the projects
+        policy_repo.add_policy(policy("1.1.0", 3)).await.unwrap();
+        assert!(!control_plane.verify_project("Floating").await.unwrap().passed);
+        assert!(control_plane.verify_project("Pinned").await.unwrap().passed);
+    }
+
+    #[tokio::test]
+    async fn test_digest_pinned_component() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            Arc::new(SimplePolicyVerifier),
+        );
+
+        policy_repo.add_policy(Policy {
+            purl: "pkg:github/acme/api".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: None,
+        }).await.unwrap();
+
+        let pinned = "1f".repeat(32);
+        control_plane.add_project(SDLCProject {
+            name: "Pinned".to_string(),
+            components: vec![Component {
+                name: "api".to_string(),
+                version: "3.0.0".to_string(),
+                policy: PolicyRef::latest("pkg:github/acme/api"),
+                digests: vec![("sha256".to_string(), pinned.clone())].into_iter().collect(),
+                selection: None,
+            }],
+        }).await.unwrap();
+
+        // The pinned artifact is found even when it is not the first subject
+        let genuine_uri = attestation_storage.store_attestation(Arc::new(Attestation {
+            id: "api-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [
+                    { "name": "api-sbom", "digest": { "sha256": "2e".repeat(32) } },
+                    { "name": "api", "version": "3.0.0", "digest": { "sha256": pinned } }
+                ],
+                "vulnerabilities": { "critical": 0, "high": 0, "medium": 0, "low": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        })).await.unwrap();
+        assert!(control_plane.verify_project("Pinned").await.unwrap().passed);
+
+        // Different bytes under the same version string are a hard failure
+        attestation_storage.delete_attestation(&genuine_uri).await.unwrap();
+        attestation_storage.store_attestation(Arc::new(Attestation {
+            id: "rebuilt-api-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": "api", "version": "3.0.0", "digest": { "sha256": "3d".repeat(32) } }],
+                "vulnerabilities": { "critical": 0, "high": 0, "medium": 0, "low": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        })).await.unwrap();
+
+        let report = control_plane.verify_project("Pinned").await.unwrap();
+        assert!(!report.passed);
+        let api = report.component("api").unwrap();
+        assert_eq!(api.decided_by, vec!["rebuilt-api-att".to_string()]);
+        match &api.status {
+            ComponentStatus::Failed { reason } => assert!(reason.starts_with("Digest mismatch for component api")),
+            status => panic!("Unexpected status: {:?}", status),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_project_report_aggregates_all_components() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            Arc::new(SimplePolicyVerifier),
+        );
+
+        policy_repo.add_policy(Policy {
+            purl: "pkg:github/acme/shared".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: None,
+        }).await.unwrap();
+
+        let component = |name: &str, purl: &str| Component {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            policy: PolicyRef::latest(purl),
+            digests: Default::default(),
+            selection: None,
+        };
+        control_plane.add_project(SDLCProject {
+            name: "Mixed".to_string(),
+            components: vec![
+                component("vulnerable", "pkg:github/acme/shared"),
+                component("unattested", "pkg:github/acme/shared"),
+                component("healthy", "pkg:github/acme/shared"),
+                component("unknown-policy", "pkg:github/acme/missing"),
             ],
-            "vulnerabilities": {
-                "critical": 0,
-                "high": 1,
-                "medium": 1, // Total high+medium is 2, which meets the limit
-                "low": 5
-            }
-        }),
-    };
-
-    // Replace the violating attestation with the valid one
-    attestation_storage.delete_attestation(&violating_uri).await.unwrap();
-    let valid_uri = attestation_storage.store_attestation(Arc::new(valid_attestation)).await.unwrap();
-    println!("Stored valid attestation with URI: {}", valid_uri);
-
-    // Print all stored attestations
-    let all_attestations = attestation_storage.list_attestations().await.unwrap();
-    println!("All stored attestations:");
-    for att in all_attestations {
-        println!("ID: {}, Subject: {:?}", att.id, att.content["subject"]);
+        }).await.unwrap();
+
+        for (name, critical) in [("vulnerable", 2), ("healthy", 0), ("unknown-policy", 0)] {
+            attestation_storage.store_attestation(Arc::new(Attestation {
+                id: format!("{}-att", name),
+                issuer: "trusted_issuer".to_string(),
+                timestamp: Utc::now(),
+                content: json!({
+                    "subject": [{ "name": name, "version": "1.0.0" }],
+                    "vulnerabilities": { "critical": critical, "high": 0, "medium": 0, "low": 0 }
+                }),
+                envelope: None,
+                verification_material: None,
+            })).await.unwrap();
+        }
+
+        let report = control_plane.verify_project("Mixed").await.unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.components.len(), 4);
+        assert_eq!(report.failed_components().count(), 3);
+
+        let vulnerable = report.component("vulnerable").unwrap();
+        assert!(matches!(&vulnerable.status, ComponentStatus::Failed { reason } if reason.contains("max_critical_vulnerabilities")));
+        assert_eq!(vulnerable.decided_by, vec!["vulnerable-att".to_string()]);
+        assert_eq!(vulnerable.policy.as_deref(), Some("pkg:github/acme/shared@1.0.0"));
+        assert!(matches!(report.component("unattested").unwrap().status, ComponentStatus::Failed { .. }));
+        assert!(report.component("healthy").unwrap().status.is_passed());
+        assert!(matches!(report.component("unknown-policy").unwrap().status, ComponentStatus::Error { .. }));
+
+        // Fail-fast stops at the first failing component
+        let options = VerificationOptions { max_concurrency: 1, fail_fast: true, ..Default::default() };
+        let report = control_plane.verify_project_with_options("Mixed", &options).await.unwrap();
+        assert!(!report.passed);
+        assert!(matches!(report.components[0].status, ComponentStatus::Failed { .. }));
+        assert!(report.components[1..].iter().all(|c| matches!(c.status, ComponentStatus::Skipped)));
     }
 
-    // Verify the project again
-    let is_valid = control_plane.verify_project("StrictProject").await.unwrap();
-    assert!(is_valid, "StrictProject should be valid after replacing with a compliant attestation");
+    #[tokio::test]
+    async fn test_attestation_selection_strategies() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            Arc::new(SimplePolicyVerifier),
+        );
+
+        policy_repo.add_policy(Policy {
+            purl: "pkg:github/acme/lib".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: Some(SelectionStrategy::AnyMayPass),
+        }).await.unwrap();
+
+        let strategies = [
+            ("policy-default", None),
+            ("newest-overall", Some(SelectionStrategy::NewestOverall)),
+            ("newest-valid", Some(SelectionStrategy::NewestValid)),
+            ("all-must-pass", Some(SelectionStrategy::AllMustPass)),
+            ("any-may-pass", Some(SelectionStrategy::AnyMayPass)),
+        ];
+        for (name, selection) in strategies {
+            control_plane.add_project(SDLCProject {
+                name: name.to_string(),
+                components: vec![Component {
+                    name: "lib".to_string(),
+                    version: "1.0.0".to_string(),
+                    policy: PolicyRef::latest("pkg:github/acme/lib"),
+                    digests: Default::default(),
+                    selection,
+                }],
+            }).await.unwrap();
+        }
+
+        // Oldest passes, the next one has a critical vulnerability, the newest is from an untrusted issuer
+        for (id, issuer, age_days, critical) in [
+            ("clean", "trusted_issuer", 3, 0),
+            ("vulnerable", "trusted_issuer", 2, 1),
+            ("untrusted", "rogue_issuer", 1, 0),
+        ] {
+            attestation_storage.store_attestation(Arc::new(Attestation {
+                id: id.to_string(),
+                issuer: issuer.to_string(),
+                timestamp: Utc::now() - Duration::days(age_days),
+                content: json!({
+                    "subject": [{ "name": "lib", "version": "1.0.0" }],
+                    "vulnerabilities": { "critical": critical, "high": 0, "medium": 0, "low": 0 }
+                }),
+                envelope: None,
+                verification_material: None,
+            })).await.unwrap();
+        }
+
+        let verdict = |report: ProjectVerificationReport| {
+            let lib = report.component("lib").unwrap().clone();
+            (report.passed, lib.selection.unwrap(), lib.decided_by)
+        };
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            verdict(control_plane.verify_project("policy-default").await.unwrap()),
+            (true, SelectionStrategy::AnyMayPass, ids(&["clean"]))
+        );
+        assert_eq!(
+            verdict(control_plane.verify_project("newest-overall").await.unwrap()),
+            (false, SelectionStrategy::NewestOverall, ids(&["untrusted"]))
+        );
+        assert_eq!(
+            verdict(control_plane.verify_project("newest-valid").await.unwrap()),
+            (false, SelectionStrategy::NewestValid, ids(&["vulnerable"]))
+        );
+        assert_eq!(
+            verdict(control_plane.verify_project("all-must-pass").await.unwrap()),
+            (false, SelectionStrategy::AllMustPass, ids(&["untrusted", "vulnerable"]))
+        );
+        assert_eq!(
+            verdict(control_plane.verify_project("any-may-pass").await.unwrap()),
+            (true, SelectionStrategy::AnyMayPass, ids(&["clean"]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verdicts_recorded_in_audit_log() {
+        use crate::models::audit::{verify_chain, GENESIS_HASH};
+        use crate::storage::audit_log::InMemoryAuditLog;
+
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let audit_log = Arc::new(InMemoryAuditLog::new());
+
+        let control_plane = ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            Arc::new(SimplePolicyVerifier),
+        ).with_audit_log(audit_log.clone());
+
+        policy_repo.add_policy(Policy {
+            purl: "pkg:github/acme/api".to_string(),
+            version: "2.1.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 30,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: None,
+        }).await.unwrap();
+
+        let component = |name: &str| Component {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            policy: PolicyRef::latest("pkg:github/acme/api"),
+            digests: Default::default(),
+            selection: None,
+        };
+        control_plane.add_project(SDLCProject {
+            name: "api".to_string(),
+            components: vec![component("api"), component("unattested")],
+        }).await.unwrap();
+
+        let uri = attestation_storage.store_attestation(Arc::new(Attestation {
+            id: "api-scan".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": "api", "version": "1.0.0" }],
+                "vulnerabilities": { "critical": 0, "high": 0, "medium": 0, "low": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        })).await.unwrap();
+
+        assert!(!control_plane.verify_project("api").await.unwrap().passed);
+
+        let mut entries = audit_log.entries(0..10).await.unwrap();
+        assert_eq!(verify_chain(GENESIS_HASH, &entries).unwrap(), audit_log.head().await.unwrap().unwrap().hash);
+        entries.sort_by(|a, b| a.record.subject.component.cmp(&b.record.subject.component));
+
+        let api = &entries[0].record;
+        assert_eq!(api.subject.project.as_deref(), Some("api"));
+        assert_eq!(api.policy.as_deref(), Some("pkg:github/acme/api@2.1.0"));
+        assert!(api.verdict.is_passed());
+        assert_eq!(api.attestations.len(), 1);
+        assert_eq!(api.attestations[0].uri, uri);
+        assert_eq!(api.attestations[0].sha256.len(), 64);
+
+        let unattested = &entries[1].record;
+        assert!(matches!(unattested.verdict, ComponentStatus::Failed { .. }));
+        assert!(unattested.attestations.is_empty());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,377 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::models::attestation::Attestation;
+use crate::models::canonical::canonical_sha256;
+use crate::models::dsse::{statement_time, Envelope, Keyring, IN_TOTO_PAYLOAD_TYPE};
+use crate::models::events::{CDEvent, CDEventType, EventSubject, SubjectType};
+use crate::models::sigstore::Bundle;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::verification::sigstore_verifier::TrustRoot;
+
+/// Lines longer than this are reported as errors instead of being buffered.
+pub const DEFAULT_MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+pub const DEFAULT_PROGRESS_INTERVAL: u64 = 10_000;
+/// How many line errors a report keeps; later ones are only counted.
+pub const DEFAULT_MAX_REPORTED_ERRORS: usize = 1_000;
+
+#[derive(Error, Debug)]
+pub enum IngestError {
+    #[error("Could not read line {line}: {source}")]
+    Read { line: u64, source: std::io::Error },
+}
+
+/// A line that could not be ingested. The stream carries on past it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineError {
+    pub line: u64,
+    pub reason: String,
+    /// Whether ingesting the line again may succeed, e.g. after a storage outage.
+    pub retryable: bool,
+}
+
+impl LineError {
+    fn new(line: u64, reason: impl Into<String>) -> Self {
+        Self {
+            line,
+            reason: reason.into(),
+            retryable: false,
+        }
+    }
+}
+
+/// Where an ingest stands; passed to the progress callback and returned at the end.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestReport {
+    /// Lines read, including skipped and blank ones.
+    pub lines: u64,
+    pub bytes: u64,
+    pub stored: u64,
+    pub failed: u64,
+    /// The first line errors, up to the ingestor's limit.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<LineError>,
+}
+
+/// Loads attestations from JSON Lines.
+///
+/// Each line is an `Attestation`, with a keyring a DSSE envelope of an in-toto statement,
+/// or with a trust root a Sigstore bundle. Every statement must name its subjects and predicate
+/// type. Lines are read and stored one at a time, and overlong lines are skipped rather
+/// than buffered, so memory stays bounded however large the input. A line that fails is
+/// recorded in the report and the stream continues.
+pub struct AttestationIngestor<A: ?Sized> {
+    storage: Arc<A>,
+    events: Option<mpsc::Sender<CDEvent>>,
+    keyring: Option<Keyring>,
+    timestamp: Option<DateTime<Utc>>,
+    trust_root: Option<TrustRoot>,
+    max_line_bytes: usize,
+    progress_interval: u64,
+    max_reported_errors: usize,
+    resume_after: u64,
+}
+
+impl<A: AttestationStorage + ?Sized> AttestationIngestor<A> {
+    pub fn new(storage: Arc<A>) -> Self {
+        Self {
+            storage,
+            events: None,
+            keyring: None,
+            timestamp: None,
+            trust_root: None,
+            max_line_bytes: DEFAULT_MAX_LINE_BYTES,
+            progress_interval: DEFAULT_PROGRESS_INTERVAL,
+            max_reported_errors: DEFAULT_MAX_REPORTED_ERRORS,
+            resume_after: 0,
+        }
+    }
+
+    /// Sends an `AttestationCreated` event for every stored attestation. The channel's
+    /// bound applies back-pressure to the stream.
+    pub fn with_events(mut self, events: mpsc::Sender<CDEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Accepts bare DSSE envelopes that verify under one of the keyring's keys, issued by
+    /// the issuer that key signs for.
+    pub fn with_keyring(mut self, keyring: Keyring) -> Self {
+        self.keyring = Some(keyring);
+        self
+    }
+
+    /// The time bare DSSE envelopes were produced, for statements that do not record it.
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Accepts Sigstore bundles, verified against the trust root and issued by the
+    /// certificate's identity.
+    pub fn with_trust_root(mut self, trust_root: TrustRoot) -> Self {
+        self.trust_root = Some(trust_root);
+        self
+    }
+
+    pub fn with_max_line_bytes(mut self, max_line_bytes: usize) -> Self {
+        self.max_line_bytes = max_line_bytes;
+        self
+    }
+
+    /// Calls the progress callback every `lines` lines.
+    pub fn with_progress_interval(mut self, lines: u64) -> Self {
+        self.progress_interval = lines.max(1);
+        self
+    }
+
+    pub fn with_max_reported_errors(mut self, max_reported_errors: usize) -> Self {
+        self.max_reported_errors = max_reported_errors;
+        self
+    }
+
+    /// Skips the first `line` lines, to pick up an interrupted ingest where its last
+    /// progress report left off.
+    pub fn resume_after(mut self, line: u64) -> Self {
+        self.resume_after = line;
+        self
+    }
+
+    /// Ingests every line of the input, calling `progress` periodically and once at
+    /// the end. Only failing to read the input stops the stream.
+    pub async fn ingest<R, F>(&self, input: R, mut progress: F) -> Result<IngestReport, IngestError>
+    where
+        R: AsyncRead + Unpin,
+        F: FnMut(&IngestReport),
+    {
+        let mut reader = BufReader::new(input);
+        let mut report = IngestReport::default();
+        let mut buf = Vec::new();
+
+        loop {
+            let line = report.lines + 1;
+            let (consumed, truncated) = next_line(&mut reader, &mut buf, self.max_line_bytes)
+                .await
+                .map_err(|source| IngestError::Read { line, source })?;
+            if consumed == 0 {
+                break;
+            }
+            report.lines = line;
+            report.bytes += consumed as u64;
+
+            if line > self.resume_after && !buf.trim_ascii().is_empty() {
+                let result = if truncated {
+                    Err(LineError::new(line, format!("line is longer than {} bytes", self.max_line_bytes)))
+                } else {
+                    self.ingest_line(line, &buf).await
+                };
+                match result {
+                    Ok(_) => report.stored += 1,
+                    Err(error) => {
+                        warn!(line, error = %error.reason, "Could not ingest attestation");
+                        report.failed += 1;
+                        if report.errors.len() < self.max_reported_errors {
+                            report.errors.push(error);
+                        }
+                    }
+                }
+            }
+
+            if line % self.progress_interval == 0 {
+                progress(&report);
+            }
+        }
+
+        progress(&report);
+        Ok(report)
+    }
+
+    async fn ingest_line(&self, line: u64, bytes: &[u8]) -> Result<String, LineError> {
+        let attestation = self.parse(bytes).map_err(|reason| LineError::new(line, reason))?;
+        validate_statement(&attestation.content).map_err(|reason| LineError::new(line, reason))?;
+
+        let id = attestation.id.clone();
+        let uri = self.storage.store_attestation(Arc::new(attestation)).await.map_err(|e| LineError {
+            line,
+            reason: e.to_string(),
+            retryable: e.is_retryable(),
+        })?;
+
+        if let Some(events) = &self.events {
+            let event = CDEvent::new(
+                CDEventType::AttestationCreated {
+                    attestation_id: id.clone(),
+                    attestation_uri: uri.clone(),
+                },
+                EventSubject {
+                    id,
+                    subject_type: SubjectType::Attestation,
+                },
+            );
+            events
+                .send(event)
+                .await
+                .map_err(|_| LineError::new(line, format!("stored as {} but the event channel is closed", uri)))?;
+        }
+
+        Ok(uri)
+    }
+
+    fn parse(&self, bytes: &[u8]) -> Result<Attestation, String> {
+        let value: Value = serde_json::from_slice(bytes).map_err(|e| format!("not JSON: {}", e))?;
+
+        if value.get("dsseEnvelope").is_some() {
+            let trust_root = self.trust_root.as_ref().ok_or("Sigstore bundles need a trust root")?;
+            let bundle: Bundle = serde_json::from_value(value).map_err(|e| format!("invalid Sigstore bundle: {}", e))?;
+            return trust_root.import_bundle(bundle).map_err(|e| e.to_string());
+        }
+
+        if value.get("payloadType").is_some() {
+            let envelope: Envelope = serde_json::from_value(value).map_err(|e| format!("invalid DSSE envelope: {}", e))?;
+            if envelope.payload_type != IN_TOTO_PAYLOAD_TYPE {
+                return Err(format!("unsupported DSSE payload type {}", envelope.payload_type));
+            }
+            let keyring = self.keyring.as_ref().ok_or("DSSE envelopes need a keyring")?;
+            let verified = keyring.verify(&envelope)?;
+            // The time of ingest would restart the statement's age on every import
+            let timestamp = statement_time(&verified.statement)
+                .or(self.timestamp)
+                .ok_or("statement records no signed time and no timestamp was given")?;
+            return Ok(Attestation {
+                // The same envelope always gets the same ID
+                id: canonical_sha256(&envelope).map_err(|e| e.to_string())?,
+                issuer: verified.issuer,
+                timestamp,
+                content: verified.statement,
+                envelope: Some(envelope),
+                verification_material: None,
+            });
+        }
+
+        let attestation: Attestation = serde_json::from_value(value).map_err(|e| format!("invalid attestation: {}", e))?;
+        if attestation.id.is_empty() || attestation.issuer.is_empty() {
+            return Err("attestation needs an id and an issuer".to_string());
+        }
+        Ok(attestation)
+    }
+}
+
+fn validate_statement(statement: &Value) -> Result<(), String> {
+    if statement["subject"].as_array().is_none_or(|subjects| subjects.is_empty()) {
+        return Err("statement names no subjects".to_string());
+    }
+    if statement["predicateType"].as_str().is_none_or(str::is_empty) {
+        return Err("statement has no predicateType".to_string());
+    }
+    Ok(())
+}
+
+/// Reads the next line into `buf`, keeping at most `max` bytes of it. Returns how many
+/// bytes were consumed, zero at the end of the input, and whether the line was cut short.
+async fn next_line<R: AsyncBufRead + Unpin>(reader: &mut R, buf: &mut Vec<u8>, max: usize) -> std::io::Result<(usize, bool)> {
+    buf.clear();
+    let mut consumed = 0;
+    let mut truncated = false;
+
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            break;
+        }
+        let (chunk, end_of_line) = match available.iter().position(|b| *b == b'\n') {
+            Some(newline) => (&available[..=newline], true),
+            None => (available, false),
+        };
+
+        let room = max.saturating_sub(buf.len());
+        truncated |= chunk.len() > room;
+        buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+
+        let len = chunk.len();
+        reader.consume(len);
+        consumed += len;
+        if end_of_line {
+            break;
+        }
+    }
+
+    Ok((consumed, truncated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use ed25519_dalek::SigningKey;
+    use serde_json::json;
+
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+
+    #[tokio::test]
+    async fn test_ingest_jsonl() {
+        let statement = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "frontend", "digest": { "sha256": "ab".repeat(32) } }],
+            "predicateType": "https://slsa.dev/provenance/v1",
+        });
+        let attestation = Attestation {
+            id: "att1".to_string(),
+            issuer: "ci".to_string(),
+            timestamp: Utc::now(),
+            content: statement.clone(),
+            ..Default::default()
+        };
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let envelope = Envelope::sign(&statement, &signing_key).unwrap();
+        let untrusted = Envelope::sign(&statement, &SigningKey::from_bytes(&[8; 32])).unwrap();
+        let input = [
+            serde_json::to_string(&attestation).unwrap(),
+            String::new(),
+            "not json".to_string(),
+            serde_json::to_string(&envelope).unwrap(),
+            json!({ "id": "att2", "issuer": "ci", "timestamp": Utc::now(), "content": {} }).to_string(),
+            format!("{{\"pad\": \"{}\"}}", "x".repeat(2000)),
+            serde_json::to_string(&untrusted).unwrap(),
+        ]
+        .join("\n");
+
+        let storage = Arc::new(InMemoryAttestationStorage::new());
+        let (tx, mut rx) = mpsc::channel(16);
+        let signed_at = Utc::now() - Duration::days(3);
+        let ingestor = AttestationIngestor::new(storage.clone())
+            .with_events(tx)
+            .with_keyring(Keyring::new().with_issuer_key(signing_key.verifying_key(), "release-bot"))
+            .with_timestamp(signed_at)
+            .with_max_line_bytes(1024)
+            .with_progress_interval(2);
+
+        let mut updates = Vec::new();
+        let report = ingestor.ingest(input.as_bytes(), |progress| updates.push(progress.lines)).await.unwrap();
+        assert_eq!((report.lines, report.stored, report.failed), (7, 2, 4));
+        assert_eq!(report.errors.iter().map(|e| e.line).collect::<Vec<_>>(), [3, 5, 6, 7]);
+        assert!(report.errors[3].reason.contains("trusted key"));
+        assert_eq!(updates, [2, 4, 6, 7]);
+
+        let entries = storage.list_attestation_entries().await.unwrap();
+        assert_eq!(entries.len(), 2);
+        let enveloped = entries.iter().find(|(_, a)| a.envelope.is_some()).unwrap();
+        assert_eq!(enveloped.1.issuer, "release-bot");
+        assert_eq!(enveloped.1.timestamp, signed_at);
+        for _ in 0..2 {
+            let CDEventType::AttestationCreated { attestation_uri, .. } = rx.recv().await.unwrap().event_type else {
+                panic!("expected an AttestationCreated event");
+            };
+            assert!(entries.iter().any(|(uri, _)| *uri == attestation_uri));
+        }
+
+        // Resuming skips what an earlier run already covered
+        let report = AttestationIngestor::new(storage).resume_after(4).ingest(input.as_bytes(), |_| {}).await.unwrap();
+        assert_eq!((report.stored, report.failed), (0, 3));
+    }
+}
@@ -1 +1,5 @@
-pub mod controlplane;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod controlplane;
+pub mod ingest;
+pub mod report;
+pub mod summary;
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::policy::SelectionStrategy;
+use crate::models::project::Component;
+use crate::verification::policy_verifier::VerificationResult;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ComponentStatus {
+    Passed,
+    /// The component was checked and does not satisfy its policy.
+    Failed { reason: String },
+    /// The component could not be checked, e.g. its policy could not be resolved.
+    Error { message: String },
+    /// The component was not checked because fail-fast verification stopped early.
+    Skipped,
+}
+
+impl ComponentStatus {
+    pub fn is_passed(&self) -> bool {
+        matches!(self, ComponentStatus::Passed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentReport {
+    pub component: String,
+    pub version: String,
+    #[serde(flatten)]
+    pub status: ComponentStatus,
+    /// The resolved policy as `purl@version`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<SelectionStrategy>,
+    /// IDs of the attestations that settled the verdict.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decided_by: Vec<String>,
+    /// Results for every candidate attestation that was evaluated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verifications: Vec<VerificationResult>,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+impl ComponentReport {
+    pub fn new(component: &Component, started_at: DateTime<Utc>) -> Self {
+        Self {
+            component: component.name.clone(),
+            version: component.version.clone(),
+            status: ComponentStatus::Skipped,
+            policy: None,
+            selection: None,
+            decided_by: Vec::new(),
+            verifications: Vec::new(),
+            started_at,
+            duration_ms: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectVerificationReport {
+    pub project: String,
+    pub passed: bool,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub components: Vec<ComponentReport>,
+}
+
+impl ProjectVerificationReport {
+    pub fn component(&self, name: &str) -> Option<&ComponentReport> {
+        self.components.iter().find(|c| c.component == name)
+    }
+
+    pub fn failed_components(&self) -> impl Iterator<Item = &ComponentReport> {
+        self.components.iter().filter(|c| !c.status.is_passed())
+    }
+}
+
+/// Controls how `ControlPlane` verifies the components of a project.
+#[derive(Debug, Clone)]
+pub struct VerificationOptions {
+    /// Maximum number of components verified at the same time.
+    pub max_concurrency: usize,
+    /// Stop at the first component that does not pass and skip the rest.
+    pub fail_fast: bool,
+    /// Who asked for the verification, recorded with each verdict in the audit log.
+    pub requested_by: Option<String>,
+}
+
+impl Default for VerificationOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 8,
+            fail_fast: false,
+            requested_by: None,
+        }
+    }
+}
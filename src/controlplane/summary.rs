@@ -0,0 +1,158 @@
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+use crate::controlplane::report::ProjectVerificationReport;
+use crate::models::attestation::Attestation;
+use crate::models::audit::AuditedAttestation;
+use crate::models::project::SDLCProject;
+use crate::models::summary_scai::SummaryScaiPredicateAttributesItemAttribute;
+
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+pub const SCAI_PREDICATE_TYPE: &str = "https://in-toto.io/attestation/scai/attribute-report/v0.2";
+
+/// Builds an in-toto SCAI attribute report stating that every component of a project
+/// passed verification.
+///
+/// Each component becomes a subject, identified by its pinned digests or else by the
+/// digests of the matching subject in the attestation that decided it. Each deciding
+/// attestation becomes the evidence of one attribute, pinned by its URI and digest,
+/// with the resolved policy as its condition.
+pub fn summary_statement(
+    project: &SDLCProject,
+    report: &ProjectVerificationReport,
+    attestations: &[(String, Arc<Attestation>)],
+    attribute: SummaryScaiPredicateAttributesItemAttribute,
+    producer_uri: &str,
+) -> Result<Value, String> {
+    if !report.passed {
+        return Err(format!("Project {} did not pass verification", report.project));
+    }
+
+    let mut subjects = Vec::new();
+    let mut attributes = Vec::new();
+    for component in &project.components {
+        let component_report = report
+            .component(&component.name)
+            .ok_or_else(|| format!("The report has no result for component {}", component.name))?;
+
+        let decisive: Vec<&(String, Arc<Attestation>)> = component_report
+            .decided_by
+            .iter()
+            .filter_map(|id| attestations.iter().find(|(_, att)| &att.id == id))
+            .collect();
+
+        let digest = if component.digests.is_empty() {
+            decisive
+                .iter()
+                .find_map(|(_, att)| subject_digest(&component.name, &component.version, &att.content))
+                .ok_or_else(|| format!("Component {} is not pinned and no attestation names its digest", component.name))?
+        } else {
+            component.digests.iter().map(|(k, v)| (k.clone(), Value::String(v.clone()))).collect()
+        };
+
+        subjects.push(json!({
+            "name": component.name,
+            "digest": digest,
+            "annotations": { "version": component.version },
+        }));
+
+        for (uri, attestation) in decisive {
+            let audited = AuditedAttestation::new(uri, attestation);
+            let mut item = json!({
+                "attribute": attribute,
+                "evidence": {
+                    "name": audited.id,
+                    "uri": audited.uri,
+                    "digest": { "sha256": audited.sha256 },
+                    "mediaType": "application/json",
+                },
+            });
+            if let Some(policy) = &component_report.policy {
+                item["conditions"] = json!({ "policy": policy });
+            }
+            attributes.push(item);
+        }
+    }
+
+    Ok(json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subjects,
+        "predicateType": SCAI_PREDICATE_TYPE,
+        "predicate": {
+            "attributes": attributes,
+            "producer": { "uri": producer_uri },
+        },
+    }))
+}
+
+/// The digests of the statement subject carrying the given name and version.
+fn subject_digest(name: &str, version: &str, content: &Value) -> Option<Map<String, Value>> {
+    content["subject"]
+        .as_array()?
+        .iter()
+        .find(|s| s["name"].as_str() == Some(name) && s["version"].as_str() == Some(version))
+        .and_then(|s| s["digest"].as_object())
+        .filter(|d| !d.is_empty())
+        .cloned()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    use crate::controlplane::report::{ComponentReport, ComponentStatus};
+    use crate::models::policy::PolicyRef;
+    use crate::models::project::Component;
+    use crate::models::summary_scai::SummaryScai;
+
+    #[test]
+    fn test_summary_statement() {
+        let component = Component {
+            name: "frontend".to_string(),
+            version: "1.2.3".to_string(),
+            policy: PolicyRef::latest("pkg:github/acme/frontend"),
+            digests: Default::default(),
+            selection: None,
+        };
+        let project = SDLCProject {
+            name: "ACMEAppX".to_string(),
+            components: vec![component.clone()],
+        };
+        let attestation = Arc::new(Attestation {
+            id: "att1".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": "frontend", "version": "1.2.3", "digest": { "sha256": "ab".repeat(32) } }],
+            }),
+            ..Default::default()
+        });
+        let attestations = vec![("attestation://att1".to_string(), attestation.clone())];
+
+        let mut component_report = ComponentReport::new(&component, Utc::now());
+        component_report.status = ComponentStatus::Passed;
+        component_report.policy = Some("pkg:github/acme/frontend@1.0.0".to_string());
+        component_report.decided_by = vec!["att1".to_string()];
+        let mut report = ProjectVerificationReport {
+            project: project.name.clone(),
+            passed: true,
+            started_at: Utc::now(),
+            duration_ms: 0,
+            components: vec![component_report],
+        };
+
+        let attribute = SummaryScaiPredicateAttributesItemAttribute::PassedBuild;
+        let statement = summary_statement(&project, &report, &attestations, attribute, "https://example.com/ci").unwrap();
+        assert_eq!(statement["subject"][0]["digest"]["sha256"], "ab".repeat(32));
+        assert_eq!(statement["predicate"]["attributes"][0]["evidence"]["uri"], "attestation://att1");
+        assert_eq!(statement["predicate"]["attributes"][0]["conditions"]["policy"], "pkg:github/acme/frontend@1.0.0");
+
+        let summary: SummaryScai = serde_json::from_value(statement).unwrap();
+        assert_eq!(summary.predicate.attributes[0].attribute, attribute);
+
+        report.passed = false;
+        assert!(summary_statement(&project, &report, &attestations, attribute, "https://example.com/ci").is_err());
+    }
+}
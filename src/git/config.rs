@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use url::Url;
+
+use crate::models::policy::PolicyRef;
+
+/// Settings for the git hooks, read from `SISYPHUS_*` environment variables or,
+/// when unset, from the repository's `sisyphus.*` git config:
+///
+/// | git config                | environment                  |                                      |
+/// |---------------------------|------------------------------|--------------------------------------|
+/// | `sisyphus.attestations`   | `SISYPHUS_ATTESTATIONS`      | attestation store file               |
+/// | `sisyphus.policies`       | `SISYPHUS_POLICIES`          | policy repository file               |
+/// | `sisyphus.policy`         | `SISYPHUS_POLICY`            | PURL of the repository's policy      |
+/// | `sisyphus.policyVersion`  | `SISYPHUS_POLICY_VERSION`    | optional semver requirement          |
+/// | `sisyphus.keyring`        | `SISYPHUS_KEYRING`           | keyring file of trusted signing keys |
+#[derive(Debug, Clone)]
+pub struct HookConfig {
+    pub attestations: PathBuf,
+    pub policies: PathBuf,
+    pub policy: PolicyRef,
+    pub keyring: PathBuf,
+}
+
+impl HookConfig {
+    /// Reads the settings from git config or the environment. Like the other git-facing
+    /// helpers this returns a boxed error: any failure is a local misconfiguration that
+    /// retrying will not fix.
+    pub async fn load(repo_dir: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let repo_dir = repo_dir.as_ref();
+        let required = |key: &'static str, value: Option<String>| value.ok_or_else(|| format!("Missing git config sisyphus.{}", key));
+
+        let attestations = required("attestations", setting(repo_dir, "attestations", "SISYPHUS_ATTESTATIONS").await?)?;
+        let policies = required("policies", setting(repo_dir, "policies", "SISYPHUS_POLICIES").await?)?;
+        let purl = required("policy", setting(repo_dir, "policy", "SISYPHUS_POLICY").await?)?;
+        let keyring = required("keyring", setting(repo_dir, "keyring", "SISYPHUS_KEYRING").await?)?;
+
+        let policy = match setting(repo_dir, "policyVersion", "SISYPHUS_POLICY_VERSION").await? {
+            Some(req) => PolicyRef::matching(&purl, &req)?,
+            None => PolicyRef::latest(&purl),
+        };
+
+        Ok(Self {
+            attestations: attestations.into(),
+            policies: policies.into(),
+            policy,
+            keyring: keyring.into(),
+        })
+    }
+}
+
+/// Settings for the post-commit hook, read like `HookConfig`:
+///
+/// | git config                | environment                  |                                      |
+/// |---------------------------|------------------------------|--------------------------------------|
+/// | `sisyphus.attestations`   | `SISYPHUS_ATTESTATIONS`      | attestation store file               |
+/// | `sisyphus.signingKey`     | `SISYPHUS_SIGNING_KEY`       | file holding a hex Ed25519 seed      |
+/// | `sisyphus.issuer`         | `SISYPHUS_ISSUER`            | optional, defaults to the key ID     |
+/// | `sisyphus.baseUri`        | `SISYPHUS_BASE_URI`          | optional base of attestation URIs    |
+#[derive(Debug, Clone)]
+pub struct SigningConfig {
+    pub attestations: PathBuf,
+    pub signing_key: PathBuf,
+    pub issuer: Option<String>,
+    pub base_uri: Option<Url>,
+}
+
+impl SigningConfig {
+    pub async fn load(repo_dir: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let repo_dir = repo_dir.as_ref();
+        let required = |key: &'static str, value: Option<String>| value.ok_or_else(|| format!("Missing git config sisyphus.{}", key));
+
+        Ok(Self {
+            attestations: required("attestations", setting(repo_dir, "attestations", "SISYPHUS_ATTESTATIONS").await?)?.into(),
+            signing_key: required("signingKey", setting(repo_dir, "signingKey", "SISYPHUS_SIGNING_KEY").await?)?.into(),
+            issuer: setting(repo_dir, "issuer", "SISYPHUS_ISSUER").await?,
+            base_uri: setting(repo_dir, "baseUri", "SISYPHUS_BASE_URI").await?.map(|uri| Url::parse(&uri)).transpose()?,
+        })
+    }
+}
+
+async fn setting(repo_dir: &Path, key: &str, env: &str) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    if let Ok(value) = std::env::var(env) {
+        return Ok(Some(value));
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_dir)
+        .args(["config", "--get", &format!("sisyphus.{}", key)])
+        .output()
+        .await?;
+
+    // `git config --get` exits with 1 when the key is not set
+    match output.status.code() {
+        Some(0) => Ok(Some(String::from_utf8(output.stdout)?.trim().to_string())),
+        Some(1) => Ok(None),
+        _ => Err(format!("git config failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into()),
+    }
+}
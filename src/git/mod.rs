@@ -0,0 +1,3 @@
+pub mod commit_attestation;
+pub mod config;
+pub mod pre_receive;
\ No newline at end of file
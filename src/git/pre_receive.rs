@@ -0,0 +1,311 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::process::Command;
+
+use crate::controlplane::controlplane::{Component, ControlPlane, ControlPlaneError};
+use crate::controlplane::report::{ComponentReport, ComponentStatus};
+use crate::models::policy::PolicyRef;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::policy_repository::PolicyRepository;
+use crate::storage::project_registry::ProjectRegistry;
+use crate::verification::dsse_verifier::DssePolicyVerifier;
+use crate::verification::policy_verifier::PolicyVerifier;
+
+/// The object ID git uses for the missing side of a ref creation or deletion.
+pub const ZERO_OID: &str = "0000000000000000000000000000000000000000";
+
+#[derive(Debug, Error)]
+pub enum PushError {
+    #[error("Could not run git: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("git {command} failed: {reason}")]
+    Git { command: &'static str, reason: String },
+    #[error("Invalid commit {commit}: {reason}")]
+    InvalidCommit { commit: String, reason: String },
+    #[error(transparent)]
+    ControlPlane(#[from] ControlPlaneError),
+}
+
+impl PushError {
+    /// Whether pushing again may reach a verdict, as opposed to one that denies.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PushError::Io(_) => true,
+            PushError::ControlPlane(e) => e.is_retryable(),
+            PushError::Git { .. } | PushError::InvalidCommit { .. } => false,
+        }
+    }
+}
+
+/// One `<old> <new> <ref>` line received by a pre-receive hook on stdin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefUpdate {
+    pub old: String,
+    pub new: String,
+    pub refname: String,
+}
+
+impl RefUpdate {
+    pub fn parse_lines(input: &str) -> Result<Vec<Self>, String> {
+        input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [old, new, refname] => Ok(Self {
+                    old: old.to_string(),
+                    new: new.to_string(),
+                    refname: refname.to_string(),
+                }),
+                _ => Err(format!("Invalid ref update line: {}", line)),
+            })
+            .collect()
+    }
+
+    pub fn is_deletion(&self) -> bool {
+        self.new == ZERO_OID
+    }
+}
+
+/// Enumerates the commits a ref update introduces to the repository.
+#[async_trait]
+pub trait CommitSource: Send + Sync {
+    async fn new_commits(&self, update: &RefUpdate) -> Result<Vec<String>, PushError>;
+}
+
+/// Lists new commits with `git rev-list`, oldest first.
+///
+/// Inside a pre-receive hook the pushed objects are quarantined and no ref points at
+/// them yet, so everything reachable from the new tip but not from an existing ref is new.
+pub struct GitRevList {
+    repo_dir: PathBuf,
+}
+
+impl GitRevList {
+    pub fn new(repo_dir: impl Into<PathBuf>) -> Self {
+        Self { repo_dir: repo_dir.into() }
+    }
+}
+
+#[async_trait]
+impl CommitSource for GitRevList {
+    async fn new_commits(&self, update: &RefUpdate) -> Result<Vec<String>, PushError> {
+        if update.is_deletion() {
+            return Ok(Vec::new());
+        }
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_dir)
+            .args(["rev-list", "--reverse", &update.new, "--not", "--all"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(PushError::Git {
+                command: "rev-list",
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect())
+    }
+}
+
+/// The verdict for one pushed commit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitVerdict {
+    pub refname: String,
+    pub commit: String,
+    pub report: ComponentReport,
+}
+
+impl CommitVerdict {
+    /// A one-line explanation suitable for the pusher's terminal, if the commit was rejected.
+    pub fn rejection(&self) -> Option<String> {
+        let reason = match &self.report.status {
+            ComponentStatus::Passed => return None,
+            ComponentStatus::Failed { reason } => reason.clone(),
+            ComponentStatus::Error { message } => message.clone(),
+            ComponentStatus::Skipped => "not verified".to_string(),
+        };
+        Some(format!("{} {}: {}", self.refname, self.commit, reason))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushReport {
+    pub accepted: bool,
+    pub commits: Vec<CommitVerdict>,
+}
+
+impl PushReport {
+    pub fn rejections(&self) -> impl Iterator<Item = String> + '_ {
+        self.commits.iter().filter_map(|c| c.rejection())
+    }
+}
+
+/// Checks that every commit introduced by a push carries a source attestation that
+/// satisfies the repository's policy.
+///
+/// Each commit is verified as a component pinned to its `gitCommit` digest, so the
+/// attestation's subject must name the commit and the policy's selection strategy
+/// decides between several attestations of the same commit. The control plane checks
+/// attestations with a `DssePolicyVerifier`, so an attestation only counts when its
+/// envelope verifies under a trusted key whose issuer the policy allows.
+pub struct PushVerifier<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier, R: ProjectRegistry> {
+    control_plane: Arc<ControlPlane<P, A, DssePolicyVerifier<V>, R>>,
+    policy: PolicyRef,
+}
+
+impl<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier, R: ProjectRegistry> PushVerifier<P, A, V, R> {
+    pub fn new(control_plane: Arc<ControlPlane<P, A, DssePolicyVerifier<V>, R>>, policy: PolicyRef) -> Self {
+        Self { control_plane, policy }
+    }
+
+    pub async fn verify_push<C: CommitSource>(&self, updates: &[RefUpdate], commits: &C) -> Result<PushReport, PushError> {
+        let mut seen = HashSet::new();
+        let mut verdicts = Vec::new();
+
+        for update in updates {
+            for commit in commits.new_commits(update).await? {
+                // A commit pushed to several refs at once only needs checking once
+                if !seen.insert(commit.clone()) {
+                    continue;
+                }
+
+                let component = Component {
+                    name: commit.clone(),
+                    version: commit.clone(),
+                    policy: self.policy.clone(),
+                    digests: BTreeMap::from([("gitCommit".to_string(), commit.to_lowercase())]),
+                    selection: None,
+                };
+                component.validate().map_err(|reason| PushError::InvalidCommit { commit: commit.clone(), reason })?;
+
+                let report = self.control_plane.verify_component(&component).await?;
+                verdicts.push(CommitVerdict {
+                    refname: update.refname.clone(),
+                    commit,
+                    report,
+                });
+            }
+        }
+
+        Ok(PushReport {
+            accepted: verdicts.iter().all(|v| v.report.status.is_passed()),
+            commits: verdicts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use ed25519_dalek::SigningKey;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    use crate::models::attestation::Attestation;
+    use crate::models::dsse::{Envelope, Keyring};
+    use crate::models::policy::{Policy, PolicyRules};
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+    use crate::storage::policy_repository::InMemoryPolicyRepository;
+    use crate::verification::policy_verifier::SourcePolicyVerifier;
+
+    struct FixedCommits(HashMap<String, Vec<String>>);
+
+    #[async_trait]
+    impl CommitSource for FixedCommits {
+        async fn new_commits(&self, update: &RefUpdate) -> Result<Vec<String>, PushError> {
+            Ok(self.0.get(&update.refname).cloned().unwrap_or_default())
+        }
+    }
+
+    fn commit_attestation(id: &str, commit: &str, signing_key: &SigningKey, age_days: i64) -> Arc<Attestation> {
+        let attested_at = Utc::now() - Duration::days(age_days);
+        let statement = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "acme/app", "digest": { "gitCommit": commit } }],
+            "predicateType": "https://example.com/commit/v1",
+            "predicate": { "attestedAt": attested_at }
+        });
+        Arc::new(Attestation {
+            id: id.to_string(),
+            issuer: "commit-signer".to_string(),
+            timestamp: attested_at,
+            envelope: Some(Envelope::sign(&statement, signing_key).unwrap()),
+            content: statement,
+            verification_material: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_push_rejected_per_commit() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let signer = SigningKey::from_bytes(&[5; 32]);
+        let keyring = Keyring::new().with_issuer_key(signer.verifying_key(), "commit-signer");
+        let control_plane = Arc::new(ControlPlane::new(
+            policy_repo.clone(),
+            attestation_storage.clone(),
+            Arc::new(DssePolicyVerifier::new(keyring, SourcePolicyVerifier)),
+        ));
+
+        policy_repo.add_policy(Policy::new(
+            "pkg:github/acme/app".to_string(),
+            "1.0.0".to_string(),
+            PolicyRules {
+                allowed_issuers: vec!["commit-signer".to_string()].into_iter().collect(),
+                max_age_days: 7,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 0,
+            },
+        ).unwrap()).await.unwrap();
+
+        let (signed, stale, unsigned, forged) = ("a".repeat(40), "b".repeat(40), "c".repeat(40), "e".repeat(40));
+        attestation_storage.store_attestation(commit_attestation("signed", &signed, &signer, 0)).await.unwrap();
+        attestation_storage.store_attestation(commit_attestation("stale", &stale, &signer, 30)).await.unwrap();
+        // Claims the trusted issuer but is signed by a key outside the keyring
+        attestation_storage.store_attestation(commit_attestation("forged", &forged, &SigningKey::from_bytes(&[6; 32]), 0)).await.unwrap();
+
+        let input = format!(
+            "{zero} {signed} refs/heads/feature\n{old} {unsigned} refs/heads/main\n{old} {zero} refs/heads/gone\n",
+            zero = ZERO_OID,
+            old = "d".repeat(40),
+        );
+        let updates = RefUpdate::parse_lines(&input).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert!(updates[2].is_deletion());
+
+        let commits = FixedCommits(HashMap::from([
+            ("refs/heads/feature".to_string(), vec![signed.clone()]),
+            ("refs/heads/main".to_string(), vec![signed.clone(), stale.clone(), unsigned.clone(), forged.clone()]),
+        ]));
+
+        let verifier = PushVerifier::new(control_plane, PolicyRef::latest("pkg:github/acme/app"));
+        let report = verifier.verify_push(&updates, &commits).await.unwrap();
+
+        assert!(!report.accepted);
+        assert_eq!(report.commits.len(), 4);
+        assert_eq!(report.commits[0].report.decided_by, vec!["signed".to_string()]);
+
+        let rejections: Vec<String> = report.rejections().collect();
+        assert_eq!(rejections.len(), 3);
+        assert!(rejections[0].starts_with(&format!("refs/heads/main {}", stale)));
+        assert!(rejections[0].contains("max_age_days"));
+        assert!(rejections[1].contains("No matching attestation"));
+        assert!(rejections[2].starts_with(&format!("refs/heads/main {}", forged)));
+        assert!(rejections[2].contains("allowed_issuers"));
+
+        // A malformed commit ID from the commit source denies rather than asking for a retry
+        let garbled = FixedCommits(HashMap::from([("refs/heads/feature".to_string(), vec!["not-a-commit".to_string()])]));
+        let error = verifier.verify_push(&updates[..1], &garbled).await.unwrap_err();
+        assert!(matches!(error, PushError::InvalidCommit { .. }), "{}", error);
+        assert!(!error.is_retryable());
+    }
+}
@@ -29,24 +29,32 @@ pub mod cbp;
 pub mod storage;
 pub mod verification;
 pub mod controlplane;
+pub mod git;
+pub mod admission;
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "cli")]
+pub mod cli;
 
 use std::fmt::{self, Display};
 
 use thiserror::Error;
-struct Uninitialized;
+use models::summary_scai::{SummaryScai, SummaryScaiPredicateAttributesItemAttribute};
+pub struct Uninitialized;
 
-struct Unverified;
-struct DevelopmentEnvironmentVerified;
-struct SourceVerified;
-struct BuildVerified;
-struct PackageVerified;
-struct DeployVerified;
-struct FullyVerified {
+pub struct Unverified;
+pub struct DevelopmentEnvironmentVerified;
+pub struct SourceVerified;
+pub struct BuildVerified;
+pub struct PackageVerified;
+pub struct DeployVerified;
+pub struct FullyVerified {
     pub digest: String,
     pub version: String,
 }
 
-trait VerifiedState {}
+pub trait VerifiedState {}
 
 impl VerifiedState for Unverified {}
 impl VerifiedState for DevelopmentEnvironmentVerified {}
@@ -56,12 +64,12 @@ impl VerifiedState for PackageVerified {}
 impl VerifiedState for DeployVerified {}
 impl VerifiedState for FullyVerified {}
 
-struct SDLCRelease<VerifiedState> {
-    name: String,
-    state: VerifiedState,
+pub struct SDLCRelease<VerifiedState> {
+    pub name: String,
+    pub state: VerifiedState,
 }
 
-/*impl SDLCRelease<Unverified> {
+impl SDLCRelease<Unverified> {
     pub fn new(name: String) -> Self {
         SDLCRelease {
             name,
@@ -78,34 +86,37 @@ struct SDLCRelease<VerifiedState> {
             &SummaryScaiPredicateAttributesItemAttribute::PassedDeploy,
         ];
         let verified = verified_enum.iter().all(|&x| summary_scai.predicate.attributes.iter().any(|y| y.attribute == *x));
-        let resource_descriptor = summary_scai.subject.get(0).unwrap();
-        let variant = match resource_descriptor {
-            models::summary_scai::ResourceDescriptor::Variant0 { annotations, content, digest, download_location, media_type, name, uri } => todo!(),
-            models::summary_scai::ResourceDescriptor::Variant1 { annotations, content, digest, download_location, media_type, name, uri } => todo!(),
-            models::summary_scai::ResourceDescriptor::Variant2 { annotations, content, digest, download_location, media_type, name, uri } => todo!(),
-        }
-
-        if verified {
-            Ok(SDLCRelease {
-                name: self.name,
-                state: FullyVerified {
-                    digest: summary_scai.subject.get(0).unwrap().digest.sha256.clone(),
-                    version: "1".into(),
-                },
-            })
-        } else {
-            Err(VerificationError::MissingPassed {
+        if !verified {
+            return Err(VerificationError::MissingPassed {
                 attributes: verified_enum.iter().map(|x| x.to_string()).collect(),
-            })
+            });
         }
+
+        let subject = summary_scai.subject.first();
+        let digest = subject
+            .and_then(|s| s.digest.get("sha256"))
+            .ok_or(VerificationError::MissingSubjectDigest)?;
+        let version = subject
+            .and_then(|s| s.annotations.get("version"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("1");
+
+        Ok(SDLCRelease {
+            name: self.name,
+            state: FullyVerified {
+                digest: digest.clone(),
+                version: version.to_string(),
+            },
+        })
     }
-}*/
+}
 
 #[derive(Error, Debug)]
-enum VerificationError {
+pub enum VerificationError {
     MissingPassed {
         attributes: Vec<String>,
     },
+    MissingSubjectDigest,
 }
 
 impl Display for VerificationError {
@@ -114,10 +125,12 @@ impl Display for VerificationError {
             VerificationError::MissingPassed { attributes } => {
                 write!(f, "Missing passed attributes: {:?}", attributes)
             }
+            VerificationError::MissingSubjectDigest => {
+                write!(f, "Summary subject has no sha256 digest")
+            }
         }
     }
 }
-/*
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,11 +138,10 @@ mod tests {
     #[test]
     fn test_deserialize_summary_scai() {
         let json = include_str!("../examples/summary_scai.json");
-        let summary_scai: Result<SummaryScai, Box<Error>> = serde_json::from_str(json).map_err(|e| Box::new(e) as Box<dyn Error>);
-        if let Err(err) = &summary_scai {
-            eprintln!("Error: {}", err);
-        }
-        assert!(summary_scai.is_ok());
+        let summary_scai: SummaryScai = serde_json::from_str(json).unwrap();
+        assert_eq!(summary_scai.subject[0].digest["sha256"], "a1b2c3d4e5f6...");
+
+        let release = SDLCRelease::new("example-software-artifact".to_string()).verify(summary_scai).unwrap();
+        assert_eq!(release.state.digest, "a1b2c3d4e5f6...");
     }
 }
-    */
\ No newline at end of file
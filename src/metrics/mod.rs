@@ -0,0 +1,173 @@
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::controlplane::report::ComponentStatus;
+
+/// Component verdicts, by `outcome`: `passed`, `failed` or `error`.
+pub const VERIFICATIONS: &str = "sisyphus_verifications_total";
+/// Reasons components did not pass, by `reason`.
+pub const VERIFICATION_FAILURES: &str = "sisyphus_verification_failures_total";
+/// Failed policy rules of decisive attestations, by `rule`.
+pub const POLICY_RULE_FAILURES: &str = "sisyphus_policy_rule_failures_total";
+pub const VERIFICATION_DURATION: &str = "sisyphus_verification_duration_seconds";
+/// Time from an event being emitted to it being handled.
+pub const EVENT_LAG: &str = "sisyphus_event_lag_seconds";
+/// Storage calls, by `store` and `operation`.
+pub const STORAGE_LATENCY: &str = "sisyphus_storage_operation_duration_seconds";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Histogram,
+}
+
+pub struct MetricDescription {
+    pub name: &'static str,
+    pub kind: MetricKind,
+    pub help: &'static str,
+}
+
+/// Every metric the crate records.
+pub const DESCRIPTIONS: [MetricDescription; 6] = [
+    MetricDescription { name: VERIFICATIONS, kind: MetricKind::Counter, help: "Component verifications by outcome." },
+    MetricDescription { name: VERIFICATION_FAILURES, kind: MetricKind::Counter, help: "Component verifications that did not pass, by reason." },
+    MetricDescription { name: POLICY_RULE_FAILURES, kind: MetricKind::Counter, help: "Policy rules failed by decisive attestations." },
+    MetricDescription { name: VERIFICATION_DURATION, kind: MetricKind::Histogram, help: "Time taken to verify a component." },
+    MetricDescription { name: EVENT_LAG, kind: MetricKind::Histogram, help: "Delay between an event's timestamp and its handling." },
+    MetricDescription { name: STORAGE_LATENCY, kind: MetricKind::Histogram, help: "Latency of storage operations." },
+];
+
+/// Upper bounds in seconds of the histogram buckets.
+pub const BUCKETS: [f64; 14] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+pub type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    /// Per-bucket counts, not cumulative, aligned with `BUCKETS`.
+    pub buckets: [u64; BUCKETS.len()],
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(i) = BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[i] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Process-wide counters and histograms, keyed by metric name and labels.
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<(&'static str, Labels), u64>>,
+    histograms: Mutex<BTreeMap<(&'static str, Labels), Histogram>>,
+}
+
+impl Metrics {
+    pub fn global() -> &'static Metrics {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS.get_or_init(Metrics::default)
+    }
+
+    pub fn increment(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        *counters.entry((name, owned(labels))).or_default() += 1;
+    }
+
+    pub fn observe(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+        let mut histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        histograms.entry((name, owned(labels))).or_default().observe(value);
+    }
+
+    pub fn counter(&self, name: &'static str, labels: &[(&'static str, &str)]) -> u64 {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        counters.get(&(name, owned(labels))).copied().unwrap_or_default()
+    }
+
+    pub fn histogram(&self, name: &'static str, labels: &[(&'static str, &str)]) -> Histogram {
+        let histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        histograms.get(&(name, owned(labels))).cloned().unwrap_or_default()
+    }
+
+    pub fn counters(&self) -> Vec<((&'static str, Labels), u64)> {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        counters.iter().map(|(key, value)| (key.clone(), *value)).collect()
+    }
+
+    pub fn histograms(&self) -> Vec<((&'static str, Labels), Histogram)> {
+        let histograms = self.histograms.lock().unwrap_or_else(|e| e.into_inner());
+        histograms.iter().map(|(key, value)| (key.clone(), value.clone())).collect()
+    }
+}
+
+fn owned(labels: &[(&'static str, &str)]) -> Labels {
+    labels.iter().map(|(name, value)| (*name, value.to_string())).collect()
+}
+
+/// Counts a component verdict and how long it took.
+pub fn record_verification(status: &ComponentStatus, duration_secs: f64) {
+    let metrics = Metrics::global();
+    let outcome = match status {
+        ComponentStatus::Passed => "passed",
+        ComponentStatus::Failed { .. } => "failed",
+        ComponentStatus::Error { .. } => "error",
+        ComponentStatus::Skipped => return,
+    };
+
+    metrics.increment(VERIFICATIONS, &[("outcome", outcome)]);
+    metrics.observe(VERIFICATION_DURATION, &[], duration_secs);
+    if let ComponentStatus::Error { .. } = status {
+        metrics.increment(VERIFICATION_FAILURES, &[("reason", "error")]);
+    }
+}
+
+pub fn record_event_lag(emitted_at: DateTime<Utc>) {
+    let lag = (Utc::now() - emitted_at).num_milliseconds().max(0) as f64 / 1000.0;
+    Metrics::global().observe(EVENT_LAG, &[], lag);
+}
+
+/// Awaits a storage call and records its latency, whether or not it succeeds.
+pub async fn timed<F: Future>(store: &'static str, operation: &'static str, call: F) -> F::Output {
+    let start = Instant::now();
+    let output = call.await;
+    Metrics::global().observe(STORAGE_LATENCY, &[("store", store), ("operation", operation)], start.elapsed().as_secs_f64());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_counters_and_histograms() {
+        let metrics = Metrics::default();
+        metrics.increment(VERIFICATIONS, &[("outcome", "passed")]);
+        metrics.increment(VERIFICATIONS, &[("outcome", "passed")]);
+        metrics.increment(VERIFICATIONS, &[("outcome", "failed")]);
+        assert_eq!(metrics.counter(VERIFICATIONS, &[("outcome", "passed")]), 2);
+        assert_eq!(metrics.counter(VERIFICATIONS, &[("outcome", "error")]), 0);
+
+        metrics.observe(STORAGE_LATENCY, &[("store", "policy")], 0.003);
+        metrics.observe(STORAGE_LATENCY, &[("store", "policy")], 0.2);
+        metrics.observe(STORAGE_LATENCY, &[("store", "policy")], 1000.0);
+        let histogram = metrics.histogram(STORAGE_LATENCY, &[("store", "policy")]);
+        assert_eq!(histogram.count, 3);
+        assert_eq!(histogram.buckets[0], 1);
+        assert_eq!(histogram.buckets[5], 1);
+        assert_eq!(histogram.buckets.iter().sum::<u64>(), 2);
+
+        let before = Metrics::global().histogram(STORAGE_LATENCY, &[("store", "test"), ("operation", "noop")]).count;
+        assert_eq!(timed("test", "noop", async { 7 }).await, 7);
+        assert_eq!(Metrics::global().histogram(STORAGE_LATENCY, &[("store", "test"), ("operation", "noop")]).count, before + 1);
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use crate::metrics::{Labels, MetricKind, Metrics, BUCKETS, DESCRIPTIONS};
+
+/// The content type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Renders the metrics in the Prometheus text exposition format.
+pub fn render(metrics: &Metrics) -> String {
+    let counters = metrics.counters();
+    let histograms = metrics.histograms();
+    let mut out = String::new();
+
+    for description in &DESCRIPTIONS {
+        let kind = match description.kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Histogram => "histogram",
+        };
+        let _ = writeln!(out, "# HELP {} {}", description.name, description.help);
+        let _ = writeln!(out, "# TYPE {} {}", description.name, kind);
+
+        for ((_, labels), value) in counters.iter().filter(|((name, _), _)| *name == description.name) {
+            let _ = writeln!(out, "{}{} {}", description.name, label_set(labels, None), value);
+        }
+
+        for ((_, labels), histogram) in histograms.iter().filter(|((name, _), _)| *name == description.name) {
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "{}_bucket{} {}", description.name, label_set(labels, Some(&bound.to_string())), cumulative);
+            }
+            let _ = writeln!(out, "{}_bucket{} {}", description.name, label_set(labels, Some("+Inf")), histogram.count);
+            let _ = writeln!(out, "{}_sum{} {}", description.name, label_set(labels, None), histogram.sum);
+            let _ = writeln!(out, "{}_count{} {}", description.name, label_set(labels, None), histogram.count);
+        }
+    }
+
+    out
+}
+
+fn label_set(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels.iter().map(|(name, value)| format!("{}=\"{}\"", name, escape(value))).collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{STORAGE_LATENCY, VERIFICATION_FAILURES};
+
+    #[test]
+    fn test_render_text_format() {
+        let metrics = Metrics::default();
+        metrics.increment(VERIFICATION_FAILURES, &[("reason", "policy \"violation\"")]);
+        metrics.observe(STORAGE_LATENCY, &[("store", "attestation"), ("operation", "list")], 0.02);
+        metrics.observe(STORAGE_LATENCY, &[("store", "attestation"), ("operation", "list")], 0.7);
+
+        let text = render(&metrics);
+        assert!(text.contains("# TYPE sisyphus_verification_failures_total counter\n"));
+        assert!(text.contains("sisyphus_verification_failures_total{reason=\"policy \\\"violation\\\"\"} 1\n"));
+        assert!(text.contains("# TYPE sisyphus_storage_operation_duration_seconds histogram\n"));
+        assert!(text.contains("sisyphus_storage_operation_duration_seconds_bucket{store=\"attestation\",operation=\"list\",le=\"0.025\"} 1\n"));
+        assert!(text.contains("sisyphus_storage_operation_duration_seconds_bucket{store=\"attestation\",operation=\"list\",le=\"1\"} 2\n"));
+        assert!(text.contains("sisyphus_storage_operation_duration_seconds_bucket{store=\"attestation\",operation=\"list\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("sisyphus_storage_operation_duration_seconds_count{store=\"attestation\",operation=\"list\"} 2\n"));
+    }
+}
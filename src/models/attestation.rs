@@ -2,10 +2,31 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::models::dsse::Envelope;
+use crate::models::sigstore::{Bundle, VerificationMaterial, BUNDLE_MEDIA_TYPE};
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Attestation {
     pub id: String,
     pub issuer: String,
     pub timestamp: DateTime<Utc>,
+    /// The in-toto statement.
     pub content: Value,
+    /// The signed envelope the statement was taken from, if it was submitted signed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<Envelope>,
+    /// The certificate and transparency log entries of a Sigstore-signed envelope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_material: Option<VerificationMaterial>,
+}
+
+impl Attestation {
+    /// Reassembles the Sigstore bundle the attestation was imported from, if any.
+    pub fn bundle(&self) -> Option<Bundle> {
+        Some(Bundle {
+            media_type: BUNDLE_MEDIA_TYPE.to_string(),
+            verification_material: self.verification_material.clone()?,
+            dsse_envelope: self.envelope.clone()?,
+        })
+    }
 }
\ No newline at end of file
@@ -0,0 +1,152 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::controlplane::report::ComponentStatus;
+use crate::models::attestation::Attestation;
+use crate::models::canonical::{canonical_sha256, to_canonical_vec};
+
+/// The `previous_hash` of the first entry in a log.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// The artifact a verification decision was made about.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditSubject {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    pub component: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub digests: BTreeMap<String, String>,
+}
+
+/// An attestation that was evaluated for a decision, pinned by the digest of its content.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditedAttestation {
+    pub id: String,
+    pub uri: String,
+    /// Hex SHA-256 of the attestation's canonical JSON.
+    pub sha256: String,
+}
+
+impl AuditedAttestation {
+    pub fn new(uri: &str, attestation: &Attestation) -> Self {
+        Self {
+            id: attestation.id.clone(),
+            uri: uri.to_string(),
+            sha256: canonical_sha256(attestation).unwrap_or_default(),
+        }
+    }
+}
+
+/// A verification decision as recorded in the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub decided_at: DateTime<Utc>,
+    pub subject: AuditSubject,
+    /// The resolved policy as `purl@version`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attestations: Vec<AuditedAttestation>,
+    /// IDs of the attestations that settled the verdict.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decided_by: Vec<String>,
+    pub verdict: ComponentStatus,
+    /// The authenticated principal that asked for the verification, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_by: Option<String>,
+}
+
+/// A record chained to its predecessor: each entry's hash covers its sequence number,
+/// the previous entry's hash and the record, so altering, dropping or reordering any
+/// entry breaks every hash after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    pub previous_hash: String,
+    pub hash: String,
+    pub record: AuditRecord,
+}
+
+impl AuditEntry {
+    /// Appends a record after the given entry, or starts a log without one.
+    pub fn chain(previous: Option<&AuditEntry>, record: AuditRecord) -> Self {
+        let (sequence, previous_hash) = match previous {
+            Some(entry) => (entry.sequence + 1, entry.hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        let hash = entry_hash(sequence, &previous_hash, &record);
+
+        Self {
+            sequence,
+            previous_hash,
+            hash,
+            record,
+        }
+    }
+
+    pub fn is_intact(&self) -> bool {
+        entry_hash(self.sequence, &self.previous_hash, &self.record) == self.hash
+    }
+}
+
+fn entry_hash(sequence: u64, previous_hash: &str, record: &AuditRecord) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(sequence.to_be_bytes());
+    hasher.update(previous_hash.as_bytes());
+    hasher.update(to_canonical_vec(record).unwrap_or_default());
+    hex::encode(hasher.finalize())
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum AuditError {
+    #[error("Audit entry {sequence} does not match its hash")]
+    TamperedEntry { sequence: u64 },
+    #[error("Audit entry {sequence} does not link to the entry before it")]
+    BrokenLink { sequence: u64 },
+    #[error("Expected audit entry {expected}, found {found}")]
+    SequenceGap { expected: u64, found: u64 },
+}
+
+/// A contiguous range of the log, verifiable on its own against the hash of the entry
+/// before it and, when the range reaches the end of the log, against the published head.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditExport {
+    /// The hash the first entry links to.
+    pub anchor_hash: String,
+    /// The hash of the log's newest entry at export time.
+    pub head_hash: String,
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditExport {
+    /// Checks the range links up from its anchor and returns the hash of its last entry.
+    pub fn verify(&self) -> Result<String, AuditError> {
+        verify_chain(&self.anchor_hash, &self.entries)
+    }
+}
+
+/// Checks that entries are intact, consecutive and linked, starting from `anchor_hash`,
+/// and returns the hash of the last one.
+pub fn verify_chain(anchor_hash: &str, entries: &[AuditEntry]) -> Result<String, AuditError> {
+    let mut previous_hash = anchor_hash.to_string();
+    let first = entries.first().map(|e| e.sequence).unwrap_or_default();
+
+    for (expected, entry) in (first..).zip(entries) {
+        if entry.sequence != expected {
+            return Err(AuditError::SequenceGap { expected, found: entry.sequence });
+        }
+        if entry.previous_hash != previous_hash {
+            return Err(AuditError::BrokenLink { sequence: entry.sequence });
+        }
+        if !entry.is_intact() {
+            return Err(AuditError::TamperedEntry { sequence: entry.sequence });
+        }
+        previous_hash = entry.hash.clone();
+    }
+
+    Ok(previous_hash)
+}
@@ -0,0 +1,242 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+/// The DSSE payload type of an in-toto statement.
+pub const IN_TOTO_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// A signature over the pre-authentication encoding of an envelope's payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyid: String,
+    /// Base64-encoded signature bytes.
+    pub sig: String,
+}
+
+/// A Dead Simple Signing Envelope wrapping a serialized in-toto statement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    #[serde(rename = "payloadType")]
+    pub payload_type: String,
+    /// Base64-encoded payload bytes.
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+impl Envelope {
+    /// Serializes the statement and signs it with an Ed25519 key.
+    pub fn sign(statement: &Value, signing_key: &SigningKey) -> Result<Self, serde_json::Error> {
+        let payload = serde_json::to_vec(statement)?;
+        let signature = signing_key.sign(&pae(IN_TOTO_PAYLOAD_TYPE, &payload));
+
+        Ok(Self {
+            payload_type: IN_TOTO_PAYLOAD_TYPE.to_string(),
+            payload: STANDARD.encode(&payload),
+            signatures: vec![EnvelopeSignature {
+                keyid: key_id(&signing_key.verifying_key()),
+                sig: STANDARD.encode(signature.to_bytes()),
+            }],
+        })
+    }
+
+    /// Decodes the payload without checking any signature.
+    pub fn statement(&self) -> Result<Value, String> {
+        let payload = STANDARD.decode(&self.payload).map_err(|e| format!("Invalid envelope payload: {}", e))?;
+        serde_json::from_slice(&payload).map_err(|e| format!("Invalid envelope statement: {}", e))
+    }
+
+    /// Returns the statement if at least one signature verifies under the given key.
+    pub fn verify(&self, verifying_key: &VerifyingKey) -> Result<Value, String> {
+        let payload = STANDARD.decode(&self.payload).map_err(|e| format!("Invalid envelope payload: {}", e))?;
+        let message = pae(&self.payload_type, &payload);
+
+        let verified = self.signatures.iter().any(|s| {
+            STANDARD
+                .decode(&s.sig)
+                .ok()
+                .and_then(|bytes| Signature::from_slice(&bytes).ok())
+                .is_some_and(|signature| verifying_key.verify(&message, &signature).is_ok())
+        });
+
+        if !verified {
+            return Err(format!("No envelope signature verifies under key {}", key_id(verifying_key)));
+        }
+
+        serde_json::from_slice(&payload).map_err(|e| format!("Invalid envelope statement: {}", e))
+    }
+}
+
+/// A statement whose envelope verified under a key in a `Keyring`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedEnvelope {
+    pub key_id: String,
+    /// The issuer the verifying key signs for.
+    pub issuer: String,
+    pub statement: Value,
+}
+
+/// Ed25519 public keys trusted to sign envelopes, each for one issuer.
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    keys: BTreeMap<String, (VerifyingKey, String)>,
+}
+
+impl Keyring {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts the key for its default issuer, `ed25519:<key ID>`.
+    pub fn with_key(self, verifying_key: VerifyingKey) -> Self {
+        let issuer = key_issuer(&verifying_key);
+        self.with_issuer_key(verifying_key, issuer)
+    }
+
+    /// Trusts the key to sign for the given issuer.
+    pub fn with_issuer_key(mut self, verifying_key: VerifyingKey, issuer: impl Into<String>) -> Self {
+        self.keys.insert(key_id(&verifying_key), (verifying_key, issuer.into()));
+        self
+    }
+
+    /// Parses one key per line: a hex Ed25519 public key, optionally followed by the
+    /// issuer it signs for. Blank lines and lines starting with `#` are skipped.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut keyring = Self::new();
+        for line in input.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (key, issuer) = match line.split_once(char::is_whitespace) {
+                Some((key, issuer)) => (key, Some(issuer.trim())),
+                None => (line, None),
+            };
+            let bytes: [u8; 32] = hex::decode(key)
+                .ok()
+                .and_then(|b| b.try_into().ok())
+                .ok_or_else(|| format!("Invalid Ed25519 public key: {}", key))?;
+            let verifying_key = VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid Ed25519 public key {}: {}", key, e))?;
+            keyring = match issuer {
+                Some(issuer) => keyring.with_issuer_key(verifying_key, issuer),
+                None => keyring.with_key(verifying_key),
+            };
+        }
+        Ok(keyring)
+    }
+
+    /// Loads a keyring file in the format read by `parse`.
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self::parse(&tokio::fs::read_to_string(path).await?)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Returns the statement and issuer of the first trusted key that one of the
+    /// envelope's signatures verifies under. Signatures naming an unknown key ID are
+    /// ignored and ones without a key ID are tried against every key.
+    pub fn verify(&self, envelope: &Envelope) -> Result<VerifiedEnvelope, String> {
+        let candidates: BTreeMap<&String, &(VerifyingKey, String)> = envelope
+            .signatures
+            .iter()
+            .flat_map(|s| match s.keyid.as_str() {
+                "" => self.keys.iter().collect::<Vec<_>>(),
+                keyid => self.keys.get_key_value(keyid).into_iter().collect(),
+            })
+            .collect();
+
+        for (key_id, (verifying_key, issuer)) in candidates {
+            if let Ok(statement) = envelope.verify(verifying_key) {
+                return Ok(VerifiedEnvelope {
+                    key_id: key_id.clone(),
+                    issuer: issuer.clone(),
+                    statement,
+                });
+            }
+        }
+        Err("No envelope signature verifies under a trusted key".to_string())
+    }
+}
+
+/// The DSSE pre-authentication encoding, `DSSEv1 <len> <type> <len> <payload>`.
+pub fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut encoded = format!("DSSEv1 {} {} {} ", payload_type.len(), payload_type, payload.len()).into_bytes();
+    encoded.extend_from_slice(payload);
+    encoded
+}
+
+/// Identifies a public key by the hex SHA-256 of its raw bytes.
+pub fn key_id(verifying_key: &VerifyingKey) -> String {
+    hex::encode(Sha256::digest(verifying_key.as_bytes()))
+}
+
+/// The issuer a key signs for unless configured otherwise, `ed25519:<key ID>`.
+pub fn key_issuer(verifying_key: &VerifyingKey) -> String {
+    format!("ed25519:{}", key_id(verifying_key))
+}
+
+/// When a statement says it was made: the `attestedAt` of the predicate, or when a SLSA
+/// provenance build finished. Unlike an attestation's timestamp this is covered by the
+/// envelope's signature.
+pub fn statement_time(statement: &Value) -> Option<DateTime<Utc>> {
+    [
+        &statement["predicate"]["attestedAt"],
+        &statement["predicate"]["runDetails"]["metadata"]["finishedOn"],
+        &statement["predicate"]["metadata"]["buildFinishedOn"],
+    ]
+    .into_iter()
+    .find_map(|v| v.as_str()?.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_envelope_sign_and_verify() {
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let statement = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "acme/app", "digest": { "gitCommit": "a".repeat(40) } }]
+        });
+
+        let envelope = Envelope::sign(&statement, &signing_key).unwrap();
+        assert_eq!(envelope.signatures[0].keyid, key_id(&signing_key.verifying_key()));
+        assert_eq!(envelope.verify(&signing_key.verifying_key()).unwrap(), statement);
+
+        let other_key = SigningKey::from_bytes(&[8; 32]);
+        assert!(envelope.verify(&other_key.verifying_key()).is_err());
+
+        let mut tampered = envelope.clone();
+        tampered.payload = STANDARD.encode(serde_json::to_vec(&json!({ "subject": [] })).unwrap());
+        assert!(tampered.verify(&signing_key.verifying_key()).is_err());
+        assert_eq!(tampered.statement().unwrap(), json!({ "subject": [] }));
+    }
+
+    #[test]
+    fn test_keyring_verifies_trusted_keys() {
+        let trusted = SigningKey::from_bytes(&[7; 32]);
+        let statement = json!({ "subject": [] });
+        let keyring = Keyring::parse(&format!(
+            "# release signers\n{} release-bot\n\n{}\n",
+            hex::encode(trusted.verifying_key().as_bytes()),
+            hex::encode(SigningKey::from_bytes(&[8; 32]).verifying_key().as_bytes()),
+        )).unwrap();
+
+        let verified = keyring.verify(&Envelope::sign(&statement, &trusted).unwrap()).unwrap();
+        assert_eq!(verified.issuer, "release-bot");
+        assert_eq!(verified.statement, statement);
+
+        // A signature claiming a trusted key ID does not verify under that key
+        let mut forged = Envelope::sign(&statement, &SigningKey::from_bytes(&[9; 32])).unwrap();
+        forged.signatures[0].keyid = key_id(&trusted.verifying_key());
+        assert!(keyring.verify(&forged).is_err());
+        assert!(Keyring::parse("not-a-key").is_err());
+    }
+}
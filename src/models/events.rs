@@ -45,6 +45,35 @@ pub enum CDEventType {
         policy_id: String,
         version: String,
     },
+    ProjectCreated {
+        project_name: String,
+    },
+    ProjectUpdated {
+        project_name: String,
+    },
+    ProjectDeleted {
+        project_name: String,
+    },
+    ComponentAdded {
+        project_name: String,
+        component_name: String,
+        version: String,
+    },
+    ComponentUpdated {
+        project_name: String,
+        component_name: String,
+        version: String,
+    },
+    ComponentRemoved {
+        project_name: String,
+        component_name: String,
+    },
+    ComponentVersionBumped {
+        project_name: String,
+        component_name: String,
+        previous_version: String,
+        version: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +89,8 @@ pub enum SubjectType {
     Artifact,
     Deployment,
     Policy,
+    Project,
+    Component,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +139,8 @@ mod tests {
             issuer: "trusted_issuer".to_string(),
             timestamp: Utc::now(),
             content: serde_json::json!({}),
+            envelope: None,
+            verification_material: None,
         });
 
         let event = CDEvent::new(
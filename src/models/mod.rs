@@ -1,4 +1,12 @@
 pub mod summary_scai;
+pub mod resource_descriptor;
 pub mod attestation;
 pub mod policy;
-pub mod events;
\ No newline at end of file
+pub mod events;
+pub mod waiver;
+pub mod project;
+pub mod dsse;
+pub mod sigstore;
+pub mod audit;
+pub mod canonical;
+pub mod retention;
@@ -1,23 +1,150 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::fmt;
 use chrono::Duration;
-use semver::Version;
+use semver::{Version, VersionReq};
+use thiserror::Error;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::models::sigstore::{IdentityPattern, SigstoreIdentity, SIGSTORE_ISSUER_PREFIX};
+use crate::storage::error::StorageError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Policy {
     pub purl: String,
     pub version: String,
     pub rules: PolicyRules,
+    /// How to choose among several attestations for the same subject, unless the
+    /// component being verified sets its own strategy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<SelectionStrategy>,
+}
+
+/// Decides which attestation(s) settle a verdict when several match the same subject.
+/// Candidates are ordered newest first by timestamp, with ties broken by attestation ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionStrategy {
+    /// The newest attestation that is authentic and current, i.e. passes the
+    /// `allowed_issuers` and `max_age_days` rules, decides.
+    NewestValid,
+    /// The newest attestation decides.
+    #[default]
+    NewestOverall,
+    /// Every candidate must pass.
+    AllMustPass,
+    /// At least one candidate must pass.
+    AnyMayPass,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PolicyRules {
+    /// Issuer strings, or Sigstore identities written `sigstore:<oidc issuer>|<san>`,
+    /// which only match attestations whose Sigstore bundle verifies.
     pub allowed_issuers: HashSet<String>,
     pub max_age_days: u32,
     pub max_critical_vulnerabilities: u32,
     pub max_high_medium_vulnerabilities: u32,
 }
 
+/// A reference to a policy held in a `PolicyRepository`, resolved at verification time.
+/// Without a version requirement the newest stored version is used.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyRef {
+    pub purl: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_req: Option<VersionReq>,
+}
+
+impl PolicyRef {
+    pub fn latest(purl: &str) -> Self {
+        Self {
+            purl: purl.to_string(),
+            version_req: None,
+        }
+    }
+
+    pub fn matching(purl: &str, version_req: &str) -> Result<Self, String> {
+        let version_req = VersionReq::parse(version_req).map_err(|e| format!("Invalid version requirement: {}", e))?;
+
+        Ok(Self {
+            purl: purl.to_string(),
+            version_req: Some(version_req),
+        })
+    }
+
+    pub fn matches(&self, policy: &Policy) -> bool {
+        if policy.purl != self.purl {
+            return false;
+        }
+
+        match &self.version_req {
+            Some(req) => Version::parse(&policy.version).is_ok_and(|v| req.matches(&v)),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for PolicyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version_req {
+            Some(req) => write!(f, "{}@{}", self.purl, req),
+            None => write!(f, "{}", self.purl),
+        }
+    }
+}
+
+/// Identifies an individual rule of a `PolicyRules` set so verification
+/// outcomes can be attributed to the rule that produced them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    AllowedIssuers,
+    MaxAgeDays,
+    MaxCriticalVulnerabilities,
+    MaxHighMediumVulnerabilities,
+}
+
+impl PolicyRule {
+    /// Whether the rule is a vulnerability threshold, the only kind of rule a waiver may
+    /// cover. Issuer and age rules decide whether the evidence can be trusted at all.
+    pub fn is_vulnerability_threshold(&self) -> bool {
+        matches!(self, PolicyRule::MaxCriticalVulnerabilities | PolicyRule::MaxHighMediumVulnerabilities)
+    }
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PolicyRule::AllowedIssuers => "allowed_issuers",
+            PolicyRule::MaxAgeDays => "max_age_days",
+            PolicyRule::MaxCriticalVulnerabilities => "max_critical_vulnerabilities",
+            PolicyRule::MaxHighMediumVulnerabilities => "max_high_medium_vulnerabilities",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Errors from storing and resolving policies.
+#[derive(Error, Debug)]
+pub enum PolicyError {
+    #[error("Policy {purl} not found")]
+    NotFound { purl: String },
+    #[error("Policy {purl}@{version} not found")]
+    VersionNotFound { purl: String, version: String },
+    #[error("No policy version matches {0}")]
+    NoMatchingVersion(PolicyRef),
+    #[error("Invalid policy: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+impl PolicyError {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, PolicyError::Storage(e) if e.is_retryable())
+    }
+}
+
 impl Policy {
     pub fn new(purl: String, version: String, rules: PolicyRules) -> Result<Self, String> {
         // Validate the version string
@@ -27,6 +154,7 @@ impl Policy {
             purl,
             version,
             rules,
+            selection: None,
         })
     }
 
@@ -67,11 +195,27 @@ impl PolicyRules {
             return Err("Max age must be greater than 0 days".to_string());
         }
 
+        for entry in &self.allowed_issuers {
+            if let Some(Err(e)) = IdentityPattern::parse(entry) {
+                return Err(e);
+            }
+        }
+
         Ok(())
     }
 
+    /// Whether an attestation's self-declared issuer is allowed. Sigstore identities
+    /// never match here, since anyone can claim one without a verified certificate.
     pub fn is_issuer_allowed(&self, issuer: &str) -> bool {
-        self.allowed_issuers.contains(issuer)
+        !issuer.starts_with(SIGSTORE_ISSUER_PREFIX) && self.allowed_issuers.contains(issuer)
+    }
+
+    /// Whether a Sigstore identity taken from a verified certificate is allowed.
+    pub fn is_identity_allowed(&self, identity: &SigstoreIdentity) -> bool {
+        self.allowed_issuers
+            .iter()
+            .filter_map(|entry| IdentityPattern::parse(entry)?.ok())
+            .any(|pattern| pattern.matches(identity))
     }
 
     pub fn max_age(&self) -> Duration {
@@ -136,5 +280,13 @@ mod tests {
             5,
         );
         assert!(invalid_rules.validate().is_err());
+
+        let malformed_identity = PolicyRules::new(
+            vec!["sigstore:https://token.actions.githubusercontent.com".to_string()].into_iter().collect(),
+            7,
+            0,
+            5,
+        );
+        assert!(malformed_identity.validate().is_err());
     }
 }
\ No newline at end of file
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::models::policy::{PolicyRef, SelectionStrategy};
+
+/// Digest algorithms a component can be pinned with, and their hex-encoded length.
+/// `gitCommit` is the in-toto name for a SHA-1 commit ID.
+pub const SUPPORTED_DIGEST_ALGORITHMS: [(&str, usize); 3] = [("sha256", 64), ("sha512", 128), ("gitCommit", 40)];
+
+/// The supported algorithms no one is known to be able to find collisions for.
+const COLLISION_RESISTANT_ALGORITHMS: [&str; 2] = ["sha256", "sha512"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Component {
+    pub name: String,
+    pub version: String,
+    pub policy: PolicyRef,
+    /// Artifact digests the component is pinned to, keyed by algorithm.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub digests: BTreeMap<String, String>,
+    /// Overrides the attestation selection strategy of the component's policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<SelectionStrategy>,
+}
+
+/// How the subjects of an attestation statement relate to a component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubjectMatch {
+    /// A subject identifies the component.
+    Matched,
+    /// A subject claims the component's name and version but describes different bytes.
+    DigestMismatch(String),
+    NotMatched,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SDLCProject {
+    pub name: String,
+    pub components: Vec<Component>,
+}
+
+impl SDLCProject {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Project name cannot be empty".to_string());
+        }
+
+        for (i, component) in self.components.iter().enumerate() {
+            component.validate()?;
+            if self.components[..i].iter().any(|c| c.name == component.name) {
+                return Err(format!("Duplicate component: {}", component.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn component(&self, name: &str) -> Option<&Component> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+impl Component {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Component name cannot be empty".to_string());
+        }
+
+        if self.version.is_empty() {
+            return Err("Component version cannot be empty".to_string());
+        }
+
+        if self.policy.purl.is_empty() {
+            return Err("Component policy PURL cannot be empty".to_string());
+        }
+
+        for (algorithm, value) in &self.digests {
+            let (_, len) = SUPPORTED_DIGEST_ALGORITHMS
+                .iter()
+                .find(|(name, _)| name == algorithm)
+                .ok_or_else(|| format!("Unsupported digest algorithm: {}", algorithm))?;
+
+            if value.len() != *len || !value.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()) {
+                return Err(format!("Invalid {} digest: {}", algorithm, value));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Matches the component against every subject of an in-toto statement.
+    ///
+    /// Unpinned components are identified by name and version. Pinned components are
+    /// identified by digest: a subject matches when it shares at least one pinned
+    /// algorithm and every shared algorithm agrees. A component pinned with a collision
+    /// resistant algorithm only matches subjects sharing one of those, so agreeing on a
+    /// SHA-1 `gitCommit` alone is not enough. A subject carrying the component's
+    /// name and version with disagreeing digests is reported as a mismatch.
+    pub fn match_subjects(&self, content: &Value) -> SubjectMatch {
+        let subjects = match content["subject"].as_array() {
+            Some(subjects) => subjects,
+            None => return SubjectMatch::NotMatched,
+        };
+
+        let mut mismatch = None;
+        for subject in subjects {
+            let named = subject["name"].as_str() == Some(&self.name)
+                && subject["version"].as_str() == Some(&self.version);
+
+            if self.digests.is_empty() {
+                if named {
+                    return SubjectMatch::Matched;
+                }
+                continue;
+            }
+
+            match self.compare_digests(&subject["digest"]) {
+                Ok(()) => return SubjectMatch::Matched,
+                Err(reason) if named => mismatch = mismatch.or(Some(reason)),
+                Err(_) => {}
+            }
+        }
+
+        mismatch.map_or(SubjectMatch::NotMatched, SubjectMatch::DigestMismatch)
+    }
+
+    fn compare_digests(&self, digest: &Value) -> Result<(), String> {
+        let mut shared = 0;
+        let mut shared_resistant = 0;
+        for (algorithm, expected) in &self.digests {
+            if let Some(actual) = digest[algorithm].as_str() {
+                if !actual.eq_ignore_ascii_case(expected) {
+                    return Err(format!("{} digest {} does not match pinned {}", algorithm, actual, expected));
+                }
+                shared += 1;
+                if COLLISION_RESISTANT_ALGORITHMS.contains(&algorithm.as_str()) {
+                    shared_resistant += 1;
+                }
+            }
+        }
+
+        let pinned: Vec<&str> = self.digests.keys().map(String::as_str).collect();
+        if shared == 0 {
+            return Err(format!("subject has none of the pinned digest algorithms ({})", pinned.join(", ")));
+        }
+        if shared_resistant == 0 && pinned.iter().any(|algorithm| COLLISION_RESISTANT_ALGORITHMS.contains(algorithm)) {
+            return Err(format!("subject shares only weak digests with the pinned ones ({})", pinned.join(", ")));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_component_digest_matching() {
+        let sha256 = "a".repeat(64);
+        let sha512 = "b".repeat(128);
+        let component = Component {
+            name: "frontend".to_string(),
+            version: "1.2.3".to_string(),
+            policy: PolicyRef::latest("pkg:policy/test"),
+            digests: vec![("sha256".to_string(), sha256.clone()), ("sha512".to_string(), sha512.clone())]
+                .into_iter()
+                .collect(),
+            selection: None,
+        };
+        assert!(component.validate().is_ok());
+
+        // Any subject may match, and only shared algorithms are compared
+        let matching = json!({
+            "subject": [
+                { "name": "unrelated", "digest": { "sha256": "c".repeat(64) } },
+                { "name": "frontend.tar.gz", "digest": { "sha512": sha512 } }
+            ]
+        });
+        assert_eq!(component.match_subjects(&matching), SubjectMatch::Matched);
+
+        let rebuilt = json!({
+            "subject": [{ "name": "frontend", "version": "1.2.3", "digest": { "sha256": "d".repeat(64) } }]
+        });
+        assert!(matches!(component.match_subjects(&rebuilt), SubjectMatch::DigestMismatch(_)));
+
+        let unrelated = json!({ "subject": [{ "name": "backend", "version": "1.2.3", "digest": { "sha256": "d".repeat(64) } }] });
+        assert_eq!(component.match_subjects(&unrelated), SubjectMatch::NotMatched);
+
+        // A commit pinned by content digest too cannot be matched through SHA-1 alone
+        let commit = "f".repeat(40);
+        let built = Component {
+            digests: [("sha256".to_string(), sha256.clone()), ("gitCommit".to_string(), commit.clone())].into_iter().collect(),
+            ..component.clone()
+        };
+        let commit_only = json!({ "subject": [{ "name": "frontend", "version": "1.2.3", "digest": { "gitCommit": commit } }] });
+        assert!(matches!(built.match_subjects(&commit_only), SubjectMatch::DigestMismatch(_)));
+        let both = json!({ "subject": [{ "name": "frontend", "digest": { "gitCommit": commit, "sha256": sha256 } }] });
+        assert_eq!(built.match_subjects(&both), SubjectMatch::Matched);
+        let source = Component {
+            digests: [("gitCommit".to_string(), commit.clone())].into_iter().collect(),
+            ..component.clone()
+        };
+        assert_eq!(source.match_subjects(&commit_only), SubjectMatch::Matched);
+
+        let mut invalid = component.clone();
+        invalid.digests.insert("md5".to_string(), "e".repeat(32));
+        assert!(invalid.validate().is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Hex-encoded digests of a resource, keyed by algorithm, e.g. `sha256` or `gitCommit`.
+pub type DigestSet = BTreeMap<String, String>;
+
+/// Digest algorithms whose values are lowercase hex of a fixed length, by hex length.
+/// Other algorithms, such as `dirHash`, are accepted with any non-empty value.
+const HEX_DIGEST_ALGORITHMS: [(&str, usize); 17] = [
+    ("sha256", 64),
+    ("sha224", 56),
+    ("sha384", 96),
+    ("sha512", 128),
+    ("sha512_224", 56),
+    ("sha512_256", 64),
+    ("sha3_224", 56),
+    ("sha3_256", 64),
+    ("sha3_384", 96),
+    ("sha3_512", 128),
+    ("sha1", 40),
+    ("md5", 32),
+    ("ripemd160", 40),
+    ("sm3", 64),
+    ("gitCommit", 40),
+    ("gitTree", 40),
+    ("gitBlob", 40),
+];
+
+/// An in-toto v1 resource descriptor: an artifact identified by any of its URI, its
+/// digests or its content.
+///
+/// Deserializing does not require any of the three; `validate` does, so that a
+/// descriptor can be inspected and reported on even when it is incomplete.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceDescriptor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub digest: DigestSet,
+    /// The resource itself, base64-encoded in JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "base64_content")]
+    pub content: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_location: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub annotations: Map<String, Value>,
+}
+
+impl ResourceDescriptor {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.uri.is_none() && self.digest.is_empty() && self.content.is_none() {
+            return Err("Resource descriptor needs at least one of uri, digest or content".to_string());
+        }
+
+        if self.uri.as_deref().is_some_and(str::is_empty) {
+            return Err("Resource descriptor URI cannot be empty".to_string());
+        }
+
+        for (algorithm, value) in &self.digest {
+            let valid = match HEX_DIGEST_ALGORITHMS.iter().find(|(name, _)| name == algorithm) {
+                Some((_, len)) => value.len() == *len && value.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()),
+                None => !value.is_empty(),
+            };
+            if !valid {
+                return Err(format!("Invalid {} digest: {}", algorithm, value));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+mod base64_content {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(content: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match content {
+            Some(bytes) => serializer.serialize_str(&STANDARD.encode(bytes)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|encoded| STANDARD.decode(encoded).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resource_descriptor() {
+        let descriptor: ResourceDescriptor = serde_json::from_value(json!({
+            "name": "app.tar.gz",
+            "digest": { "sha256": "ab".repeat(32), "gitCommit": "cd".repeat(20), "dirHash": "h1:abc=" },
+            "content": "aGVsbG8=",
+            "downloadLocation": "https://example.com/app.tar.gz",
+            "annotations": { "version": "1.2.3" },
+        }))
+        .unwrap();
+        assert_eq!(descriptor.content.as_deref(), Some(&b"hello"[..]));
+        assert_eq!(descriptor.digest["gitCommit"], "cd".repeat(20));
+        assert!(descriptor.validate().is_ok());
+        assert_eq!(serde_json::to_value(&descriptor).unwrap()["content"], "aGVsbG8=");
+
+        assert!(serde_json::from_value::<ResourceDescriptor>(json!({ "content": "not base64!" })).is_err());
+
+        let empty = ResourceDescriptor {
+            name: Some("app".to_string()),
+            ..Default::default()
+        };
+        assert!(empty.validate().is_err());
+
+        let uppercase = ResourceDescriptor {
+            digest: [("sha512".to_string(), "AB".repeat(64))].into(),
+            ..Default::default()
+        };
+        assert!(uppercase.validate().is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::attestation::Attestation;
+
+/// Who is removing evidence and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deletion {
+    pub actor: String,
+    pub reason: String,
+}
+
+impl Deletion {
+    pub fn new(actor: impl Into<String>, reason: impl Into<String>) -> Result<Self, String> {
+        let deletion = Self {
+            actor: actor.into(),
+            reason: reason.into(),
+        };
+        if deletion.actor.trim().is_empty() {
+            return Err("A deletion must name who is deleting".to_string());
+        }
+        if deletion.reason.trim().is_empty() {
+            return Err("A deletion must give a reason".to_string());
+        }
+        Ok(deletion)
+    }
+}
+
+/// A deletion as recorded, with when it happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeletionRecord {
+    pub actor: String,
+    pub reason: String,
+    pub at: DateTime<Utc>,
+}
+
+impl From<&Deletion> for DeletionRecord {
+    fn from(deletion: &Deletion) -> Self {
+        Self {
+            actor: deletion.actor.clone(),
+            reason: deletion.reason.clone(),
+            at: Utc::now(),
+        }
+    }
+}
+
+/// Marks a deleted attestation. Until it is purged the attestation's content is kept,
+/// only hidden from lookups and listings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    pub uri: String,
+    pub attestation_id: String,
+    pub predicate_type: String,
+    pub deleted: DeletionRecord,
+    /// Set once the content itself has been removed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purged: Option<DeletionRecord>,
+}
+
+impl Tombstone {
+    pub fn new(uri: &str, attestation: &Attestation, deletion: &Deletion) -> Self {
+        Self {
+            uri: uri.to_string(),
+            attestation_id: attestation.id.clone(),
+            predicate_type: predicate_type(attestation).to_string(),
+            deleted: deletion.into(),
+            purged: None,
+        }
+    }
+
+    pub fn is_purged(&self) -> bool {
+        self.purged.is_some()
+    }
+}
+
+/// Keeps an attestation from being purged, whatever its retention, until released.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub placed_by: String,
+    pub reason: String,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl LegalHold {
+    pub fn new(placed_by: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            placed_by: placed_by.into(),
+            reason: reason.into(),
+            placed_at: Utc::now(),
+        }
+    }
+}
+
+/// How long an attestation must be kept, counted from when the store received it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Retention {
+    #[default]
+    Forever,
+    Days(u32),
+}
+
+/// Retention by predicate type, with a default for the types it does not name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    #[serde(default)]
+    pub default: Retention,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub predicate_types: BTreeMap<String, Retention>,
+}
+
+impl RetentionPolicy {
+    pub fn new(default: Retention) -> Self {
+        Self {
+            default,
+            predicate_types: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_predicate_type(mut self, predicate_type: impl Into<String>, retention: Retention) -> Self {
+        self.predicate_types.insert(predicate_type.into(), retention);
+        self
+    }
+
+    pub fn retention(&self, attestation: &Attestation) -> Retention {
+        self.predicate_types.get(predicate_type(attestation)).copied().unwrap_or(self.default)
+    }
+
+    /// When the retention of an attestation received at the given time ends, or `None`
+    /// if it must be kept forever. The attestation's own timestamp is not used, since
+    /// whoever submitted it chose that.
+    pub fn retained_until(&self, attestation: &Attestation, received: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.retention(attestation) {
+            Retention::Forever => None,
+            Retention::Days(days) => Some(received + Duration::days(days as i64)),
+        }
+    }
+}
+
+fn predicate_type(attestation: &Attestation) -> &str {
+    attestation.content["predicateType"].as_str().unwrap_or("unknown")
+}
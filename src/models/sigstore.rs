@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::models::dsse::Envelope;
+
+/// The media type of the only bundle version accepted, Sigstore bundle v0.3.
+pub const BUNDLE_MEDIA_TYPE: &str = "application/vnd.dev.sigstore.bundle.v0.3+json";
+
+/// `allowed_issuers` entries with this prefix name a Sigstore identity rather than an issuer string.
+pub const SIGSTORE_ISSUER_PREFIX: &str = "sigstore:";
+
+/// The OIDC issuer GitHub Actions workflows authenticate with.
+pub const GITHUB_ACTIONS_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+/// A Sigstore bundle: a DSSE envelope plus everything needed to verify it offline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+    pub media_type: String,
+    pub verification_material: VerificationMaterial,
+    pub dsse_envelope: Envelope,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMaterial {
+    /// The Fulcio-issued signing certificate.
+    pub certificate: X509Certificate,
+    #[serde(default)]
+    pub tlog_entries: Vec<TransparencyLogEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct X509Certificate {
+    /// Base64-encoded DER.
+    pub raw_bytes: String,
+}
+
+/// A Rekor entry for the bundle's signature, with the log's promise and proof of inclusion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransparencyLogEntry {
+    #[serde(with = "int64")]
+    pub log_index: i64,
+    pub log_id: LogId,
+    pub kind_version: KindVersion,
+    /// Seconds since the Unix epoch at which the log integrated the entry.
+    #[serde(with = "int64")]
+    pub integrated_time: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inclusion_promise: Option<InclusionPromise>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inclusion_proof: Option<InclusionProof>,
+    /// Base64-encoded canonical JSON of the entry as the log stored it.
+    pub canonicalized_body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogId {
+    /// Base64-encoded SHA-256 of the log's DER public key.
+    pub key_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KindVersion {
+    pub kind: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionPromise {
+    /// Base64-encoded log signature over the entry, its index and integration time.
+    pub signed_entry_timestamp: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionProof {
+    /// The entry's index within the tree the proof is for, which may differ from its global log index.
+    #[serde(with = "int64")]
+    pub log_index: i64,
+    /// Base64-encoded.
+    pub root_hash: String,
+    #[serde(with = "int64")]
+    pub tree_size: i64,
+    /// Base64-encoded sibling hashes from the leaf up.
+    pub hashes: Vec<String>,
+    pub checkpoint: Checkpoint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The signed note committing to the tree size and root hash.
+    pub envelope: String,
+}
+
+/// Who Fulcio certified as the signer: the token's OIDC issuer and the certificate's
+/// subject alternative name, e.g. a GitHub Actions workflow URI or an email address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SigstoreIdentity {
+    pub issuer: String,
+    pub san: String,
+}
+
+impl fmt::Display for SigstoreIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}|{}", SIGSTORE_ISSUER_PREFIX, self.issuer, self.san)
+    }
+}
+
+/// An `allowed_issuers` entry of the form `sigstore:<oidc issuer>|<san>`, where `*` in
+/// the SAN matches any run of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentityPattern {
+    pub issuer: String,
+    pub san: String,
+}
+
+impl IdentityPattern {
+    /// Parses an `allowed_issuers` entry, returning `None` for plain issuer strings.
+    pub fn parse(entry: &str) -> Option<Result<Self, String>> {
+        let pattern = entry.strip_prefix(SIGSTORE_ISSUER_PREFIX)?;
+
+        Some(match pattern.split_once('|') {
+            Some((issuer, san)) if !issuer.is_empty() && !san.is_empty() => Ok(Self {
+                issuer: issuer.to_string(),
+                san: san.to_string(),
+            }),
+            _ => Err(format!("Invalid Sigstore identity '{}', expected sigstore:<issuer>|<san>", entry)),
+        })
+    }
+
+    /// A workflow in a GitHub repository, e.g. `github_workflow("acme/app", "release.yml", "refs/tags/*")`.
+    pub fn github_workflow(repository: &str, workflow: &str, git_ref: &str) -> Self {
+        Self {
+            issuer: GITHUB_ACTIONS_ISSUER.to_string(),
+            san: format!("https://github.com/{}/.github/workflows/{}@{}", repository, workflow, git_ref),
+        }
+    }
+
+    pub fn matches(&self, identity: &SigstoreIdentity) -> bool {
+        self.issuer == identity.issuer && glob_matches(&self.san, &identity.san)
+    }
+}
+
+impl fmt::Display for IdentityPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}|{}", SIGSTORE_ISSUER_PREFIX, self.issuer, self.san)
+    }
+}
+
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Protobuf's JSON mapping writes 64-bit integers as strings; numbers are accepted too.
+mod int64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Int64 {
+        Number(i64),
+        String(String),
+    }
+
+    pub fn serialize<S: Serializer>(value: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        match Int64::deserialize(deserializer)? {
+            Int64::Number(n) => Ok(n),
+            Int64::String(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity_patterns() {
+        let pattern = IdentityPattern::github_workflow("acme/app", "release.yml", "refs/tags/v*");
+        assert_eq!(IdentityPattern::parse(&pattern.to_string()), Some(Ok(pattern.clone())));
+        assert_eq!(IdentityPattern::parse("trusted_issuer"), None);
+        assert!(IdentityPattern::parse("sigstore:https://accounts.google.com").unwrap().is_err());
+
+        let identity = SigstoreIdentity {
+            issuer: GITHUB_ACTIONS_ISSUER.to_string(),
+            san: "https://github.com/acme/app/.github/workflows/release.yml@refs/tags/v1.2.0".to_string(),
+        };
+        assert!(pattern.matches(&identity));
+        assert!(!IdentityPattern::github_workflow("acme/app", "ci.yml", "refs/tags/v*").matches(&identity));
+        assert!(!IdentityPattern::github_workflow("acme/app", "release.yml", "refs/heads/*").matches(&identity));
+
+        let other_issuer = SigstoreIdentity { issuer: "https://accounts.google.com".to_string(), ..identity.clone() };
+        assert!(!pattern.matches(&other_issuer));
+
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("a*a", "a"));
+        assert!(!glob_matches("abc", "abcd"));
+    }
+}
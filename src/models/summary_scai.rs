@@ -2,9 +2,12 @@
 #![allow(clippy::needless_lifetimes)]
 #![allow(clippy::match_single_binding)]
 #![allow(clippy::clone_on_copy)]
+#![allow(clippy::to_string_trait_impl)]
 
 use serde::{Deserialize, Serialize};
 
+pub use crate::models::resource_descriptor::ResourceDescriptor;
+
 #[doc = r" Error types."]
 pub mod error {
     #[doc = r" Error from a TryFrom or FromStr implementation."]
@@ -31,158 +34,6 @@ pub mod error {
         }
     }
 }
-#[doc = "ResourceDescriptor"]
-#[doc = r""]
-#[doc = r" <details><summary>JSON schema</summary>"]
-#[doc = r""]
-#[doc = r" ```json"]
-#[doc = "{"]
-#[doc = "  \"type\": \"object\","]
-#[doc = "  \"anyOf\": ["]
-#[doc = "    {"]
-#[doc = "      \"required\": ["]
-#[doc = "        \"uri\""]
-#[doc = "      ]"]
-#[doc = "    },"]
-#[doc = "    {"]
-#[doc = "      \"required\": ["]
-#[doc = "        \"digest\""]
-#[doc = "      ]"]
-#[doc = "    },"]
-#[doc = "    {"]
-#[doc = "      \"required\": ["]
-#[doc = "        \"content\""]
-#[doc = "      ]"]
-#[doc = "    }"]
-#[doc = "  ],"]
-#[doc = "  \"properties\": {"]
-#[doc = "    \"annotations\": {"]
-#[doc = "      \"type\": \"object\","]
-#[doc = "      \"additionalProperties\": true"]
-#[doc = "    },"]
-#[doc = "    \"content\": {"]
-#[doc = "      \"type\": \"string\""]
-#[doc = "    },"]
-#[doc = "    \"digest\": {"]
-#[doc = "      \"type\": \"object\","]
-#[doc = "      \"required\": ["]
-#[doc = "        \"sha256\""]
-#[doc = "      ],"]
-#[doc = "      \"properties\": {"]
-#[doc = "        \"sha256\": {"]
-#[doc = "          \"type\": \"string\""]
-#[doc = "        }"]
-#[doc = "      }"]
-#[doc = "    },"]
-#[doc = "    \"downloadLocation\": {"]
-#[doc = "      \"type\": \"string\""]
-#[doc = "    },"]
-#[doc = "    \"mediaType\": {"]
-#[doc = "      \"type\": \"string\""]
-#[doc = "    },"]
-#[doc = "    \"name\": {"]
-#[doc = "      \"type\": \"string\""]
-#[doc = "    },"]
-#[doc = "    \"uri\": {"]
-#[doc = "      \"type\": \"string\""]
-#[doc = "    }"]
-#[doc = "  },"]
-#[doc = "  \"additionalProperties\": false"]
-#[doc = "}"]
-#[doc = r" ```"]
-#[doc = r" </details>"]
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(untagged, deny_unknown_fields)]
-pub enum ResourceDescriptor {
-    Variant0 {
-        #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
-        annotations: serde_json::Map<String, serde_json::Value>,
-        content: Option<String>,
-        digest: Option<ResourceDescriptorVariant1Digest>,
-        #[serde(
-            rename = "downloadLocation",
-            default,
-            skip_serializing_if = "Option::is_none"
-        )]
-        download_location: Option<String>,
-        #[serde(rename = "mediaType", default, skip_serializing_if = "Option::is_none")]
-        media_type: Option<String>,
-        #[serde(default, skip_serializing_if = "Option::is_none")]
-        name: Option<String>,
-        uri: String,
-    },
-    Variant1 {
-        #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
-        annotations: serde_json::Map<String, serde_json::Value>,
-        content: Option<String>,
-        digest: ResourceDescriptorVariant1Digest,
-        #[serde(
-            rename = "downloadLocation",
-            default,
-            skip_serializing_if = "Option::is_none"
-        )]
-        download_location: Option<String>,
-        #[serde(rename = "mediaType", default, skip_serializing_if = "Option::is_none")]
-        media_type: Option<String>,
-        #[serde(default, skip_serializing_if = "Option::is_none")]
-        name: Option<String>,
-        uri: Option<String>,
-    },
-    Variant2 {
-        #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
-        annotations: serde_json::Map<String, serde_json::Value>,
-        content: String,
-        digest: Option<ResourceDescriptorVariant1Digest>,
-        #[serde(
-            rename = "downloadLocation",
-            default,
-            skip_serializing_if = "Option::is_none"
-        )]
-        download_location: Option<String>,
-        #[serde(rename = "mediaType", default, skip_serializing_if = "Option::is_none")]
-        media_type: Option<String>,
-        #[serde(default, skip_serializing_if = "Option::is_none")]
-        name: Option<String>,
-        uri: Option<String>,
-    },
-}
-impl From<&ResourceDescriptor> for ResourceDescriptor {
-    fn from(value: &ResourceDescriptor) -> Self {
-        value.clone()
-    }
-}
-#[doc = "ResourceDescriptorVariant1Digest"]
-#[doc = r""]
-#[doc = r" <details><summary>JSON schema</summary>"]
-#[doc = r""]
-#[doc = r" ```json"]
-#[doc = "{"]
-#[doc = "  \"type\": \"object\","]
-#[doc = "  \"required\": ["]
-#[doc = "    \"sha256\""]
-#[doc = "  ],"]
-#[doc = "  \"properties\": {"]
-#[doc = "    \"sha256\": {"]
-#[doc = "      \"type\": \"string\""]
-#[doc = "    }"]
-#[doc = "  }"]
-#[doc = "}"]
-#[doc = r" ```"]
-#[doc = r" </details>"]
-#[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct ResourceDescriptorVariant1Digest {
-    pub sha256: String,
-}
-impl From<&ResourceDescriptorVariant1Digest> for ResourceDescriptorVariant1Digest {
-    fn from(value: &ResourceDescriptorVariant1Digest) -> Self {
-        value.clone()
-    }
-}
-impl ResourceDescriptorVariant1Digest {
-    pub fn builder() -> builder::ResourceDescriptorVariant1Digest {
-        Default::default()
-    }
-}
 #[doc = "SummaryScai"]
 #[doc = r""]
 #[doc = r" <details><summary>JSON schema</summary>"]
@@ -277,6 +128,27 @@ impl SummaryScai {
     pub fn builder() -> builder::SummaryScai {
         Default::default()
     }
+
+    /// Checks every resource descriptor of the report. Subjects must also carry a
+    /// digest, as the in-toto statement requires.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.subject.is_empty() {
+            return Err("Summary has no subject".to_string());
+        }
+
+        for subject in &self.subject {
+            subject.validate().map_err(|e| format!("Invalid subject: {}", e))?;
+            if subject.digest.is_empty() {
+                return Err("Summary subjects must have a digest".to_string());
+            }
+        }
+
+        for item in &self.predicate.attributes {
+            item.evidence.validate().map_err(|e| format!("Invalid evidence for {}: {}", item.attribute.to_string(), e))?;
+        }
+
+        self.predicate.producer.validate().map_err(|e| format!("Invalid producer: {}", e))
+    }
 }
 #[doc = "SummaryScaiPredicate"]
 #[doc = r""]
@@ -512,48 +384,6 @@ impl SummaryScaiPredicateAttributesItemConditions {
 }
 #[doc = r" Types for composing complex structures."]
 pub mod builder {
-    #[derive(Clone, Debug)]
-    pub struct ResourceDescriptorVariant1Digest {
-        sha256: Result<String, String>,
-    }
-    impl Default for ResourceDescriptorVariant1Digest {
-        fn default() -> Self {
-            Self {
-                sha256: Err("no value supplied for sha256".to_string()),
-            }
-        }
-    }
-    impl ResourceDescriptorVariant1Digest {
-        pub fn sha256<T>(mut self, value: T) -> Self
-        where
-            T: std::convert::TryInto<String>,
-            T::Error: std::fmt::Display,
-        {
-            self.sha256 = value
-                .try_into()
-                .map_err(|e| format!("error converting supplied value for sha256: {}", e));
-            self
-        }
-    }
-    impl std::convert::TryFrom<ResourceDescriptorVariant1Digest>
-        for super::ResourceDescriptorVariant1Digest
-    {
-        type Error = super::error::ConversionError;
-        fn try_from(
-            value: ResourceDescriptorVariant1Digest,
-        ) -> Result<Self, super::error::ConversionError> {
-            Ok(Self {
-                sha256: value.sha256?,
-            })
-        }
-    }
-    impl From<super::ResourceDescriptorVariant1Digest> for ResourceDescriptorVariant1Digest {
-        fn from(value: super::ResourceDescriptorVariant1Digest) -> Self {
-            Self {
-                sha256: Ok(value.sha256),
-            }
-        }
-    }
     #[derive(Clone, Debug)]
     pub struct SummaryScai {
         predicate: Result<super::SummaryScaiPredicate, String>,
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::policy::PolicyRule;
+
+/// The subject a waiver applies to, matched against the `subject` entries of an attestation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WaiverScope {
+    Name { name: String },
+    Version { name: String, version: String },
+    Digest { algorithm: String, value: String },
+}
+
+impl WaiverScope {
+    pub fn matches(&self, subject: &Value) -> bool {
+        match self {
+            WaiverScope::Name { name } => subject["name"].as_str() == Some(name),
+            WaiverScope::Version { name, version } => {
+                subject["name"].as_str() == Some(name) && subject["version"].as_str() == Some(version)
+            }
+            WaiverScope::Digest { algorithm, value } => {
+                subject["digest"][algorithm].as_str() == Some(value)
+            }
+        }
+    }
+}
+
+/// An approved exception to a single policy rule, optionally narrowed to one finding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Waiver {
+    pub id: String,
+    pub scope: WaiverScope,
+    pub rule: PolicyRule,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finding_id: Option<String>,
+    pub approver: String,
+    pub justification: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Waiver {
+    pub fn new(
+        scope: WaiverScope,
+        rule: PolicyRule,
+        approver: String,
+        justification: String,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Self, String> {
+        let waiver = Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            scope,
+            rule,
+            finding_id: None,
+            approver,
+            justification,
+            created_at: Utc::now(),
+            expires_at,
+        };
+        waiver.validate()?;
+        Ok(waiver)
+    }
+
+    pub fn with_finding(mut self, finding_id: String) -> Self {
+        self.finding_id = Some(finding_id);
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.rule.is_vulnerability_threshold() {
+            return Err(format!("Rule {} cannot be waived, only vulnerability thresholds can", self.rule));
+        }
+
+        if self.approver.trim().is_empty() {
+            return Err("Waiver approver cannot be empty".to_string());
+        }
+
+        if self.justification.trim().is_empty() {
+            return Err("Waiver justification cannot be empty".to_string());
+        }
+
+        if self.expires_at <= self.created_at {
+            return Err("Waiver must expire after it was created".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.created_at <= now && now < self.expires_at
+    }
+
+    /// Whether the waiver covers any of the subjects listed in an attestation's content.
+    pub fn applies_to(&self, content: &Value) -> bool {
+        content["subject"]
+            .as_array()
+            .is_some_and(|subjects| subjects.iter().any(|s| self.scope.matches(s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    #[test]
+    fn test_waiver_scope_and_expiry() {
+        let waiver = Waiver::new(
+            WaiverScope::Digest { algorithm: "sha256".to_string(), value: "abc123".to_string() },
+            PolicyRule::MaxCriticalVulnerabilities,
+            "security-lead".to_string(),
+            "No upstream fix available yet".to_string(),
+            Utc::now() + Duration::days(14),
+        ).unwrap();
+
+        let content = json!({
+            "subject": [
+                { "name": "frontend", "version": "1.2.3", "digest": { "sha256": "abc123" } }
+            ]
+        });
+        assert!(waiver.applies_to(&content));
+        assert!(!waiver.applies_to(&json!({ "subject": [{ "name": "frontend" }] })));
+
+        assert!(waiver.is_active_at(Utc::now()));
+        assert!(!waiver.is_active_at(Utc::now() + Duration::days(15)));
+
+        let unjustified = Waiver::new(
+            WaiverScope::Name { name: "frontend".to_string() },
+            PolicyRule::MaxHighMediumVulnerabilities,
+            "security-lead".to_string(),
+            "  ".to_string(),
+            Utc::now() + Duration::days(1),
+        );
+        assert!(unjustified.is_err());
+
+        // Issuer and age rules decide whether the evidence is trusted and cannot be waived
+        for rule in [PolicyRule::AllowedIssuers, PolicyRule::MaxAgeDays] {
+            let trust_waiver = Waiver::new(
+                WaiverScope::Name { name: "frontend".to_string() },
+                rule,
+                "security-lead".to_string(),
+                "Vendor build system".to_string(),
+                Utc::now() + Duration::days(1),
+            );
+            assert!(trust_waiver.is_err(), "{} waiver accepted", rule);
+        }
+    }
+}
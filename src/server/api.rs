@@ -0,0 +1,752 @@
+use axum::extract::{FromRequestParts, OptionalFromRequestParts, Path, Query, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+use crate::admission::webhook::{AdmissionError, AdmissionReview, AdmissionWebhook};
+use crate::controlplane::controlplane::{Component, ControlPlane, ControlPlaneError, SDLCProject};
+use crate::controlplane::report::{ComponentReport, ProjectVerificationReport, VerificationOptions};
+use crate::models::attestation::Attestation;
+use crate::models::audit::AuditExport;
+use crate::models::policy::{Policy, PolicyError, PolicyRef};
+use crate::models::retention::Deletion;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::audit_log::AuditLog;
+use crate::storage::error::StorageError;
+use crate::storage::policy_repository::PolicyRepository;
+use crate::storage::project_registry::ProjectRegistry;
+use crate::verification::policy_verifier::{PolicyVerifier, VerificationError};
+
+/// An error returned to API clients as `{"error": "..."}`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message: message.into(),
+        }
+    }
+
+    fn unauthorized() -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            message: "A valid bearer token is required".to_string(),
+        }
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(error: StorageError) -> Self {
+        let status = match &error {
+            StorageError::NotFound { .. } => StatusCode::NOT_FOUND,
+            StorageError::AlreadyExists { .. } | StorageError::Conflict { .. } => StatusCode::CONFLICT,
+            StorageError::Immutable { .. } => StatusCode::FORBIDDEN,
+            StorageError::Invalid(_) => StatusCode::BAD_REQUEST,
+            StorageError::Corrupt { .. } | StorageError::Serialization(_) | StorageError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            StorageError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            StorageError::Rejected(_) => StatusCode::BAD_GATEWAY,
+        };
+
+        Self {
+            status,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<PolicyError> for ApiError {
+    fn from(error: PolicyError) -> Self {
+        let status = match error {
+            PolicyError::Storage(e) => return e.into(),
+            PolicyError::NotFound { .. } | PolicyError::VersionNotFound { .. } | PolicyError::NoMatchingVersion(_) => StatusCode::NOT_FOUND,
+            PolicyError::Invalid(_) => StatusCode::BAD_REQUEST,
+        };
+
+        Self {
+            status,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<ControlPlaneError> for ApiError {
+    /// Storage and policy errors keep their status, so an outage is retryable; anything
+    /// else kept the verification from reaching a verdict on the stored data.
+    fn from(error: ControlPlaneError) -> Self {
+        match error {
+            ControlPlaneError::Storage(e) | ControlPlaneError::Verification(VerificationError::Storage(e)) => e.into(),
+            ControlPlaneError::Policy(e) => e.into(),
+            e => Self {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+impl From<AdmissionError> for ApiError {
+    fn from(error: AdmissionError) -> Self {
+        let status = match &error {
+            AdmissionError::MissingRequest => StatusCode::BAD_REQUEST,
+            AdmissionError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        Self {
+            status,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+/// Bearer tokens accepted by the routes that change the stores, each authenticating one
+/// principal. Only the tokens' SHA-256 digests are kept.
+#[derive(Debug, Clone, Default)]
+pub struct ApiTokens {
+    principals: HashMap<String, String>,
+}
+
+impl ApiTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_token(mut self, token: &str, principal: impl Into<String>) -> Self {
+        self.principals.insert(hex::encode(Sha256::digest(token)), principal.into());
+        self
+    }
+
+    fn principal(&self, token: &str) -> Option<&str> {
+        self.principals.get(&hex::encode(Sha256::digest(token))).map(String::as_str)
+    }
+}
+
+/// Who a request authenticated as. Handlers that change the stores take a `Principal`,
+/// so requests without a known `Authorization: Bearer` token are refused with 401. An
+/// `Option<Principal>` is `None` without an `Authorization` header, but an unknown token
+/// is still refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        parts
+            .extensions
+            .get::<Arc<ApiTokens>>()
+            .zip(token)
+            .and_then(|(tokens, token)| tokens.principal(token.trim()))
+            .map(|principal| Principal(principal.to_string()))
+            .ok_or_else(ApiError::unauthorized)
+    }
+}
+
+impl<S: Send + Sync> OptionalFromRequestParts<S> for Principal {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key(AUTHORIZATION) {
+            return Ok(None);
+        }
+        <Self as FromRequestParts<S>>::from_request_parts(parts, state).await.map(Some)
+    }
+}
+
+/// Filters for `GET /attestations`. All given filters must match.
+#[derive(Debug, Default, Deserialize)]
+pub struct AttestationQuery {
+    pub issuer: Option<String>,
+    /// Name of any subject of the statement.
+    pub subject: Option<String>,
+    pub predicate_type: Option<String>,
+}
+
+impl AttestationQuery {
+    fn matches(&self, attestation: &Attestation) -> bool {
+        let content = &attestation.content;
+        self.issuer.as_ref().is_none_or(|issuer| &attestation.issuer == issuer)
+            && self.predicate_type.as_ref().is_none_or(|t| content["predicateType"].as_str() == Some(t))
+            && self.subject.as_ref().is_none_or(|name| {
+                content["subject"]
+                    .as_array()
+                    .is_some_and(|subjects| subjects.iter().any(|s| s["name"].as_str() == Some(name)))
+            })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UriQuery {
+    pub uri: String,
+}
+
+/// Why an attestation is being deleted, which immutable storage requires. The deletion
+/// is recorded as made by the authenticated principal.
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuery {
+    pub uri: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PolicyQuery {
+    pub purl: String,
+    /// A semver requirement; the newest matching version is returned.
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PolicyVersionQuery {
+    pub purl: String,
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VersionBump {
+    pub version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredAttestation {
+    pub uri: String,
+}
+
+/// The sequence range for `GET /audit`, `from` inclusive and `to` exclusive. Defaults to
+/// the whole log.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+/// The outcome of `GET /audit/verify`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditIntegrity {
+    pub intact: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The body of `POST /verify`: either a registered project or a single ad-hoc component.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VerifyRequest {
+    Project {
+        project: String,
+        #[serde(default)]
+        fail_fast: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_concurrency: Option<usize>,
+    },
+    Component {
+        component: Component,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VerifyReport {
+    Project(ProjectVerificationReport),
+    Component(ComponentReport),
+}
+
+/// The verdict of `POST /verify`. Gates only need `allowed`; the report explains it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub allowed: bool,
+    pub report: VerifyReport,
+}
+
+/// HTTP API over a `ControlPlane` and the stores it was built with.
+///
+/// The gate routes, `/health`, `/verify` and `/admission`, and the `GET` routes are open;
+/// the routes that change the stores need one of the given bearer tokens.
+///
+/// ```text
+/// GET    /health
+/// POST   /verify
+/// POST   /admission                 (Kubernetes AdmissionReview v1)
+/// GET    /attestations?issuer=&subject=&predicate_type=
+/// POST   /attestations
+/// GET    /attestations/by-uri?uri=
+/// DELETE /attestations/by-uri?uri=
+/// GET    /policies?purl=
+/// POST   /policies
+/// DELETE /policies?purl=&version=
+/// GET    /policies/resolve?purl=&version=
+/// GET    /projects
+/// POST   /projects
+/// GET    /projects/{name}
+/// PUT    /projects/{name}
+/// DELETE /projects/{name}
+/// POST   /projects/{name}/components
+/// GET    /projects/{name}/components/{component}
+/// PUT    /projects/{name}/components/{component}
+/// DELETE /projects/{name}/components/{component}
+/// POST   /projects/{name}/components/{component}/bump
+/// GET    /audit?from=&to=
+/// GET    /audit/verify
+/// GET    /metrics              (with the `prometheus` feature)
+/// ```
+pub fn router<P, A, V, R>(control_plane: Arc<ControlPlane<P, A, V, R>>, tokens: ApiTokens) -> Router
+where
+    P: PolicyRepository + 'static,
+    A: AttestationStorage + 'static,
+    V: PolicyVerifier + 'static,
+    R: ProjectRegistry + 'static,
+{
+    Api::<P, A, V, R>::router().layer(Extension(Arc::new(tokens))).with_state(control_plane)
+}
+
+/// Serves the API on an already bound listener until the server fails.
+pub async fn serve<P, A, V, R>(listener: TcpListener, control_plane: Arc<ControlPlane<P, A, V, R>>, tokens: ApiTokens) -> std::io::Result<()>
+where
+    P: PolicyRepository + 'static,
+    A: AttestationStorage + 'static,
+    V: PolicyVerifier + 'static,
+    R: ProjectRegistry + 'static,
+{
+    tracing::info!(address = %listener.local_addr()?, "Serving control plane API");
+    axum::serve(listener, router(control_plane, tokens)).await
+}
+
+struct Api<P, A, V, R>(PhantomData<(P, A, V, R)>);
+
+type Plane<P, A, V, R> = State<Arc<ControlPlane<P, A, V, R>>>;
+
+impl<P, A, V, R> Api<P, A, V, R>
+where
+    P: PolicyRepository + 'static,
+    A: AttestationStorage + 'static,
+    V: PolicyVerifier + 'static,
+    R: ProjectRegistry + 'static,
+{
+    fn router() -> Router<Arc<ControlPlane<P, A, V, R>>> {
+        Router::new()
+            .route("/health", get(|| async { Json(json!({ "status": "ok" })) }))
+            .route("/verify", post(Self::verify))
+            .route("/admission", post(Self::admission))
+            .route("/attestations", get(Self::list_attestations).post(Self::store_attestation))
+            .route("/attestations/by-uri", get(Self::get_attestation).delete(Self::delete_attestation))
+            .route("/policies", get(Self::list_policies).post(Self::add_policy).delete(Self::delete_policy))
+            .route("/policies/resolve", get(Self::resolve_policy))
+            .route("/projects", get(Self::list_projects).post(Self::create_project))
+            .route("/projects/{name}", get(Self::get_project).put(Self::update_project).delete(Self::delete_project))
+            .route("/projects/{name}/components", post(Self::add_component))
+            .route(
+                "/projects/{name}/components/{component}",
+                get(Self::get_component).put(Self::update_component).delete(Self::remove_component),
+            )
+            .route("/projects/{name}/components/{component}/bump", post(Self::bump_component))
+            .route("/audit", get(Self::export_audit_log))
+            .route("/audit/verify", get(Self::verify_audit_log))
+            .merge(Self::metrics_router())
+    }
+
+    #[cfg(feature = "prometheus")]
+    fn metrics_router() -> Router<Arc<ControlPlane<P, A, V, R>>> {
+        use crate::metrics::{prometheus, Metrics};
+
+        Router::new().route(
+            "/metrics",
+            get(|| async { ([(axum::http::header::CONTENT_TYPE, prometheus::CONTENT_TYPE)], prometheus::render(Metrics::global())) }),
+        )
+    }
+
+    #[cfg(not(feature = "prometheus"))]
+    fn metrics_router() -> Router<Arc<ControlPlane<P, A, V, R>>> {
+        Router::new()
+    }
+
+    fn audit_log(plane: &ControlPlane<P, A, V, R>) -> ApiResult<&Arc<dyn AuditLog>> {
+        plane.audit_log().ok_or_else(|| ApiError::not_found("No audit log is configured"))
+    }
+
+    /// Registered projects may be verified anonymously, but an ad-hoc component names its
+    /// own policy and pins, so only an authenticated principal may have its verdict
+    /// appended to the audit log.
+    async fn verify(State(plane): Plane<P, A, V, R>, principal: Option<Principal>, Json(request): Json<VerifyRequest>) -> ApiResult<Json<VerifyResponse>> {
+        let requested_by = principal.map(|Principal(principal)| principal);
+        let response = match request {
+            VerifyRequest::Project { project, fail_fast, max_concurrency } => {
+                let defaults = VerificationOptions::default();
+                let options = VerificationOptions {
+                    max_concurrency: max_concurrency.unwrap_or(defaults.max_concurrency),
+                    fail_fast,
+                    requested_by,
+                };
+                let report = plane.verify_project_with_options(&project, &options).await?;
+                VerifyResponse {
+                    allowed: report.passed,
+                    report: VerifyReport::Project(report),
+                }
+            }
+            VerifyRequest::Component { component } => {
+                let requested_by = requested_by.ok_or_else(ApiError::unauthorized)?;
+                component.validate().map_err(ApiError::bad_request)?;
+                let report = plane.verify_component_as(&component, &requested_by).await?;
+                VerifyResponse {
+                    allowed: report.status.is_passed(),
+                    report: VerifyReport::Component(report),
+                }
+            }
+        };
+
+        Ok(Json(response))
+    }
+
+    async fn admission(State(plane): Plane<P, A, V, R>, Json(review): Json<AdmissionReview>) -> ApiResult<Json<AdmissionReview>> {
+        let webhook = AdmissionWebhook::new(plane);
+        Ok(Json(webhook.review(review).await?))
+    }
+
+    async fn list_attestations(State(plane): Plane<P, A, V, R>, Query(query): Query<AttestationQuery>) -> ApiResult<Json<Vec<Attestation>>> {
+        let attestations = plane.attestation_storage().list_attestations().await?;
+        let mut matching: Vec<Attestation> = attestations
+            .iter()
+            .filter(|att| query.matches(att))
+            .map(|att| att.as_ref().clone())
+            .collect();
+        matching.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| a.id.cmp(&b.id)));
+        Ok(Json(matching))
+    }
+
+    async fn store_attestation(State(plane): Plane<P, A, V, R>, _: Principal, Json(attestation): Json<Attestation>) -> ApiResult<(StatusCode, Json<StoredAttestation>)> {
+        if attestation.id.is_empty() || attestation.issuer.is_empty() {
+            return Err(ApiError::bad_request("Attestation id and issuer are required"));
+        }
+
+        let uri = plane.attestation_storage().store_attestation(Arc::new(attestation)).await?;
+        Ok((StatusCode::CREATED, Json(StoredAttestation { uri })))
+    }
+
+    async fn get_attestation(State(plane): Plane<P, A, V, R>, Query(query): Query<UriQuery>) -> ApiResult<Json<Attestation>> {
+        let attestation = plane.attestation_storage().get_attestation(&query.uri).await?;
+        Ok(Json(attestation.as_ref().clone()))
+    }
+
+    async fn delete_attestation(State(plane): Plane<P, A, V, R>, Principal(actor): Principal, Query(query): Query<DeleteQuery>) -> ApiResult<StatusCode> {
+        let storage = plane.attestation_storage();
+        match query.reason {
+            None => storage.delete_attestation(&query.uri).await?,
+            Some(reason) => {
+                let deletion = Deletion::new(actor, reason).map_err(ApiError::bad_request)?;
+                storage.delete_attestation_by(&query.uri, &deletion).await?
+            }
+        }
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn list_policies(State(plane): Plane<P, A, V, R>, Query(query): Query<PolicyQuery>) -> ApiResult<Json<Vec<Policy>>> {
+        let policies = plane.policy_repo().list_policies(&query.purl).await?;
+        Ok(Json(policies.iter().map(|p| p.as_ref().clone()).collect()))
+    }
+
+    async fn add_policy(State(plane): Plane<P, A, V, R>, _: Principal, Json(policy): Json<Policy>) -> ApiResult<StatusCode> {
+        policy.validate().map_err(ApiError::bad_request)?;
+        plane.policy_repo().add_policy(policy).await?;
+        Ok(StatusCode::CREATED)
+    }
+
+    async fn delete_policy(State(plane): Plane<P, A, V, R>, _: Principal, Query(query): Query<PolicyVersionQuery>) -> ApiResult<StatusCode> {
+        plane.policy_repo().delete_policy(&query.purl, &query.version).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn resolve_policy(State(plane): Plane<P, A, V, R>, Query(query): Query<PolicyQuery>) -> ApiResult<Json<Policy>> {
+        let policy_ref = match &query.version {
+            Some(req) => PolicyRef::matching(&query.purl, req).map_err(ApiError::bad_request)?,
+            None => PolicyRef::latest(&query.purl),
+        };
+        let policy = plane.policy_repo().resolve_policy(&policy_ref).await?;
+        Ok(Json(policy.as_ref().clone()))
+    }
+
+    async fn list_projects(State(plane): Plane<P, A, V, R>) -> ApiResult<Json<Vec<SDLCProject>>> {
+        let projects = plane.registry().list_projects().await?;
+        Ok(Json(projects.iter().map(|p| p.as_ref().clone()).collect()))
+    }
+
+    async fn create_project(State(plane): Plane<P, A, V, R>, _: Principal, Json(project): Json<SDLCProject>) -> ApiResult<StatusCode> {
+        plane.registry().create_project(project).await?;
+        Ok(StatusCode::CREATED)
+    }
+
+    async fn get_project(State(plane): Plane<P, A, V, R>, Path(name): Path<String>) -> ApiResult<Json<SDLCProject>> {
+        let project = plane.registry().get_project(&name).await?;
+        Ok(Json(project.as_ref().clone()))
+    }
+
+    async fn update_project(State(plane): Plane<P, A, V, R>, _: Principal, Path(name): Path<String>, Json(project): Json<SDLCProject>) -> ApiResult<StatusCode> {
+        if project.name != name {
+            return Err(ApiError::bad_request("Project name in the body does not match the path"));
+        }
+        plane.registry().update_project(project).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn delete_project(State(plane): Plane<P, A, V, R>, _: Principal, Path(name): Path<String>) -> ApiResult<StatusCode> {
+        plane.registry().delete_project(&name).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn add_component(State(plane): Plane<P, A, V, R>, _: Principal, Path(name): Path<String>, Json(component): Json<Component>) -> ApiResult<StatusCode> {
+        plane.registry().add_component(&name, component).await?;
+        Ok(StatusCode::CREATED)
+    }
+
+    async fn get_component(State(plane): Plane<P, A, V, R>, Path((name, component)): Path<(String, String)>) -> ApiResult<Json<Component>> {
+        Ok(Json(plane.registry().get_component(&name, &component).await?))
+    }
+
+    async fn update_component(
+        State(plane): Plane<P, A, V, R>,
+        _: Principal,
+        Path((name, component_name)): Path<(String, String)>,
+        Json(component): Json<Component>,
+    ) -> ApiResult<StatusCode> {
+        if component.name != component_name {
+            return Err(ApiError::bad_request("Component name in the body does not match the path"));
+        }
+        plane.registry().update_component(&name, component).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn remove_component(State(plane): Plane<P, A, V, R>, _: Principal, Path((name, component)): Path<(String, String)>) -> ApiResult<StatusCode> {
+        plane.registry().remove_component(&name, &component).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    async fn export_audit_log(State(plane): Plane<P, A, V, R>, Query(query): Query<AuditQuery>) -> ApiResult<Json<AuditExport>> {
+        let audit_log = Self::audit_log(&plane)?;
+        let from = query.from.unwrap_or(0);
+        let to = query.to.unwrap_or(u64::MAX);
+        if from > to {
+            return Err(ApiError::bad_request("from must not be greater than to"));
+        }
+        Ok(Json(audit_log.export(from..to).await?))
+    }
+
+    async fn verify_audit_log(State(plane): Plane<P, A, V, R>) -> ApiResult<Json<AuditIntegrity>> {
+        let integrity = match Self::audit_log(&plane)?.verify_integrity().await {
+            Ok(head_hash) => AuditIntegrity { intact: true, head_hash: Some(head_hash), error: None },
+            Err(e) => AuditIntegrity { intact: false, head_hash: None, error: Some(e.to_string()) },
+        };
+        Ok(Json(integrity))
+    }
+
+    async fn bump_component(
+        State(plane): Plane<P, A, V, R>,
+        _: Principal,
+        Path((name, component)): Path<(String, String)>,
+        Json(bump): Json<VersionBump>,
+    ) -> ApiResult<StatusCode> {
+        plane.registry().bump_component_version(&name, &component, &bump.version).await?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use reqwest::Client;
+    use serde_json::Value;
+
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+    use crate::storage::audit_log::InMemoryAuditLog;
+    use crate::storage::policy_repository::InMemoryPolicyRepository;
+    use crate::verification::policy_verifier::SimplePolicyVerifier;
+
+    const ADMIN_TOKEN: &str = "s3cret-admin-token";
+
+    async fn spawn_server() -> String {
+        let control_plane = Arc::new(ControlPlane::new(
+            Arc::new(InMemoryPolicyRepository::new()),
+            Arc::new(InMemoryAttestationStorage::new()),
+            Arc::new(SimplePolicyVerifier),
+        ).with_audit_log(Arc::new(InMemoryAuditLog::new())));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, control_plane, ApiTokens::new().with_token(ADMIN_TOKEN, "release-admin")));
+        base
+    }
+
+    #[tokio::test]
+    async fn test_gate_api_over_http() {
+        let base = spawn_server().await;
+        let client = Client::new();
+
+        let health: Value = client.get(format!("{}/health", base)).send().await.unwrap().json().await.unwrap();
+        assert_eq!(health["status"], "ok");
+
+        let response = client.post(format!("{}/policies", base))
+            .bearer_auth(ADMIN_TOKEN)
+            .json(&json!({
+                "purl": "pkg:github/acme/api",
+                "version": "1.0.0",
+                "rules": {
+                    "allowed_issuers": ["trusted_issuer"],
+                    "max_age_days": 30,
+                    "max_critical_vulnerabilities": 0,
+                    "max_high_medium_vulnerabilities": 5
+                }
+            }))
+            .send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let project = json!({
+            "name": "acme",
+            "components": [{ "name": "api", "version": "1.0.0", "policy": { "purl": "pkg:github/acme/api" } }]
+        });
+        let response = client.post(format!("{}/projects", base)).bearer_auth(ADMIN_TOKEN).json(&project).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = client.post(format!("{}/projects", base)).bearer_auth(ADMIN_TOKEN).json(&project).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let verdict: VerifyResponse = client.post(format!("{}/verify", base))
+            .json(&json!({ "project": "acme" }))
+            .send().await.unwrap().json().await.unwrap();
+        assert!(!verdict.allowed);
+
+        let attestation = Attestation {
+            id: "api-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": "api", "version": "1.0.0" }],
+                "vulnerabilities": { "critical": 0, "high": 1, "medium": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        // Anonymous clients and unknown tokens cannot change the stores
+        let response = client.post(format!("{}/attestations", base)).json(&attestation).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client.post(format!("{}/attestations", base)).bearer_auth("guess").json(&attestation).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client.delete(format!("{}/projects/acme", base)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let stored: StoredAttestation = client.post(format!("{}/attestations", base))
+            .bearer_auth(ADMIN_TOKEN)
+            .json(&attestation)
+            .send().await.unwrap().json().await.unwrap();
+
+        let found: Vec<Attestation> = client.get(format!("{}/attestations", base))
+            .query(&[("subject", "api")])
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(found.len(), 1);
+        let by_uri: Attestation = client.get(format!("{}/attestations/by-uri", base))
+            .query(&[("uri", stored.uri.as_str())])
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(by_uri.id, "api-att");
+
+        let verdict: VerifyResponse = client.post(format!("{}/verify", base))
+            .json(&json!({ "project": "acme" }))
+            .send().await.unwrap().json().await.unwrap();
+        assert!(verdict.allowed);
+        match verdict.report {
+            VerifyReport::Project(report) => assert_eq!(report.component("api").unwrap().decided_by, vec!["api-att".to_string()]),
+            VerifyReport::Component(_) => panic!("expected a project report"),
+        }
+
+        // Ad-hoc components choose their own policy, so anonymous clients cannot have
+        // their verdicts recorded
+        let ad_hoc = json!({
+            "component": {
+                "name": "api",
+                "version": "1.0.0",
+                "policy": { "purl": "pkg:github/acme/api", "version_req": ">=2.0.0" }
+            }
+        });
+        let response = client.post(format!("{}/verify", base)).json(&ad_hoc).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // An ad-hoc component whose policy cannot be resolved is denied, not rejected
+        let response = client.post(format!("{}/verify", base))
+            .bearer_auth(ADMIN_TOKEN)
+            .json(&ad_hoc)
+            .send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let verdict: Value = response.json().await.unwrap();
+        assert_eq!(verdict["allowed"], false);
+        assert_eq!(verdict["report"]["status"], "error");
+
+        let response = client.get(format!("{}/projects/missing", base)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Every verdict above was recorded
+        let export: AuditExport = client.get(format!("{}/audit", base))
+            .query(&[("from", "1")])
+            .send().await.unwrap().json().await.unwrap();
+        assert_eq!(export.entries.len(), 2);
+        assert_eq!(export.entries[0].record.requested_by, None);
+        assert_eq!(export.entries[1].record.requested_by.as_deref(), Some("release-admin"));
+        assert_eq!(export.verify().unwrap(), export.head_hash);
+        let integrity: AuditIntegrity = client.get(format!("{}/audit/verify", base)).send().await.unwrap().json().await.unwrap();
+        assert!(integrity.intact);
+        assert_eq!(integrity.head_hash, Some(export.head_hash));
+
+        #[cfg(feature = "prometheus")]
+        {
+            let metrics = client.get(format!("{}/metrics", base)).send().await.unwrap().text().await.unwrap();
+            assert!(metrics.contains("sisyphus_verifications_total{outcome=\"passed\"}"));
+        }
+    }
+
+    #[test]
+    fn test_verification_error_statuses() {
+        let outage = ControlPlaneError::Verification(VerificationError::Storage(StorageError::Unavailable("registry timed out".into())));
+        assert_eq!(ApiError::from(outage).status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let missing = ControlPlaneError::Policy(PolicyError::NotFound { purl: "pkg:github/acme/app".to_string() });
+        assert_eq!(ApiError::from(missing).status, StatusCode::NOT_FOUND);
+
+        let malformed = ControlPlaneError::Verification(VerificationError::MalformedAttestation {
+            attestation_id: "att1".to_string(),
+            reason: "no subject".to_string(),
+        });
+        assert_eq!(ApiError::from(malformed).status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1 @@
+pub mod api;
\ No newline at end of file
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use url::{form_urlencoded, Url};
+
+use crate::models::attestation::Attestation;
+use crate::models::canonical::canonical_sha256;
+
+/// The base URI attestations are addressed under unless a storage is given another.
+pub const DEFAULT_BASE_URI: &str = "https://example.com/";
+
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{uri} is not an attestation URI: {reason}")]
+pub struct AttestationRefError {
+    pub uri: String,
+    pub reason: String,
+}
+
+/// A content-addressed attestation URI of the form
+/// `<base>/<predicate type>/<sha256>.jsonl`, where the predicate type is
+/// form-urlencoded and the digest is the full SHA-256 of the attestation's canonical
+/// JSON. Two attestations share a URI only if they are the same attestation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AttestationRef {
+    base: Url,
+    predicate_type: String,
+    sha256: String,
+}
+
+impl AttestationRef {
+    pub fn for_attestation(base: &Url, attestation: &Attestation) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            base: directory(base.clone()),
+            predicate_type: attestation.content["predicateType"].as_str().unwrap_or("unknown").to_string(),
+            sha256: canonical_sha256(attestation)?,
+        })
+    }
+
+    pub fn base(&self) -> &Url {
+        &self.base
+    }
+
+    pub fn predicate_type(&self) -> &str {
+        &self.predicate_type
+    }
+
+    /// The hex SHA-256 of the attestation's canonical JSON.
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+
+    /// Whether this is the URI of the given attestation, under any base.
+    pub fn addresses(&self, attestation: &Attestation) -> bool {
+        canonical_sha256(attestation).is_ok_and(|digest| digest == self.sha256)
+    }
+}
+
+/// Gives a base URI the trailing slash that refs are appended after.
+pub(crate) fn directory(mut base: Url) -> Url {
+    if !base.path().ends_with('/') {
+        let path = format!("{}/", base.path());
+        base.set_path(&path);
+    }
+    base
+}
+
+impl fmt::Display for AttestationRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let predicate_type: String = form_urlencoded::byte_serialize(self.predicate_type.as_bytes()).collect();
+        write!(f, "{}{}/{}.jsonl", self.base, predicate_type, self.sha256)
+    }
+}
+
+impl FromStr for AttestationRef {
+    type Err = AttestationRefError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let error = |reason: String| AttestationRefError {
+            uri: uri.to_string(),
+            reason,
+        };
+
+        let (rest, file) = uri.rsplit_once('/').ok_or_else(|| error("it has no path".to_string()))?;
+        let sha256 = file
+            .strip_suffix(".jsonl")
+            .filter(|hex| hex.len() == 64 && hex.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)))
+            .ok_or_else(|| error("it does not end in a full SHA-256 digest".to_string()))?;
+        let (base, predicate_type) = rest.rsplit_once('/').ok_or_else(|| error("it names no predicate type".to_string()))?;
+        let predicate_type = form_urlencoded::parse(predicate_type.as_bytes())
+            .next()
+            .map(|(decoded, _)| decoded.into_owned())
+            .ok_or_else(|| error("it names no predicate type".to_string()))?;
+        let base = Url::parse(&format!("{}/", base)).map_err(|e| error(e.to_string()))?;
+
+        Ok(Self {
+            base,
+            predicate_type,
+            sha256: sha256.to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for AttestationRef {
+    type Error = AttestationRefError;
+
+    fn try_from(uri: String) -> Result<Self, Self::Error> {
+        uri.parse()
+    }
+}
+
+impl From<AttestationRef> for String {
+    fn from(reference: AttestationRef) -> Self {
+        reference.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    #[test]
+    fn test_attestation_ref_round_trip() {
+        let attestation = Attestation {
+            id: "att1".to_string(),
+            issuer: "issuer1".to_string(),
+            timestamp: Utc::now(),
+            content: json!({ "predicateType": "https://slsa.dev/provenance/v1" }),
+            ..Default::default()
+        };
+        let base = Url::parse("https://attestations.acme.dev/store").unwrap();
+        let reference = AttestationRef::for_attestation(&base, &attestation).unwrap();
+
+        let uri = reference.to_string();
+        assert!(uri.starts_with("https://attestations.acme.dev/store/https%3A%2F%2Fslsa.dev%2Fprovenance%2Fv1/"));
+        assert_eq!(reference.sha256().len(), 64);
+
+        let parsed: AttestationRef = uri.parse().unwrap();
+        assert_eq!(parsed, reference);
+        assert_eq!(parsed.predicate_type(), "https://slsa.dev/provenance/v1");
+        assert!(parsed.addresses(&attestation));
+
+        // The truncated digests of earlier URIs no longer parse
+        assert!("https://example.com/unknown/0123456789ab.jsonl".parse::<AttestationRef>().is_err());
+        assert!("non_existent".parse::<AttestationRef>().is_err());
+    }
+}
@@ -1,69 +1,218 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::error::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use url::form_urlencoded;
-use sha2::{Sha256, Digest};
+use url::Url;
 use crate::models::attestation::Attestation;
+use crate::models::canonical::to_canonical_vec;
+use crate::models::retention::Deletion;
+use crate::storage::attestation_ref::{directory, AttestationRef, DEFAULT_BASE_URI};
+use crate::storage::error::StorageError;
 
 #[async_trait]
 pub trait AttestationStorage: Send + Sync {
-    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, Box<dyn Error + Send + Sync>>;
-    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, Box<dyn Error + Send + Sync>>;
-    async fn delete_attestation(&self, uri: &str) -> Result<(), Box<dyn Error + Send + Sync>>;
-    async fn list_attestations(&self) -> Result<Vec<Arc<Attestation>>, Box<dyn Error + Send + Sync>>;
+    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, StorageError>;
+    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError>;
+    async fn delete_attestation(&self, uri: &str) -> Result<(), StorageError>;
+    /// Deletes an attestation on someone's behalf. Stores that keep a record of
+    /// deletions note who deleted it and why; the others simply delete it.
+    async fn delete_attestation_by(&self, uri: &str, _deletion: &Deletion) -> Result<(), StorageError> {
+        self.delete_attestation(uri).await
+    }
+    /// Lists every stored attestation together with the URI it is stored under.
+    async fn list_attestation_entries(&self) -> Result<Vec<(String, Arc<Attestation>)>, StorageError>;
+
+    /// Lists up to `limit` entries in URI order, starting after the URI `after`. Stores
+    /// that cannot page natively page through `list_attestation_entries`, which bounds
+    /// what the caller holds at a time but not what the store loads.
+    async fn list_attestation_page(&self, after: Option<&str>, limit: usize) -> Result<Vec<(String, Arc<Attestation>)>, StorageError> {
+        let mut entries = self.list_attestation_entries().await?;
+        entries.retain(|(uri, _)| after.is_none_or(|after| uri.as_str() > after));
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    /// The URI `store_attestation` would store the attestation under, or `None` if the
+    /// store cannot tell without storing it.
+    fn uri_for(&self, _attestation: &Attestation) -> Result<Option<String>, StorageError> {
+        Ok(None)
+    }
+
+    async fn list_attestations(&self) -> Result<Vec<Arc<Attestation>>, StorageError> {
+        Ok(self.list_attestation_entries().await?.into_iter().map(|(_, attestation)| attestation).collect())
+    }
 }
 
 pub struct InMemoryAttestationStorage {
+    base_uri: Url,
     attestations: RwLock<HashMap<String, Arc<Attestation>>>,
 }
 
 impl InMemoryAttestationStorage {
     pub fn new() -> Self {
         Self {
+            base_uri: default_base_uri(),
             attestations: RwLock::new(HashMap::new()),
         }
     }
 
-    fn generate_uri(attestation: &Attestation) -> String {
-        let predicate_type = attestation.content["predicateType"].as_str().unwrap_or("unknown");
-        let encoded_predicate_type = form_urlencoded::byte_serialize(predicate_type.as_bytes()).collect::<String>();
+    /// Addresses stored attestations under `base_uri` instead of `DEFAULT_BASE_URI`.
+    pub fn with_base_uri(mut self, base_uri: Url) -> Self {
+        self.base_uri = directory(base_uri);
+        self
+    }
+}
+
+impl Default for InMemoryAttestationStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn not_found(uri: &str) -> StorageError {
+    StorageError::not_found("Attestation", uri)
+}
 
-        let attestation_json = serde_json::to_string(attestation).unwrap();
-        let hash = Sha256::digest(attestation_json.as_bytes());
-        let hash_str = hex::encode(&hash[..6]);  // Use first 6 bytes of the hash
+fn default_base_uri() -> Url {
+    Url::parse(DEFAULT_BASE_URI).expect("the default base URI is valid")
+}
 
-        format!("https://example.com/{}/{}.jsonl", encoded_predicate_type, hash_str)
+/// Refuses to store an attestation under a URI that already holds different content.
+/// Storing the same attestation again is not an error.
+fn check_conflict(uri: &str, existing: Option<&Arc<Attestation>>, attestation: &Attestation) -> Result<(), StorageError> {
+    if let Some(existing) = existing {
+        if to_canonical_vec(existing.as_ref())? != to_canonical_vec(attestation)? {
+            return Err(StorageError::conflict("Attestation", uri));
+        }
+    }
+    Ok(())
+}
+
+/// Attestations persisted as a single JSON document mapping URI to attestation, for
+/// tools such as git hooks that run without a long-lived server. Writes go through an
+/// atomic rename.
+pub struct FileAttestationStorage {
+    path: PathBuf,
+    base_uri: Url,
+    attestations: RwLock<BTreeMap<String, Arc<Attestation>>>,
+}
+
+impl FileAttestationStorage {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let attestations = match tokio::fs::read(&path).await {
+            Ok(bytes) => {
+                let stored: BTreeMap<String, Attestation> = serde_json::from_slice(&bytes).map_err(|e| StorageError::corrupt(path.display(), e))?;
+                stored.into_iter().map(|(uri, att)| (uri, Arc::new(att))).collect()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            base_uri: default_base_uri(),
+            attestations: RwLock::new(attestations),
+        })
+    }
+
+    /// Addresses newly stored attestations under `base_uri` instead of `DEFAULT_BASE_URI`.
+    pub fn with_base_uri(mut self, base_uri: Url) -> Self {
+        self.base_uri = directory(base_uri);
+        self
+    }
+
+    async fn persist(&self, attestations: &BTreeMap<String, Arc<Attestation>>) -> Result<(), StorageError> {
+        let stored: BTreeMap<&String, &Attestation> = attestations.iter().map(|(uri, att)| (uri, att.as_ref())).collect();
+        let json = serde_json::to_vec_pretty(&stored)?;
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AttestationStorage for FileAttestationStorage {
+    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, StorageError> {
+        let uri = AttestationRef::for_attestation(&self.base_uri, &attestation)?.to_string();
+        let mut attestations = self.attestations.write().await;
+        check_conflict(&uri, attestations.get(&uri), &attestation)?;
+        let mut next = attestations.clone();
+        next.insert(uri.clone(), attestation);
+        self.persist(&next).await?;
+        *attestations = next;
+        Ok(uri)
+    }
+
+    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+        let attestations = self.attestations.read().await;
+        attestations.get(uri).cloned().ok_or_else(|| not_found(uri))
+    }
+
+    async fn delete_attestation(&self, uri: &str) -> Result<(), StorageError> {
+        let mut attestations = self.attestations.write().await;
+        let mut next = attestations.clone();
+        next.remove(uri).ok_or_else(|| not_found(uri))?;
+        self.persist(&next).await?;
+        *attestations = next;
+        Ok(())
+    }
+
+    async fn list_attestation_entries(&self) -> Result<Vec<(String, Arc<Attestation>)>, StorageError> {
+        let attestations = self.attestations.read().await;
+        Ok(attestations.iter().map(|(uri, attestation)| (uri.clone(), attestation.clone())).collect())
+    }
+
+    async fn list_attestation_page(&self, after: Option<&str>, limit: usize) -> Result<Vec<(String, Arc<Attestation>)>, StorageError> {
+        let attestations = self.attestations.read().await;
+        let start = match after {
+            Some(after) => Bound::Excluded(after),
+            None => Bound::Unbounded,
+        };
+        Ok(attestations
+            .range::<str, _>((start, Bound::Unbounded))
+            .take(limit)
+            .map(|(uri, attestation)| (uri.clone(), attestation.clone()))
+            .collect())
+    }
+
+    fn uri_for(&self, attestation: &Attestation) -> Result<Option<String>, StorageError> {
+        Ok(Some(AttestationRef::for_attestation(&self.base_uri, attestation)?.to_string()))
     }
 }
 
 #[async_trait]
 impl AttestationStorage for InMemoryAttestationStorage {
-    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, Box<dyn Error + Send + Sync>> {
-        let uri = Self::generate_uri(&attestation);
+    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, StorageError> {
+        let uri = AttestationRef::for_attestation(&self.base_uri, &attestation)?.to_string();
         let mut attestations = self.attestations.write().await;
+        check_conflict(&uri, attestations.get(&uri), &attestation)?;
         attestations.insert(uri.clone(), attestation);
         Ok(uri)
     }
 
-    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, Box<dyn Error + Send + Sync>> {
+    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
         let attestations = self.attestations.read().await;
-        attestations
-            .get(uri)
-            .cloned()
-            .ok_or_else(|| Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "Attestation not found")) as Box<dyn Error + Send + Sync>)
+        attestations.get(uri).cloned().ok_or_else(|| not_found(uri))
     }
 
-    async fn delete_attestation(&self, uri: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    async fn delete_attestation(&self, uri: &str) -> Result<(), StorageError> {
         let mut attestations = self.attestations.write().await;
-        attestations.remove(uri).ok_or_else(|| Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, "Attestation not found")) as Box<dyn Error + Send + Sync>)?;
+        attestations.remove(uri).ok_or_else(|| not_found(uri))?;
         Ok(())
     }
 
-    async fn list_attestations(&self) -> Result<Vec<Arc<Attestation>>, Box<dyn Error + Send + Sync>> {
+    async fn list_attestation_entries(&self) -> Result<Vec<(String, Arc<Attestation>)>, StorageError> {
         let attestations = self.attestations.read().await;
-        Ok(attestations.values().cloned().collect())
+        Ok(attestations.iter().map(|(uri, attestation)| (uri.clone(), attestation.clone())).collect())
+    }
+
+    fn uri_for(&self, attestation: &Attestation) -> Result<Option<String>, StorageError> {
+        Ok(Some(AttestationRef::for_attestation(&self.base_uri, attestation)?.to_string()))
     }
 }
 
@@ -85,6 +234,8 @@ mod tests {
                 "predicateType": "https://in-toto.io/attestation/scai/attribute-report/v0.2",
                 "key": "value1"
             }),
+            envelope: None,
+            verification_material: None,
         });
 
         let attestation2 = Arc::new(Attestation {
@@ -95,6 +246,8 @@ mod tests {
                 "predicateType": "https://example.com/custom-attestation/v1",
                 "key": "value2"
             }),
+            envelope: None,
+            verification_material: None,
         });
 
         // Test storing attestations
@@ -106,6 +259,14 @@ mod tests {
         assert!(uri1.ends_with(".jsonl"));
         assert!(uri2.starts_with("https://example.com/https%3A%2F%2Fexample.com%2Fcustom-attestation%2Fv1/"));
         assert!(uri2.ends_with(".jsonl"));
+        assert!(uri1.parse::<AttestationRef>().unwrap().addresses(&attestation1));
+
+        // Storing the same attestation again is idempotent; different content under a
+        // taken URI is refused rather than overwriting it
+        assert_eq!(storage.store_attestation(attestation2.clone()).await.unwrap(), uri2);
+        storage.attestations.write().await.insert(uri2.clone(), attestation1.clone());
+        assert!(matches!(storage.store_attestation(attestation2.clone()).await, Err(StorageError::Conflict { .. })));
+        storage.attestations.write().await.insert(uri2.clone(), attestation2.clone());
 
         // Test retrieving attestations
         let retrieved1 = storage.get_attestation(&uri1).await.unwrap();
@@ -115,13 +276,40 @@ mod tests {
         // Test listing attestations
         let all_attestations = storage.list_attestations().await.unwrap();
         assert_eq!(all_attestations.len(), 2);
+        let entries = storage.list_attestation_entries().await.unwrap();
+        assert!(entries.iter().any(|(uri, attestation)| uri == &uri1 && attestation.id == "att1"));
 
         // Test deleting an attestation
         storage.delete_attestation(&uri1).await.unwrap();
         assert!(storage.get_attestation(&uri1).await.is_err());
 
         // Test error handling for non-existent attestation
-        assert!(storage.get_attestation("non_existent").await.is_err());
+        assert!(matches!(storage.get_attestation("non_existent").await, Err(StorageError::NotFound { .. })));
         assert!(storage.delete_attestation("non_existent").await.is_err());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_file_attestation_storage_persists() {
+        let path = std::env::temp_dir().join(format!("sisyphus-attestations-{}.json", uuid::Uuid::new_v4()));
+
+        let base_uri = Url::parse("https://attestations.acme.dev/v1").unwrap();
+        let storage = FileAttestationStorage::open(&path).await.unwrap().with_base_uri(base_uri);
+        let uri = storage.store_attestation(Arc::new(Attestation {
+            id: "att1".to_string(),
+            issuer: "issuer1".to_string(),
+            timestamp: Utc::now(),
+            content: json!({ "predicateType": "https://example.com/custom-attestation/v1" }),
+            envelope: None,
+            verification_material: None,
+        })).await.unwrap();
+        assert!(uri.starts_with("https://attestations.acme.dev/v1/"));
+        drop(storage);
+
+        let reopened = FileAttestationStorage::open(&path).await.unwrap();
+        assert_eq!(reopened.get_attestation(&uri).await.unwrap().id, "att1");
+        reopened.delete_attestation(&uri).await.unwrap();
+        assert!(FileAttestationStorage::open(&path).await.unwrap().list_attestations().await.unwrap().is_empty());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
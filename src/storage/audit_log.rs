@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::models::audit::{verify_chain, AuditEntry, AuditExport, AuditRecord, GENESIS_HASH};
+use crate::storage::error::StorageError;
+
+/// An append-only, hash-chained record of verification decisions.
+#[async_trait]
+pub trait AuditLog: Send + Sync {
+    /// Chains the record to the newest entry and appends it.
+    async fn append(&self, record: AuditRecord) -> Result<AuditEntry, StorageError>;
+    /// Entries whose sequence numbers fall in the range, oldest first.
+    async fn entries(&self, range: Range<u64>) -> Result<Vec<AuditEntry>, StorageError>;
+    async fn head(&self) -> Result<Option<AuditEntry>, StorageError>;
+
+    /// Exports a range of entries for an auditor, anchored to the entry before it.
+    async fn export(&self, range: Range<u64>) -> Result<AuditExport, StorageError> {
+        let head_hash = self.head().await?.map_or_else(|| GENESIS_HASH.to_string(), |head| head.hash);
+        let anchor_hash = match range.start.checked_sub(1) {
+            None => GENESIS_HASH.to_string(),
+            Some(before) => self
+                .entries(before..range.start)
+                .await?
+                .pop()
+                .map(|entry| entry.hash)
+                .ok_or_else(|| StorageError::not_found("Audit entry", before.to_string()))?,
+        };
+
+        Ok(AuditExport {
+            anchor_hash,
+            head_hash,
+            entries: self.entries(range).await?,
+        })
+    }
+
+    /// Re-hashes the whole log from its first entry and returns the hash of the newest.
+    async fn verify_integrity(&self) -> Result<String, StorageError> {
+        let entries = self.entries(0..u64::MAX).await?;
+        let head_hash = verify_chain(GENESIS_HASH, &entries).map_err(|e| StorageError::corrupt("Audit log", e))?;
+
+        let expected = self.head().await?.map_or_else(|| GENESIS_HASH.to_string(), |head| head.hash);
+        if head_hash != expected {
+            return Err(StorageError::corrupt("Audit log", format!("it ends at {} rather than its head {}", head_hash, expected)));
+        }
+        Ok(head_hash)
+    }
+}
+
+pub struct InMemoryAuditLog {
+    entries: RwLock<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditLog {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for InMemoryAuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AuditLog for InMemoryAuditLog {
+    async fn append(&self, record: AuditRecord) -> Result<AuditEntry, StorageError> {
+        let mut entries = self.entries.write().await;
+        let entry = AuditEntry::chain(entries.last(), record);
+        entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    async fn entries(&self, range: Range<u64>) -> Result<Vec<AuditEntry>, StorageError> {
+        let entries = self.entries.read().await;
+        Ok(entries.iter().filter(|e| range.contains(&e.sequence)).cloned().collect())
+    }
+
+    async fn head(&self) -> Result<Option<AuditEntry>, StorageError> {
+        Ok(self.entries.read().await.last().cloned())
+    }
+}
+
+/// An audit log kept as a JSON Lines file that is only ever appended to. Reads go to
+/// the file rather than a cache, so integrity checks see what is actually on disk.
+pub struct FileAuditLog {
+    path: PathBuf,
+    head: Mutex<Option<AuditEntry>>,
+}
+
+impl FileAuditLog {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let head = read_entries(&path).await?.pop();
+
+        Ok(Self {
+            path,
+            head: Mutex::new(head),
+        })
+    }
+}
+
+async fn read_entries(path: &Path) -> Result<Vec<AuditEntry>, StorageError> {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| StorageError::corrupt(format!("{} line {}", path.display(), i + 1), e)))
+        .collect()
+}
+
+#[async_trait]
+impl AuditLog for FileAuditLog {
+    async fn append(&self, record: AuditRecord) -> Result<AuditEntry, StorageError> {
+        let mut head = self.head.lock().await;
+        let entry = AuditEntry::chain(head.as_ref(), record);
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(&line).await?;
+        file.sync_data().await?;
+
+        *head = Some(entry.clone());
+        Ok(entry)
+    }
+
+    async fn entries(&self, range: Range<u64>) -> Result<Vec<AuditEntry>, StorageError> {
+        Ok(read_entries(&self.path).await?.into_iter().filter(|e| range.contains(&e.sequence)).collect())
+    }
+
+    async fn head(&self) -> Result<Option<AuditEntry>, StorageError> {
+        Ok(self.head.lock().await.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controlplane::report::ComponentStatus;
+    use crate::models::audit::{AuditError, AuditSubject};
+    use chrono::Utc;
+
+    fn record(component: &str, verdict: ComponentStatus) -> AuditRecord {
+        AuditRecord {
+            decided_at: Utc::now(),
+            subject: AuditSubject {
+                project: Some("shop".to_string()),
+                component: component.to_string(),
+                version: "1.0.0".to_string(),
+                digests: [("sha256".to_string(), "a".repeat(64))].into_iter().collect(),
+            },
+            policy: Some("pkg:github/acme/shop@1.0.0".to_string()),
+            attestations: Vec::new(),
+            decided_by: Vec::new(),
+            verdict,
+            requested_by: None,
+        }
+    }
+
+    fn audit_error(error: &StorageError) -> Option<&AuditError> {
+        match error {
+            StorageError::Corrupt { source, .. } => source.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_detects_tampering() {
+        let path = std::env::temp_dir().join(format!("sisyphus-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        let file_log = FileAuditLog::open(&path).await.unwrap();
+        let memory_log = InMemoryAuditLog::new();
+        let logs: [&dyn AuditLog; 2] = [&memory_log, &file_log];
+
+        for log in logs {
+            assert_eq!(log.verify_integrity().await.unwrap(), GENESIS_HASH);
+
+            log.append(record("frontend", ComponentStatus::Passed)).await.unwrap();
+            log.append(record("backend", ComponentStatus::Failed { reason: "critical vulnerabilities".to_string() })).await.unwrap();
+            let head = log.append(record("worker", ComponentStatus::Passed)).await.unwrap();
+            assert_eq!(head.sequence, 2);
+            assert_eq!(log.verify_integrity().await.unwrap(), head.hash);
+
+            // An auditor can check an exported range on its own
+            let export = log.export(1..3).await.unwrap();
+            assert_eq!(export.entries.len(), 2);
+            assert_eq!(export.verify().unwrap(), export.head_hash);
+        }
+
+        // Entries survive reopening and keep chaining from the last one
+        let reopened = FileAuditLog::open(&path).await.unwrap();
+        assert_eq!(reopened.append(record("frontend", ComponentStatus::Passed)).await.unwrap().sequence, 3);
+
+        // Flipping a verdict on disk breaks the chain
+        let contents = tokio::fs::read_to_string(&path).await.unwrap();
+        tokio::fs::write(&path, contents.replacen("\"failed\"", "\"passed\"", 1)).await.unwrap();
+        let err = reopened.verify_integrity().await.unwrap_err();
+        assert_eq!(audit_error(&err), Some(&AuditError::TamperedEntry { sequence: 1 }));
+
+        // So does dropping an entry
+        let lines: Vec<&str> = contents.lines().collect();
+        tokio::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2..].join("\n"))).await.unwrap();
+        let err = reopened.verify_integrity().await.unwrap_err();
+        assert!(!err.is_retryable());
+        assert_eq!(audit_error(&err), Some(&AuditError::SequenceGap { expected: 1, found: 2 }));
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fmt::Display;
+use thiserror::Error;
+
+/// Errors from the storage backends: attestation storage, the project and waiver
+/// registries and the audit log.
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("{kind} {key} not found")]
+    NotFound { kind: &'static str, key: String },
+    #[error("{kind} {key} already exists")]
+    AlreadyExists { kind: &'static str, key: String },
+    /// Different content was stored under a key that already holds something else.
+    #[error("{kind} {key} is already stored with different content")]
+    Conflict { kind: &'static str, key: String },
+    /// Stored data may not be removed or replaced, such as evidence under retention
+    /// or legal hold.
+    #[error("{kind} {key} cannot be removed or replaced: {reason}")]
+    Immutable { kind: &'static str, key: String, reason: String },
+    /// The request cannot be applied as it stands, such as an invalid project or a
+    /// version bump that does not move forward.
+    #[error("{0}")]
+    Invalid(String),
+    /// Stored data failed to parse or does not match its own digests.
+    #[error("{location} is corrupt: {source}")]
+    Corrupt {
+        location: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    #[error("Could not serialize data for storage: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Storage I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    /// The backend could not be reached or failed to answer.
+    #[error("Storage backend unavailable: {0}")]
+    Unavailable(#[source] Box<dyn Error + Send + Sync>),
+    /// The backend answered but refused the request, for instance for lack of
+    /// authorization.
+    #[error("Storage backend rejected the request: {0}")]
+    Rejected(#[source] Box<dyn Error + Send + Sync>),
+}
+
+impl StorageError {
+    pub fn not_found(kind: &'static str, key: impl Into<String>) -> Self {
+        StorageError::NotFound { kind, key: key.into() }
+    }
+
+    pub fn already_exists(kind: &'static str, key: impl Into<String>) -> Self {
+        StorageError::AlreadyExists { kind, key: key.into() }
+    }
+
+    pub fn conflict(kind: &'static str, key: impl Into<String>) -> Self {
+        StorageError::Conflict { kind, key: key.into() }
+    }
+
+    pub fn immutable(kind: &'static str, key: impl Into<String>, reason: impl Into<String>) -> Self {
+        StorageError::Immutable {
+            kind,
+            key: key.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn corrupt(location: impl Display, source: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        StorageError::Corrupt {
+            location: location.to_string(),
+            source: source.into(),
+        }
+    }
+
+    /// Whether the same request may succeed later without anything else changing, as
+    /// opposed to failing until the request or the stored data is fixed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, StorageError::Io(_) | StorageError::Unavailable(_))
+    }
+}
+
+#[cfg(feature = "oci")]
+impl From<reqwest::Error> for StorageError {
+    fn from(error: reqwest::Error) -> Self {
+        match error.status() {
+            Some(status) if status.is_client_error() => StorageError::Rejected(Box::new(error)),
+            _ => StorageError::Unavailable(Box::new(error)),
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::models::attestation::Attestation;
+use crate::models::retention::{Deletion, LegalHold, RetentionPolicy, Tombstone};
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
+
+/// What deleting an attestation does in an `ImmutableAttestationStorage`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionMode {
+    /// Deletes are refused; evidence only goes away by being purged.
+    Refuse,
+    /// Deletes leave a tombstone and keep the content until it is purged.
+    #[default]
+    Tombstone,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Ledger {
+    #[serde(default)]
+    tombstones: BTreeMap<String, Tombstone>,
+    #[serde(default)]
+    holds: BTreeMap<String, LegalHold>,
+    /// When each attestation was first stored, which retention is counted from.
+    #[serde(default)]
+    received: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// Write-once attestation storage on top of another store.
+///
+/// Stored attestations cannot be replaced. Deleting one either is refused or leaves a
+/// tombstone recording who deleted it and why, depending on the `DeletionMode`; a
+/// tombstoned attestation is hidden but its content is kept. Only `purge` removes
+/// content from the underlying store, and only once no legal hold is on it and the
+/// `RetentionPolicy` no longer requires it. Retention counts from when this store first
+/// received the attestation, as recorded in its ledger; attestations stored without the
+/// ledger have no receipt and are never purged. The default policy keeps everything forever.
+pub struct ImmutableAttestationStorage<S> {
+    inner: S,
+    mode: DeletionMode,
+    retention: RetentionPolicy,
+    /// Where the ledger of tombstones, holds and receipts is persisted, if anywhere.
+    path: Option<PathBuf>,
+    ledger: RwLock<Ledger>,
+}
+
+impl<S: AttestationStorage> ImmutableAttestationStorage<S> {
+    /// Keeps tombstones, legal holds and receipt times in memory.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            mode: DeletionMode::default(),
+            retention: RetentionPolicy::default(),
+            path: None,
+            ledger: RwLock::new(Ledger::default()),
+        }
+    }
+
+    /// Keeps tombstones, legal holds and receipt times in a JSON file, written through an
+    /// atomic rename.
+    pub async fn open(inner: S, path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let ledger = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| StorageError::corrupt(path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ledger::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            ledger: RwLock::new(ledger),
+            ..Self::new(inner)
+        })
+    }
+
+    pub fn with_mode(mut self, mode: DeletionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    async fn persist(&self, ledger: &Ledger) -> Result<(), StorageError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(ledger)?;
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+
+    pub async fn tombstone(&self, uri: &str) -> Result<Tombstone, StorageError> {
+        let ledger = self.ledger.read().await;
+        ledger.tombstones.get(uri).cloned().ok_or_else(|| StorageError::not_found("Tombstone", uri))
+    }
+
+    pub async fn list_tombstones(&self) -> Result<Vec<Tombstone>, StorageError> {
+        let ledger = self.ledger.read().await;
+        Ok(ledger.tombstones.values().cloned().collect())
+    }
+
+    /// The kept content of a deleted attestation that has not been purged.
+    pub async fn get_deleted_attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+        let ledger = self.ledger.read().await;
+        match ledger.tombstones.get(uri) {
+            Some(tombstone) if !tombstone.is_purged() => self.inner.get_attestation(uri).await,
+            _ => Err(StorageError::not_found("Deleted attestation", uri)),
+        }
+    }
+
+    /// Places a legal hold on a stored attestation, deleted or not, replacing any hold
+    /// already on it.
+    pub async fn place_hold(&self, uri: &str, hold: LegalHold) -> Result<(), StorageError> {
+        let mut ledger = self.ledger.write().await;
+        if ledger.tombstones.get(uri).is_some_and(Tombstone::is_purged) {
+            return Err(StorageError::not_found("Attestation", uri));
+        }
+        self.inner.get_attestation(uri).await?;
+
+        let mut next = ledger.clone();
+        next.holds.insert(uri.to_string(), hold);
+        self.persist(&next).await?;
+        *ledger = next;
+        Ok(())
+    }
+
+    pub async fn release_hold(&self, uri: &str) -> Result<LegalHold, StorageError> {
+        let mut ledger = self.ledger.write().await;
+        let mut next = ledger.clone();
+        let hold = next.holds.remove(uri).ok_or_else(|| StorageError::not_found("Legal hold", uri))?;
+        self.persist(&next).await?;
+        *ledger = next;
+        Ok(hold)
+    }
+
+    pub async fn hold(&self, uri: &str) -> Option<LegalHold> {
+        self.ledger.read().await.holds.get(uri).cloned()
+    }
+
+    /// Removes an attestation's content from the underlying store for good, whether or
+    /// not it was deleted first. The purge is recorded in its tombstone.
+    pub async fn purge(&self, uri: &str, deletion: &Deletion) -> Result<Tombstone, StorageError> {
+        let mut ledger = self.ledger.write().await;
+        if ledger.tombstones.get(uri).is_some_and(Tombstone::is_purged) {
+            return Err(StorageError::not_found("Attestation", uri));
+        }
+        if let Some(hold) = ledger.holds.get(uri) {
+            return Err(StorageError::immutable(
+                "Attestation",
+                uri,
+                format!("it is under legal hold by {}: {}", hold.placed_by, hold.reason),
+            ));
+        }
+
+        let attestation = self.inner.get_attestation(uri).await?;
+        let received = *ledger
+            .received
+            .get(uri)
+            .ok_or_else(|| StorageError::immutable("Attestation", uri, "no receipt time is recorded to count its retention from"))?;
+        match self.retention.retained_until(&attestation, received) {
+            None => return Err(StorageError::immutable("Attestation", uri, "its retention policy keeps it forever")),
+            Some(until) if until > Utc::now() => {
+                return Err(StorageError::immutable("Attestation", uri, format!("it must be retained until {}", until.to_rfc3339())));
+            }
+            Some(_) => {}
+        }
+
+        // Record the purge before carrying it out, so content never disappears unrecorded
+        let mut next = ledger.clone();
+        let tombstone = next
+            .tombstones
+            .entry(uri.to_string())
+            .or_insert_with(|| Tombstone::new(uri, &attestation, deletion));
+        tombstone.purged = Some(deletion.into());
+        let tombstone = tombstone.clone();
+        self.persist(&next).await?;
+
+        if let Err(e) = self.inner.delete_attestation(uri).await {
+            self.persist(&ledger).await?;
+            return Err(e);
+        }
+        *ledger = next;
+        Ok(tombstone)
+    }
+}
+
+#[async_trait]
+impl<S: AttestationStorage> AttestationStorage for ImmutableAttestationStorage<S> {
+    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, StorageError> {
+        // Hold the ledger so the URI cannot be tombstoned between the check and the store
+        let mut ledger = self.ledger.write().await;
+        let uri = self.inner.store_attestation(attestation).await?;
+        if let Some(tombstone) = ledger.tombstones.get(&uri) {
+            // Purged content was just stored again; take it back out
+            if tombstone.is_purged() {
+                self.inner.delete_attestation(&uri).await?;
+            }
+            return Err(StorageError::immutable(
+                "Attestation",
+                uri,
+                format!("it was deleted by {}: {}", tombstone.deleted.actor, tombstone.deleted.reason),
+            ));
+        }
+
+        // Storing the same content again keeps the first receipt
+        if !ledger.received.contains_key(&uri) {
+            let mut next = ledger.clone();
+            next.received.insert(uri.clone(), Utc::now());
+            self.persist(&next).await?;
+            *ledger = next;
+        }
+        Ok(uri)
+    }
+
+    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+        if self.ledger.read().await.tombstones.contains_key(uri) {
+            return Err(StorageError::not_found("Attestation", uri));
+        }
+        self.inner.get_attestation(uri).await
+    }
+
+    /// Always refused: a deletion has to say who is deleting and why.
+    async fn delete_attestation(&self, uri: &str) -> Result<(), StorageError> {
+        let reason = match self.mode {
+            DeletionMode::Refuse => "the storage is write-once",
+            DeletionMode::Tombstone => "a deletion must record who deleted it and why",
+        };
+        Err(StorageError::immutable("Attestation", uri, reason))
+    }
+
+    async fn delete_attestation_by(&self, uri: &str, deletion: &Deletion) -> Result<(), StorageError> {
+        if self.mode == DeletionMode::Refuse {
+            return Err(StorageError::immutable("Attestation", uri, "the storage is write-once"));
+        }
+
+        let mut ledger = self.ledger.write().await;
+        if ledger.tombstones.contains_key(uri) {
+            return Err(StorageError::not_found("Attestation", uri));
+        }
+        let attestation = self.inner.get_attestation(uri).await?;
+
+        let mut next = ledger.clone();
+        next.tombstones.insert(uri.to_string(), Tombstone::new(uri, &attestation, deletion));
+        self.persist(&next).await?;
+        *ledger = next;
+        Ok(())
+    }
+
+    async fn list_attestation_entries(&self) -> Result<Vec<(String, Arc<Attestation>)>, StorageError> {
+        let ledger = self.ledger.read().await;
+        Ok(self
+            .inner
+            .list_attestation_entries()
+            .await?
+            .into_iter()
+            .filter(|(uri, _)| !ledger.tombstones.contains_key(uri))
+            .collect())
+    }
+
+    fn uri_for(&self, attestation: &Attestation) -> Result<Option<String>, StorageError> {
+        self.inner.uri_for(attestation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    use crate::models::retention::Retention;
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+
+    fn attestation(id: &str, predicate_type: &str, age_days: i64) -> Arc<Attestation> {
+        Arc::new(Attestation {
+            id: id.to_string(),
+            issuer: "ci".to_string(),
+            timestamp: Utc::now() - Duration::days(age_days),
+            content: json!({ "predicateType": predicate_type }),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_retention_and_legal_hold() {
+        let retention = RetentionPolicy::new(Retention::Days(30)).with_predicate_type("https://slsa.dev/provenance/v1", Retention::Forever);
+        let storage = ImmutableAttestationStorage::new(InMemoryAttestationStorage::new()).with_retention(retention);
+        let deletion = Deletion::new("alice", "superseded by a rebuild").unwrap();
+
+        let scan = attestation("scan", "https://example.com/scan/v1", 60);
+        let scan_uri = storage.store_attestation(scan.clone()).await.unwrap();
+        let provenance_uri = storage.store_attestation(attestation("build", "https://slsa.dev/provenance/v1", 60)).await.unwrap();
+        let recent_uri = storage.store_attestation(attestation("recent", "https://example.com/scan/v1", 1)).await.unwrap();
+
+        // A delete without who and why is refused; with them it leaves a tombstone
+        assert!(matches!(storage.delete_attestation(&scan_uri).await, Err(StorageError::Immutable { .. })));
+        storage.delete_attestation_by(&scan_uri, &deletion).await.unwrap();
+        assert!(matches!(storage.get_attestation(&scan_uri).await, Err(StorageError::NotFound { .. })));
+        assert_eq!(storage.list_attestations().await.unwrap().len(), 2);
+        assert_eq!(storage.get_deleted_attestation(&scan_uri).await.unwrap().id, "scan");
+        assert_eq!(storage.tombstone(&scan_uri).await.unwrap().deleted.actor, "alice");
+        assert!(matches!(storage.store_attestation(scan.clone()).await, Err(StorageError::Immutable { .. })));
+
+        // Retention counts from receipt, not from the attestation's own timestamp
+        assert!(storage.purge(&scan_uri, &deletion).await.is_err());
+        for uri in [&scan_uri, &provenance_uri] {
+            storage.ledger.write().await.received.insert(uri.clone(), Utc::now() - Duration::days(60));
+        }
+
+        // Purging follows retention by predicate type and age, and legal holds
+        assert!(storage.purge(&provenance_uri, &deletion).await.is_err());
+        assert!(storage.purge(&recent_uri, &deletion).await.is_err());
+        storage.place_hold(&scan_uri, LegalHold::new("legal", "litigation")).await.unwrap();
+        assert!(matches!(storage.purge(&scan_uri, &deletion).await, Err(StorageError::Immutable { .. })));
+        storage.release_hold(&scan_uri).await.unwrap();
+        let tombstone = storage.purge(&scan_uri, &deletion).await.unwrap();
+        assert!(tombstone.is_purged());
+        assert!(storage.get_deleted_attestation(&scan_uri).await.is_err());
+
+        let refusing = ImmutableAttestationStorage::new(InMemoryAttestationStorage::new()).with_mode(DeletionMode::Refuse);
+        let uri = refusing.store_attestation(attestation("scan", "https://example.com/scan/v1", 60)).await.unwrap();
+        assert!(refusing.delete_attestation_by(&uri, &deletion).await.is_err());
+        assert!(refusing.get_attestation(&uri).await.is_ok());
+    }
+}
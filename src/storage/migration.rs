@@ -0,0 +1,486 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tracing::warn;
+
+use crate::models::attestation::Attestation;
+use crate::models::canonical::canonical_sha256;
+use crate::models::policy::{Policy, PolicyError};
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
+use crate::storage::policy_repository::PolicyRepository;
+
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 1_000;
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Why an item was not copied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationFailure {
+    pub reason: String,
+    /// Whether running the migration again may copy it, e.g. after an outage.
+    pub retryable: bool,
+}
+
+impl MigrationFailure {
+    fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            retryable: false,
+        }
+    }
+}
+
+impl From<StorageError> for MigrationFailure {
+    fn from(error: StorageError) -> Self {
+        Self {
+            reason: error.to_string(),
+            retryable: error.is_retryable(),
+        }
+    }
+}
+
+impl From<PolicyError> for MigrationFailure {
+    fn from(error: PolicyError) -> Self {
+        Self {
+            reason: error.to_string(),
+            retryable: error.is_retryable(),
+        }
+    }
+}
+
+/// Reconciles a migration: what was copied where, what was not and why, and how source
+/// and target compare afterwards. It doubles as the checkpoint a migration resumes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestationMigrationReport {
+    pub started_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub source_count: usize,
+    /// Source URI to target URI of every attestation copied and verified. The two are
+    /// equal where the target preserved the URI.
+    pub uri_map: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failed: BTreeMap<String, MigrationFailure>,
+    /// Attestations this run skipped because an earlier run had copied them.
+    #[serde(default)]
+    pub resumed: usize,
+    /// Mapped target URIs the target no longer has.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_in_target: Vec<String>,
+    /// Target URIs no source attestation maps to, such as ones stored there before.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmapped_in_target: Vec<String>,
+}
+
+impl AttestationMigrationReport {
+    fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            finished_at: None,
+            source_count: 0,
+            uri_map: BTreeMap::new(),
+            failed: BTreeMap::new(),
+            resumed: 0,
+            missing_in_target: Vec::new(),
+            unmapped_in_target: Vec::new(),
+        }
+    }
+
+    /// How many copied attestations kept their URI.
+    pub fn preserved(&self) -> usize {
+        self.uri_map.iter().filter(|(source, target)| source == target).count()
+    }
+
+    /// Whether every source attestation is in the target under a mapped URI.
+    pub fn is_reconciled(&self) -> bool {
+        self.failed.is_empty() && self.missing_in_target.is_empty() && self.uri_map.len() >= self.source_count
+    }
+}
+
+/// Copies every attestation from one storage backend to another.
+///
+/// Each copy is read back from the target and its canonical digest compared with the
+/// source's before it counts as copied. The target decides the URI; with
+/// `preserving_uris` an attestation the target would store under a different URI, or
+/// cannot say where it would store, fails without being stored. With a checkpoint file
+/// the report is saved as the migration goes, and a later run skips what is already
+/// mapped in it.
+///
+/// Source and target are read a page at a time, so apart from the report's URI map only
+/// one page of attestations is held at once; see `list_attestation_page` for stores
+/// that cannot page natively.
+pub struct AttestationMigration<'a, S: ?Sized, T: ?Sized> {
+    source: &'a S,
+    target: &'a T,
+    preserve_uris: bool,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: usize,
+    page_size: usize,
+}
+
+impl<'a, S, T> AttestationMigration<'a, S, T>
+where
+    S: AttestationStorage + ?Sized,
+    T: AttestationStorage + ?Sized,
+{
+    pub fn new(source: &'a S, target: &'a T) -> Self {
+        Self {
+            source,
+            target,
+            preserve_uris: false,
+            checkpoint: None,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn preserving_uris(mut self) -> Self {
+        self.preserve_uris = true;
+        self
+    }
+
+    pub fn with_checkpoint(mut self, path: impl AsRef<Path>) -> Self {
+        self.checkpoint = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Saves the checkpoint after every `copies` copies.
+    pub fn with_checkpoint_interval(mut self, copies: usize) -> Self {
+        self.checkpoint_interval = copies.max(1);
+        self
+    }
+
+    /// Reads source and target `attestations` entries at a time.
+    pub fn with_page_size(mut self, attestations: usize) -> Self {
+        self.page_size = attestations.max(1);
+        self
+    }
+
+    pub async fn run(&self) -> Result<AttestationMigrationReport, StorageError> {
+        let mut report = match &self.checkpoint {
+            Some(path) => read_checkpoint(path).await?.unwrap_or_else(AttestationMigrationReport::new),
+            None => AttestationMigrationReport::new(),
+        };
+        // Failures from an earlier run are retried
+        report.failed.clear();
+        report.resumed = 0;
+        report.source_count = 0;
+        report.finished_at = None;
+
+        let mut copies = 0;
+        let mut after = None;
+        loop {
+            let page = self.source.list_attestation_page(after.as_deref(), self.page_size).await?;
+            let Some((last, _)) = page.last() else {
+                break;
+            };
+            after = Some(last.clone());
+
+            for (uri, attestation) in page {
+                report.source_count += 1;
+                if report.uri_map.contains_key(&uri) {
+                    report.resumed += 1;
+                    continue;
+                }
+
+                match self.copy(&uri, &attestation).await {
+                    Ok(target_uri) => {
+                        report.uri_map.insert(uri, target_uri);
+                    }
+                    Err(failure) => {
+                        warn!(uri = %uri, error = %failure.reason, "Could not migrate attestation");
+                        report.failed.insert(uri, failure);
+                    }
+                }
+
+                copies += 1;
+                if copies % self.checkpoint_interval == 0 {
+                    self.save(&report).await?;
+                }
+            }
+        }
+
+        self.reconcile(&mut report).await?;
+        report.finished_at = Some(Utc::now());
+        self.save(&report).await?;
+        Ok(report)
+    }
+
+    async fn copy(&self, uri: &str, attestation: &Arc<Attestation>) -> Result<String, MigrationFailure> {
+        if self.preserve_uris {
+            match self.target.uri_for(attestation)? {
+                Some(target_uri) if target_uri == uri => {}
+                Some(target_uri) => return Err(MigrationFailure::new(format!("the target would store it as {}", target_uri))),
+                None => return Err(MigrationFailure::new("the target cannot tell which URI it would store it under")),
+            }
+        }
+
+        let digest = canonical_sha256(attestation.as_ref()).map_err(StorageError::from)?;
+        let target_uri = self.target.store_attestation(attestation.clone()).await?;
+
+        let copied = self.target.get_attestation(&target_uri).await?;
+        if canonical_sha256(copied.as_ref()).map_err(StorageError::from)? != digest {
+            return Err(MigrationFailure::new(format!("{} does not hold the same attestation", target_uri)));
+        }
+        Ok(target_uri)
+    }
+
+    /// Pages through the target, noting mapped URIs it lacks and URIs nothing maps to.
+    async fn reconcile(&self, report: &mut AttestationMigrationReport) -> Result<(), StorageError> {
+        let mut missing: BTreeSet<String> = report.uri_map.values().cloned().collect();
+        let mut unmapped = Vec::new();
+        let mut after = None;
+        loop {
+            let page = self.target.list_attestation_page(after.as_deref(), self.page_size).await?;
+            let Some((last, _)) = page.last() else {
+                break;
+            };
+            after = Some(last.clone());
+            unmapped.extend(page.into_iter().map(|(uri, _)| uri).filter(|uri| !missing.remove(uri)));
+        }
+
+        report.missing_in_target = missing.into_iter().collect();
+        report.unmapped_in_target = unmapped;
+        Ok(())
+    }
+
+    async fn save(&self, report: &AttestationMigrationReport) -> Result<(), StorageError> {
+        let Some(path) = &self.checkpoint else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(report)?;
+        let tmp_path = path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+}
+
+async fn read_checkpoint(path: &Path) -> Result<Option<AttestationMigrationReport>, StorageError> {
+    match tokio::fs::read(path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| StorageError::corrupt(path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes every attestation as a line of JSON, in the form `AttestationIngestor` reads
+/// back, a page at a time. Returns how many were written.
+pub async fn export_attestations<S, W>(source: &S, mut output: W) -> Result<usize, StorageError>
+where
+    S: AttestationStorage + ?Sized,
+    W: AsyncWrite + Unpin,
+{
+    let mut count = 0;
+    let mut after = None;
+    loop {
+        let page = source.list_attestation_page(after.as_deref(), DEFAULT_PAGE_SIZE).await?;
+        let Some((last, _)) = page.last() else {
+            break;
+        };
+        after = Some(last.clone());
+
+        for (_, attestation) in &page {
+            let mut line = serde_json::to_vec(attestation.as_ref())?;
+            line.push(b'\n');
+            output.write_all(&line).await?;
+        }
+        count += page.len();
+    }
+    output.flush().await?;
+    Ok(count)
+}
+
+/// The outcome of copying every policy version from one repository to another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyMigrationReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub source_count: usize,
+    /// `purl@version` of every policy copied and verified.
+    pub copied: Vec<String>,
+    /// Policies the target already held unchanged, e.g. from an interrupted run.
+    pub already_present: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failed: BTreeMap<String, MigrationFailure>,
+    /// Policies the target holds that the source does not.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmapped_in_target: Vec<String>,
+}
+
+impl PolicyMigrationReport {
+    pub fn is_reconciled(&self) -> bool {
+        self.failed.is_empty() && self.copied.len() + self.already_present.len() == self.source_count
+    }
+}
+
+/// Copies every version of every policy from one repository to another. Versions the
+/// target already holds with the same content are skipped, so an interrupted migration
+/// is resumed by running it again; ones it holds with different content fail.
+pub struct PolicyMigration<'a, S: ?Sized, T: ?Sized> {
+    source: &'a S,
+    target: &'a T,
+}
+
+impl<'a, S, T> PolicyMigration<'a, S, T>
+where
+    S: PolicyRepository + ?Sized,
+    T: PolicyRepository + ?Sized,
+{
+    pub fn new(source: &'a S, target: &'a T) -> Self {
+        Self { source, target }
+    }
+
+    pub async fn run(&self) -> Result<PolicyMigrationReport, PolicyError> {
+        let started_at = Utc::now();
+        let mut copied = Vec::new();
+        let mut already_present = Vec::new();
+        let mut failed = BTreeMap::new();
+        let mut source_keys = BTreeSet::new();
+
+        for purl in self.source.list_purls().await? {
+            for policy in self.source.list_policies(&purl).await? {
+                let key = policy_key(&policy);
+                source_keys.insert(key.clone());
+                match self.copy(&policy).await {
+                    Ok(true) => copied.push(key),
+                    Ok(false) => already_present.push(key),
+                    Err(failure) => {
+                        warn!(policy = %key, error = %failure.reason, "Could not migrate policy");
+                        failed.insert(key, failure);
+                    }
+                }
+            }
+        }
+
+        let mut unmapped_in_target = Vec::new();
+        for purl in self.target.list_purls().await? {
+            for policy in self.target.list_policies(&purl).await? {
+                let key = policy_key(&policy);
+                if !source_keys.contains(&key) {
+                    unmapped_in_target.push(key);
+                }
+            }
+        }
+
+        Ok(PolicyMigrationReport {
+            started_at,
+            finished_at: Utc::now(),
+            source_count: source_keys.len(),
+            copied,
+            already_present,
+            failed,
+            unmapped_in_target,
+        })
+    }
+
+    /// Copies one policy version, returning whether the target lacked it.
+    async fn copy(&self, policy: &Policy) -> Result<bool, MigrationFailure> {
+        let digest = canonical_sha256(policy).map_err(StorageError::from)?;
+        let existing = match self.target.get_policy(&policy.purl, Some(&policy.version)).await {
+            Ok(existing) => Some(existing),
+            Err(PolicyError::NotFound { .. } | PolicyError::VersionNotFound { .. }) => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(existing) = existing {
+            return match canonical_sha256(existing.as_ref()).map_err(StorageError::from)? == digest {
+                true => Ok(false),
+                false => Err(MigrationFailure::new("the target holds this version with different content")),
+            };
+        }
+
+        self.target.add_policy(policy.clone()).await?;
+        let copied = self.target.get_policy(&policy.purl, Some(&policy.version)).await?;
+        if canonical_sha256(copied.as_ref()).map_err(StorageError::from)? != digest {
+            return Err(MigrationFailure::new("the target returns different content for the copy"));
+        }
+        Ok(true)
+    }
+}
+
+fn policy_key(policy: &Policy) -> String {
+    format!("{}@{}", policy.purl, policy.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use url::Url;
+
+    use crate::models::policy::PolicyRules;
+    use crate::storage::attestation_storage::{FileAttestationStorage, InMemoryAttestationStorage};
+    use crate::storage::policy_repository::{FilePolicyRepository, InMemoryPolicyRepository};
+
+    #[tokio::test]
+    async fn test_migrate_attestations_and_policies() {
+        let dir = std::env::temp_dir().join(format!("sisyphus-migration-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let source = InMemoryAttestationStorage::new();
+        for id in ["att1", "att2", "att3"] {
+            source.store_attestation(Arc::new(Attestation {
+                id: id.to_string(),
+                issuer: "ci".to_string(),
+                timestamp: Utc::now(),
+                content: json!({ "predicateType": "https://slsa.dev/provenance/v1" }),
+                ..Default::default()
+            })).await.unwrap();
+        }
+
+        // A first run is cut short after one copy; its checkpoint has that copy mapped
+        let target = FileAttestationStorage::open(dir.join("attestations.json")).await.unwrap();
+        let checkpoint = dir.join("checkpoint.json");
+        let first = source.list_attestation_entries().await.unwrap().remove(0);
+        let mut partial = AttestationMigrationReport::new();
+        partial.uri_map.insert(first.0.clone(), target.store_attestation(first.1).await.unwrap());
+        tokio::fs::write(&checkpoint, serde_json::to_vec(&partial).unwrap()).await.unwrap();
+
+        let report = AttestationMigration::new(&source, &target).preserving_uris().with_checkpoint(&checkpoint).run().await.unwrap();
+        assert_eq!((report.source_count, report.resumed, report.uri_map.len(), report.preserved()), (3, 1, 3, 3));
+        assert!(report.is_reconciled(), "{:?}", report);
+        assert_eq!(read_checkpoint(&checkpoint).await.unwrap().unwrap().uri_map, report.uri_map);
+
+        // Another base URI maps every URI to a new one; preserving URIs it stores nothing
+        let base_uri = Url::parse("https://attestations.acme.dev/").unwrap();
+        let strict = InMemoryAttestationStorage::new().with_base_uri(base_uri.clone());
+        assert_eq!(AttestationMigration::new(&target, &strict).preserving_uris().run().await.unwrap().failed.len(), 3);
+        assert!(strict.list_attestations().await.unwrap().is_empty());
+
+        let rebased = InMemoryAttestationStorage::new().with_base_uri(base_uri);
+        let report = AttestationMigration::new(&target, &rebased).with_page_size(2).run().await.unwrap();
+        assert!(report.is_reconciled());
+        assert_eq!((report.source_count, report.preserved()), (3, 0));
+        assert!(report.uri_map.values().all(|uri| uri.starts_with("https://attestations.acme.dev/")));
+
+        let mut exported = Vec::new();
+        assert_eq!(export_attestations(&rebased, &mut exported).await.unwrap(), 3);
+        assert_eq!(exported.iter().filter(|b| **b == b'\n').count(), 3);
+
+        let policies = InMemoryPolicyRepository::new();
+        for (purl, version) in [("pkg:github/acme/api", "1.0.0"), ("pkg:github/acme/api", "1.1.0"), ("pkg:github/acme/web", "2.0.0")] {
+            policies.add_policy(Policy {
+                purl: purl.to_string(),
+                version: version.to_string(),
+                rules: PolicyRules::new(["ci".to_string()].into(), 30, 0, 0),
+                selection: None,
+            }).await.unwrap();
+        }
+        let policy_target = FilePolicyRepository::open(dir.join("policies.json")).await.unwrap();
+        policy_target.add_policy((*policies.get_policy("pkg:github/acme/api", Some("1.0.0")).await.unwrap()).clone()).await.unwrap();
+        let mut diverged = (*policies.get_policy("pkg:github/acme/web", Some("2.0.0")).await.unwrap()).clone();
+        diverged.rules.max_age_days = 7;
+        policy_target.add_policy(diverged).await.unwrap();
+
+        let report = PolicyMigration::new(&policies, &policy_target).run().await.unwrap();
+        assert_eq!(report.copied, ["pkg:github/acme/api@1.1.0"]);
+        assert_eq!(report.already_present, ["pkg:github/acme/api@1.0.0"]);
+        assert!(report.failed.contains_key("pkg:github/acme/web@2.0.0"));
+        assert!(!report.is_reconciled());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -1,2 +1,11 @@
+pub mod error;
 pub mod policy_repository;
-pub mod attestation_storage;
\ No newline at end of file
+pub mod attestation_storage;
+pub mod attestation_ref;
+pub mod immutable_attestation_storage;
+pub mod migration;
+pub mod waiver_repository;
+pub mod project_registry;
+pub mod audit_log;
+#[cfg(feature = "oci")]
+pub mod oci_attestation_storage;
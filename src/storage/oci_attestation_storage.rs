@@ -0,0 +1,629 @@
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use url::Url;
+
+use crate::models::attestation::Attestation;
+use crate::models::canonical::to_canonical_vec;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
+
+/// The artifact type attestation manifests are pushed and discovered with.
+pub const ATTESTATION_ARTIFACT_TYPE: &str = "application/vnd.in-toto+json";
+/// The media type of the layer holding the serialized `Attestation`.
+pub const ATTESTATION_MEDIA_TYPE: &str = "application/vnd.sisyphus.attestation.v1+json";
+
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const INDEX_MEDIA_TYPE: &str = "application/vnd.oci.image.index.v1+json";
+const EMPTY_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+const EMPTY_CONFIG: &[u8] = b"{}";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_type: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl Descriptor {
+    fn of(media_type: &str, bytes: &[u8]) -> Self {
+        Self {
+            media_type: media_type.to_string(),
+            digest: sha256_digest(bytes),
+            size: bytes.len() as u64,
+            artifact_type: None,
+            annotations: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    schema_version: u32,
+    media_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    artifact_type: Option<String>,
+    config: Descriptor,
+    layers: Vec<Descriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subject: Option<Descriptor>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Index {
+    schema_version: u32,
+    media_type: String,
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self {
+            schema_version: 2,
+            media_type: INDEX_MEDIA_TYPE.to_string(),
+            manifests: Vec::new(),
+        }
+    }
+}
+
+fn sha256_digest(bytes: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(bytes)))
+}
+
+/// The tag the OCI 1.1 referrers tag schema keeps a subject's referrers index under.
+fn fallback_tag(subject_digest: &str) -> String {
+    subject_digest.replacen(':', "-", 1)
+}
+
+/// The target of the `rel="next"` link among `Link` headers, which registries use to
+/// page through tag listings.
+fn next_link(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(reqwest::header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let (target, params) = link.split_once(';')?;
+            let is_next = params.split(';').any(|param| matches!(param.trim(), "rel=\"next\"" | "rel=next"));
+            is_next.then(|| target.trim().trim_start_matches('<').trim_end_matches('>'))
+        })
+}
+
+fn not_found(uri: &str) -> StorageError {
+    StorageError::not_found("Attestation", uri)
+}
+
+/// Stores attestations in an OCI registry as artifacts attached to the image they
+/// describe.
+///
+/// Each attestation is pushed as a manifest whose `subject` is the first `sha256`
+/// digest among the statement's subjects, which must already exist in the repository.
+/// Registries implementing the OCI 1.1 referrers API index it themselves; for others
+/// the `sha256-<hex>` referrers tag is maintained. URIs have the form
+/// `oci://<registry>/<repository>@<manifest digest>`. A registry URL with a path, such
+/// as a mirror served under a prefix, has the API under that path.
+pub struct OciAttestationStorage {
+    client: Client,
+    registry: Url,
+    repository: String,
+    bearer_token: Option<String>,
+}
+
+impl OciAttestationStorage {
+    pub fn new(registry: Url, repository: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            registry,
+            repository: repository.into(),
+            bearer_token: None,
+        }
+    }
+
+    pub fn with_bearer_token(mut self, token: String) -> Self {
+        self.bearer_token = Some(token);
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder, StorageError> {
+        Ok(self.authorize(self.client.request(method, self.endpoint(path)?)))
+    }
+
+    /// The URL of a repository API path, below any path the registry URL has.
+    fn endpoint(&self, path: &str) -> Result<Url, StorageError> {
+        let mut base = self.registry.clone();
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        base.join(&format!("v2/{}/{}", self.repository, path))
+            .map_err(|e| StorageError::Invalid(format!("Invalid registry path {}: {}", path, e)))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.bearer_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    fn uri_prefix(&self) -> String {
+        let host = self.registry.host_str().unwrap_or_default();
+        match self.registry.port() {
+            Some(port) => format!("oci://{}:{}/{}@", host, port, self.repository),
+            None => format!("oci://{}/{}@", host, self.repository),
+        }
+    }
+
+    fn manifest_digest<'a>(&self, uri: &'a str) -> Result<&'a str, StorageError> {
+        uri.strip_prefix(&self.uri_prefix())
+            .filter(|digest| digest.starts_with("sha256:"))
+            .ok_or_else(|| not_found(uri))
+    }
+
+    async fn push_blob(&self, media_type: &str, bytes: Vec<u8>) -> Result<Descriptor, StorageError> {
+        let descriptor = Descriptor::of(media_type, &bytes);
+
+        let response = self.request(Method::POST, "blobs/uploads/")?.send().await?.error_for_status()?;
+        let location = response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .ok_or_else(|| StorageError::Unavailable("Registry did not return an upload location".into()))?
+            .to_str()
+            .map_err(|e| StorageError::Unavailable(Box::new(e)))?;
+
+        // The location may be relative to the registry and may already carry a query
+        let mut upload = self.registry.join(location).map_err(|e| StorageError::Unavailable(Box::new(e)))?;
+        upload.query_pairs_mut().append_pair("digest", &descriptor.digest);
+
+        let request = self.client.put(upload).header(reqwest::header::CONTENT_TYPE, "application/octet-stream").body(bytes);
+        self.authorize(request).send().await?.error_for_status()?;
+
+        Ok(descriptor)
+    }
+
+    /// Fetches a manifest or index, returning its descriptor and parsed body. A manifest
+    /// fetched by digest must hash to it; only `sha256` digests are supported.
+    async fn get_manifest<T: DeserializeOwned>(&self, reference: &str) -> Result<Option<(Descriptor, T)>, StorageError> {
+        let response = self
+            .request(Method::GET, &format!("manifests/{}", reference))?
+            .header(reqwest::header::ACCEPT, format!("{}, {}", MANIFEST_MEDIA_TYPE, INDEX_MEDIA_TYPE))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        let media_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(MANIFEST_MEDIA_TYPE)
+            .to_string();
+
+        let bytes = response.bytes().await?;
+        // Tags cannot contain a colon, so any reference with one is a digest
+        if reference.contains(':') && sha256_digest(&bytes) != reference {
+            return Err(StorageError::corrupt(format!("Manifest {}", reference), "its content does not match its digest"));
+        }
+        let body = serde_json::from_slice(&bytes).map_err(|e| StorageError::corrupt(format!("Manifest {}", reference), e))?;
+        Ok(Some((Descriptor::of(&media_type, &bytes), body)))
+    }
+
+    async fn put_manifest(&self, reference: &str, media_type: &str, bytes: Vec<u8>) -> Result<reqwest::Response, StorageError> {
+        Ok(self
+            .request(Method::PUT, &format!("manifests/{}", reference))?
+            .header(reqwest::header::CONTENT_TYPE, media_type)
+            .body(bytes)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Applies a change to the referrers tag schema index of a subject.
+    async fn update_fallback_index<F>(&self, subject_digest: &str, change: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut Vec<Descriptor>),
+    {
+        let tag = fallback_tag(subject_digest);
+        let mut index: Index = self.get_manifest(&tag).await?.map(|(_, index)| index).unwrap_or_default();
+        change(&mut index.manifests);
+        self.put_manifest(&tag, INDEX_MEDIA_TYPE, serde_json::to_vec(&index)?).await?;
+        Ok(())
+    }
+
+    /// Lists the attestation manifests attached to a subject, through the referrers API
+    /// or, when the registry does not offer it, the referrers tag.
+    pub async fn list_referrers(&self, subject_digest: &str) -> Result<Vec<Descriptor>, StorageError> {
+        let response = self
+            .request(Method::GET, &format!("referrers/{}", subject_digest))?
+            .query(&[("artifactType", ATTESTATION_ARTIFACT_TYPE)])
+            .send()
+            .await?;
+
+        let index: Index = if response.status() == StatusCode::NOT_FOUND {
+            self.get_manifest(&fallback_tag(subject_digest)).await?.map(|(_, index)| index).unwrap_or_default()
+        } else {
+            response.error_for_status()?.json().await?
+        };
+
+        // Registries may ignore the filter, and the tag schema index is never filtered
+        Ok(index
+            .manifests
+            .into_iter()
+            .filter(|d| d.artifact_type.as_deref() == Some(ATTESTATION_ARTIFACT_TYPE))
+            .collect())
+    }
+
+    async fn fetch(&self, manifest_digest: &str) -> Result<(Manifest, Arc<Attestation>), StorageError> {
+        let (_, manifest): (Descriptor, Manifest) = self.get_manifest(manifest_digest).await?.ok_or_else(|| not_found(manifest_digest))?;
+        let layer = manifest
+            .layers
+            .iter()
+            .find(|l| l.media_type == ATTESTATION_MEDIA_TYPE)
+            .ok_or_else(|| StorageError::corrupt(format!("Manifest {}", manifest_digest), "it has no attestation layer"))?;
+
+        let bytes = self
+            .request(Method::GET, &format!("blobs/{}", layer.digest))?
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        if sha256_digest(&bytes) != layer.digest {
+            return Err(StorageError::corrupt(format!("Blob {}", layer.digest), "its content does not match its digest"));
+        }
+
+        let attestation = serde_json::from_slice(&bytes).map_err(|e| StorageError::corrupt(format!("Blob {}", layer.digest), e))?;
+        Ok((manifest, Arc::new(attestation)))
+    }
+}
+
+#[async_trait]
+impl AttestationStorage for OciAttestationStorage {
+    async fn store_attestation(&self, attestation: Arc<Attestation>) -> Result<String, StorageError> {
+        let subject_digest = attestation.content["subject"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find_map(|s| s["digest"]["sha256"].as_str())
+            .map(|hex| format!("sha256:{}", hex.to_lowercase()))
+            .ok_or_else(|| StorageError::Invalid("Attestation has no sha256 subject digest to attach to".to_string()))?;
+
+        let (subject, _): (Descriptor, serde_json::Value) = self
+            .get_manifest(&subject_digest)
+            .await?
+            .ok_or_else(|| StorageError::not_found("Subject", format!("{} in {}", subject_digest, self.repository)))?;
+
+        let config = self.push_blob(EMPTY_MEDIA_TYPE, EMPTY_CONFIG.to_vec()).await?;
+        let layer = self.push_blob(ATTESTATION_MEDIA_TYPE, to_canonical_vec(attestation.as_ref())?).await?;
+
+        let annotations = BTreeMap::from([
+            ("org.opencontainers.image.created".to_string(), attestation.timestamp.to_rfc3339()),
+            ("dev.sisyphus.attestation.id".to_string(), attestation.id.clone()),
+        ]);
+        let manifest = Manifest {
+            schema_version: 2,
+            media_type: MANIFEST_MEDIA_TYPE.to_string(),
+            artifact_type: Some(ATTESTATION_ARTIFACT_TYPE.to_string()),
+            config,
+            layers: vec![layer],
+            subject: Some(subject),
+            annotations: annotations.clone(),
+        };
+        let bytes = serde_json::to_vec(&manifest)?;
+        let mut descriptor = Descriptor::of(MANIFEST_MEDIA_TYPE, &bytes);
+        descriptor.artifact_type = Some(ATTESTATION_ARTIFACT_TYPE.to_string());
+        descriptor.annotations = annotations;
+
+        let response = self.put_manifest(&descriptor.digest, MANIFEST_MEDIA_TYPE, bytes).await?;
+
+        // Registries that index referrers themselves acknowledge the subject
+        if !response.headers().contains_key("OCI-Subject") {
+            let entry = descriptor.clone();
+            self.update_fallback_index(&subject_digest, |manifests| {
+                manifests.retain(|m| m.digest != entry.digest);
+                manifests.push(entry);
+            }).await?;
+        }
+
+        Ok(format!("{}{}", self.uri_prefix(), descriptor.digest))
+    }
+
+    async fn get_attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+        let (_, attestation) = self.fetch(self.manifest_digest(uri)?).await?;
+        Ok(attestation)
+    }
+
+    async fn delete_attestation(&self, uri: &str) -> Result<(), StorageError> {
+        let digest = self.manifest_digest(uri)?;
+        let (manifest, _) = self.fetch(digest).await?;
+
+        self.request(Method::DELETE, &format!("manifests/{}", digest))?.send().await?.error_for_status()?;
+
+        if let Some(subject) = manifest.subject {
+            let tag = fallback_tag(&subject.digest);
+            if self.get_manifest::<Index>(&tag).await?.is_some() {
+                self.update_fallback_index(&subject.digest, |manifests| manifests.retain(|m| m.digest != digest)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Walks every tagged image in the repository, following the pages of the tag
+    /// listing, and collects its attestation referrers.
+    async fn list_attestation_entries(&self) -> Result<Vec<(String, Arc<Attestation>)>, StorageError> {
+        #[derive(Deserialize)]
+        struct TagList {
+            #[serde(default)]
+            tags: Option<Vec<String>>,
+        }
+
+        let mut tags = Vec::new();
+        let mut pages = HashSet::new();
+        let mut next = Some(self.endpoint("tags/list")?);
+        while let Some(page) = next.take() {
+            if !pages.insert(page.clone()) {
+                return Err(StorageError::corrupt(format!("Tag listing of {}", self.repository), "its pages link back to an earlier one"));
+            }
+            let response = self.authorize(self.client.get(page.clone())).send().await?.error_for_status()?;
+            // Links are relative to the page they were returned with
+            next = next_link(response.headers())
+                .map(|link| page.join(link))
+                .transpose()
+                .map_err(|e| StorageError::Unavailable(Box::new(e)))?;
+            let listed: TagList = response.json().await?;
+            tags.extend(listed.tags.unwrap_or_default());
+        }
+
+        let mut subjects = HashSet::new();
+        let mut attestations = Vec::new();
+        for tag in tags {
+            if tag.starts_with("sha256-") {
+                continue;
+            }
+
+            let Some((subject, _)) = self.get_manifest::<serde_json::Value>(&tag).await? else {
+                continue;
+            };
+            if !subjects.insert(subject.digest.clone()) {
+                continue;
+            }
+
+            for referrer in self.list_referrers(&subject.digest).await? {
+                let (_, attestation) = self.fetch(&referrer.digest).await?;
+                attestations.push((format!("{}{}", self.uri_prefix(), referrer.digest), attestation));
+            }
+        }
+
+        Ok(attestations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Bytes;
+    use axum::extract::{Path, Query, State};
+    use axum::http::{header, HeaderMap, StatusCode as HttpStatus};
+    use axum::response::IntoResponse;
+    use axum::routing::{get, post, put};
+    use axum::{Json, Router};
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Just enough of the OCI distribution API for one repository, `shop/frontend`.
+    #[derive(Default)]
+    struct MockRegistry {
+        referrers_api: bool,
+        /// The path the API is served under, e.g. `/mirror`.
+        prefix: String,
+        blobs: HashMap<String, Vec<u8>>,
+        manifests: HashMap<String, (String, Vec<u8>)>,
+        tags: HashMap<String, String>,
+    }
+
+    type Registry = State<Arc<Mutex<MockRegistry>>>;
+
+    async fn start_upload(State(registry): Registry) -> impl IntoResponse {
+        let prefix = registry.lock().unwrap().prefix.clone();
+        (HttpStatus::ACCEPTED, [(header::LOCATION, format!("{}/v2/shop/frontend/blobs/uploads/{}", prefix, uuid::Uuid::new_v4()))])
+    }
+
+    async fn finish_upload(State(registry): Registry, Query(query): Query<HashMap<String, String>>, body: Bytes) -> HttpStatus {
+        let digest = &query["digest"];
+        if &sha256_digest(&body) != digest {
+            return HttpStatus::BAD_REQUEST;
+        }
+        registry.lock().unwrap().blobs.insert(digest.clone(), body.to_vec());
+        HttpStatus::CREATED
+    }
+
+    async fn get_blob(State(registry): Registry, Path(digest): Path<String>) -> Result<Vec<u8>, HttpStatus> {
+        registry.lock().unwrap().blobs.get(&digest).cloned().ok_or(HttpStatus::NOT_FOUND)
+    }
+
+    async fn get_manifest(State(registry): Registry, Path(reference): Path<String>) -> Result<impl IntoResponse, HttpStatus> {
+        let registry = registry.lock().unwrap();
+        let digest = registry.tags.get(&reference).cloned().unwrap_or(reference);
+        let (media_type, bytes) = registry.manifests.get(&digest).cloned().ok_or(HttpStatus::NOT_FOUND)?;
+        Ok(([(header::CONTENT_TYPE, media_type)], bytes))
+    }
+
+    async fn put_manifest(State(registry): Registry, Path(reference): Path<String>, headers: HeaderMap, body: Bytes) -> impl IntoResponse {
+        let mut registry = registry.lock().unwrap();
+        let digest = sha256_digest(&body);
+        let media_type = headers[header::CONTENT_TYPE].to_str().unwrap().to_string();
+        registry.manifests.insert(digest.clone(), (media_type, body.to_vec()));
+        if !reference.starts_with("sha256:") {
+            registry.tags.insert(reference, digest.clone());
+        }
+
+        let manifest: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let mut headers = HeaderMap::new();
+        if let (true, Some(subject)) = (registry.referrers_api, manifest["subject"]["digest"].as_str()) {
+            headers.insert("OCI-Subject", subject.parse().unwrap());
+        }
+        (HttpStatus::CREATED, headers)
+    }
+
+    async fn delete_manifest(State(registry): Registry, Path(reference): Path<String>) -> HttpStatus {
+        match registry.lock().unwrap().manifests.remove(&reference) {
+            Some(_) => HttpStatus::ACCEPTED,
+            None => HttpStatus::NOT_FOUND,
+        }
+    }
+
+    /// Lists one tag per page, linking to the next like registries with a page size do.
+    async fn list_tags(State(registry): Registry, Query(query): Query<HashMap<String, String>>) -> impl IntoResponse {
+        let registry = registry.lock().unwrap();
+        let mut tags: Vec<&String> = registry.tags.keys().filter(|tag| query.get("last").is_none_or(|last| *tag > last)).collect();
+        tags.sort();
+
+        let mut headers = HeaderMap::new();
+        if let [tag, _, ..] = tags[..] {
+            let link = format!("<{}/v2/shop/frontend/tags/list?n=1&last={}>; rel=\"next\"", registry.prefix, tag);
+            headers.insert(header::LINK, link.parse().unwrap());
+        }
+        (headers, Json(json!({ "name": "shop/frontend", "tags": tags.first().into_iter().collect::<Vec<_>>() })))
+    }
+
+    async fn referrers(State(registry): Registry, Path(subject): Path<String>) -> Result<Json<Index>, HttpStatus> {
+        let registry = registry.lock().unwrap();
+        if !registry.referrers_api {
+            return Err(HttpStatus::NOT_FOUND);
+        }
+
+        let manifests = registry
+            .manifests
+            .iter()
+            .filter_map(|(digest, (media_type, bytes))| {
+                let manifest: Manifest = serde_json::from_slice(bytes).ok()?;
+                (manifest.subject?.digest == subject).then(|| Descriptor {
+                    media_type: media_type.clone(),
+                    digest: digest.clone(),
+                    size: bytes.len() as u64,
+                    artifact_type: manifest.artifact_type,
+                    annotations: manifest.annotations,
+                })
+            })
+            .collect();
+        Ok(Json(Index { manifests, ..Index::default() }))
+    }
+
+    async fn spawn_registry(referrers_api: bool, prefix: &str) -> (Url, Arc<Mutex<MockRegistry>>, String) {
+        let registry = Arc::new(Mutex::new(MockRegistry {
+            referrers_api,
+            prefix: prefix.to_string(),
+            ..MockRegistry::default()
+        }));
+
+        let image = |config: &[u8]| serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "mediaType": MANIFEST_MEDIA_TYPE,
+            "config": { "mediaType": "application/vnd.oci.image.config.v1+json", "digest": sha256_digest(config), "size": config.len() },
+            "layers": []
+        })).unwrap();
+        // The image the attestations describe, and an older one listed on an earlier page
+        let (attested, older) = (image(b"config"), image(b"older"));
+        let image_digest = sha256_digest(&attested);
+        {
+            let mut state = registry.lock().unwrap();
+            state.tags.insert("1.2.3".to_string(), image_digest.clone());
+            state.tags.insert("1.0.0".to_string(), sha256_digest(&older));
+            state.manifests.insert(image_digest.clone(), (MANIFEST_MEDIA_TYPE.to_string(), attested));
+            state.manifests.insert(sha256_digest(&older), (MANIFEST_MEDIA_TYPE.to_string(), older));
+        }
+
+        let routes = Router::new()
+            .route("/v2/shop/frontend/blobs/uploads/", post(start_upload))
+            .route("/v2/shop/frontend/blobs/uploads/{id}", put(finish_upload))
+            .route("/v2/shop/frontend/blobs/{digest}", get(get_blob))
+            .route("/v2/shop/frontend/manifests/{reference}", get(get_manifest).put(put_manifest).delete(delete_manifest))
+            .route("/v2/shop/frontend/tags/list", get(list_tags))
+            .route("/v2/shop/frontend/referrers/{digest}", get(referrers))
+            .with_state(registry.clone());
+        let app = match prefix {
+            "" => routes,
+            prefix => Router::new().nest(prefix, routes),
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}{}", listener.local_addr().unwrap(), prefix)).unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, registry, image_digest)
+    }
+
+    #[tokio::test]
+    async fn test_oci_attestation_storage_with_and_without_referrers_api() {
+        for (referrers_api, prefix) in [(true, ""), (false, "/mirror")] {
+            let (url, registry, image_digest) = spawn_registry(referrers_api, prefix).await;
+            let storage = OciAttestationStorage::new(url, "shop/frontend");
+
+            let attestation = Arc::new(Attestation {
+                id: "frontend-att".to_string(),
+                issuer: "build-server".to_string(),
+                timestamp: Utc::now(),
+                content: json!({
+                    "subject": [{ "name": "frontend", "digest": { "sha256": image_digest.trim_start_matches("sha256:") } }],
+                    "predicateType": "https://slsa.dev/provenance/v1"
+                }),
+                envelope: None,
+                verification_material: None,
+            });
+
+            let uri = storage.store_attestation(attestation).await.unwrap();
+            assert!(uri.starts_with("oci://127.0.0.1:"));
+            assert!(uri.contains("/shop/frontend@sha256:"));
+            assert_eq!(storage.get_attestation(&uri).await.unwrap().id, "frontend-att");
+
+            // Without the referrers API the tag schema index is maintained instead
+            let fallback_tagged = registry.lock().unwrap().tags.contains_key(&fallback_tag(&image_digest));
+            assert_eq!(fallback_tagged, !referrers_api);
+
+            let listed = storage.list_attestations().await.unwrap();
+            assert_eq!(listed.len(), 1);
+            assert_eq!(listed[0].issuer, "build-server");
+            let entries = storage.list_attestation_entries().await.unwrap();
+            assert_eq!(entries[0].0, uri);
+
+            // A registry serving other bytes for the manifest digest is caught
+            let digest = uri.rsplit_once('@').unwrap().1.to_string();
+            let original = registry.lock().unwrap().manifests[&digest].clone();
+            let mut altered = original.clone();
+            altered.1.push(b' ');
+            registry.lock().unwrap().manifests.insert(digest.clone(), altered);
+            assert!(matches!(storage.get_attestation(&uri).await, Err(StorageError::Corrupt { .. })));
+            registry.lock().unwrap().manifests.insert(digest, original);
+
+            storage.delete_attestation(&uri).await.unwrap();
+            assert!(storage.list_attestations().await.unwrap().is_empty());
+            assert!(storage.get_attestation(&uri).await.is_err());
+
+            let unattached = Arc::new(Attestation {
+                id: "no-digest".to_string(),
+                content: json!({ "subject": [{ "name": "frontend" }] }),
+                ..Attestation::default()
+            });
+            assert!(storage.store_attestation(unattached).await.is_err());
+        }
+    }
+}
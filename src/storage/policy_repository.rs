@@ -1,17 +1,51 @@
 use async_trait::async_trait;
 use std::collections::HashMap;
-use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use semver::Version;
-use crate::models::policy::Policy;
+use crate::models::policy::{Policy, PolicyError, PolicyRef};
+use crate::storage::error::StorageError;
 
 #[async_trait]
 pub trait PolicyRepository: Send + Sync {
-    async fn add_policy(&self, policy: Policy) -> Result<(), Box<dyn Error + Send + Sync>>;
-    async fn get_policy(&self, purl: &str, version: Option<&str>) -> Result<Arc<Policy>, Box<dyn Error + Send + Sync>>;
-    async fn list_policies(&self, purl: &str) -> Result<Vec<Arc<Policy>>, Box<dyn Error + Send + Sync>>;
-    async fn delete_policy(&self, purl: &str, version: &str) -> Result<(), Box<dyn Error + Send + Sync>>;
+    async fn add_policy(&self, policy: Policy) -> Result<(), PolicyError>;
+    async fn get_policy(&self, purl: &str, version: Option<&str>) -> Result<Arc<Policy>, PolicyError>;
+    async fn list_policies(&self, purl: &str) -> Result<Vec<Arc<Policy>>, PolicyError>;
+    async fn delete_policy(&self, purl: &str, version: &str) -> Result<(), PolicyError>;
+    /// The PURLs that have at least one stored version, in order.
+    async fn list_purls(&self) -> Result<Vec<String>, PolicyError>;
+
+    /// Resolves a policy reference to the newest stored version satisfying its requirement.
+    async fn resolve_policy(&self, policy_ref: &PolicyRef) -> Result<Arc<Policy>, PolicyError> {
+        if policy_ref.version_req.is_none() {
+            return self.get_policy(&policy_ref.purl, None).await;
+        }
+
+        self.list_policies(&policy_ref.purl)
+            .await?
+            .into_iter()
+            .filter(|p| policy_ref.matches(p))
+            .filter_map(|p| Version::parse(&p.version).ok().map(|v| (v, p)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, p)| p)
+            .ok_or_else(|| PolicyError::NoMatchingVersion(policy_ref.clone()))
+    }
+}
+
+fn not_found(purl: &str) -> PolicyError {
+    PolicyError::NotFound { purl: purl.to_string() }
+}
+
+fn version_not_found(purl: &str, version: &str) -> PolicyError {
+    PolicyError::VersionNotFound {
+        purl: purl.to_string(),
+        version: version.to_string(),
+    }
+}
+
+fn parse_version(version: &str) -> Result<Version, PolicyError> {
+    Version::parse(version).map_err(|e| PolicyError::Invalid(format!("Invalid version {}: {}", version, e)))
 }
 
 #[derive(Debug, Clone)]
@@ -32,10 +66,16 @@ impl InMemoryPolicyRepository {
     }
 }
 
+impl Default for InMemoryPolicyRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait]
 impl PolicyRepository for InMemoryPolicyRepository {
-    async fn add_policy(&self, policy: Policy) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let version = Version::parse(&policy.version)?;
+    async fn add_policy(&self, policy: Policy) -> Result<(), PolicyError> {
+        let version = parse_version(&policy.version)?;
         let versioned_policy = VersionedPolicy {
             policy: Arc::new(policy.clone()),
             version,
@@ -50,30 +90,30 @@ impl PolicyRepository for InMemoryPolicyRepository {
         Ok(())
     }
 
-    async fn get_policy(&self, purl: &str, version: Option<&str>) -> Result<Arc<Policy>, Box<dyn Error + Send + Sync>> {
+    async fn get_policy(&self, purl: &str, version: Option<&str>) -> Result<Arc<Policy>, PolicyError> {
         let policies = self.policies.read().await;
-        let policy_versions = policies.get(purl).ok_or("Policy not found")?;
+        let policy_versions = policies.get(purl).ok_or_else(|| not_found(purl))?;
 
         match version {
             Some(v) => {
-                let version = Version::parse(v)?;
+                let version = parse_version(v)?;
                 policy_versions
                     .iter()
                     .find(|p| p.version == version)
                     .map(|p| p.policy.clone())
-                    .ok_or_else(|| "Specific version not found".into())
+                    .ok_or_else(|| version_not_found(purl, v))
             }
             None => {
                 policy_versions
                     .iter()
                     .max_by(|a, b| a.version.cmp(&b.version))
                     .map(|p| p.policy.clone())
-                    .ok_or_else(|| "No versions available".into())
+                    .ok_or_else(|| not_found(purl))
             }
         }
     }
 
-    async fn list_policies(&self, purl: &str) -> Result<Vec<Arc<Policy>>, Box<dyn Error + Send + Sync>> {
+    async fn list_policies(&self, purl: &str) -> Result<Vec<Arc<Policy>>, PolicyError> {
         let policies = self.policies.read().await;
         Ok(policies
             .get(purl)
@@ -81,27 +121,115 @@ impl PolicyRepository for InMemoryPolicyRepository {
             .unwrap_or_default())
     }
 
-    async fn delete_policy(&self, purl: &str, version: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    async fn delete_policy(&self, purl: &str, version: &str) -> Result<(), PolicyError> {
         let mut policies = self.policies.write().await;
-        let version = Version::parse(version)?;
+        let parsed = parse_version(version)?;
 
         if let Some(versions) = policies.get_mut(purl) {
             let initial_len = versions.len();
-            versions.retain(|p| p.version != version);
+            versions.retain(|p| p.version != parsed);
             
             if versions.len() == initial_len {
-                return Err("Specific version not found".into());
+                return Err(version_not_found(purl, version));
             }
             
             if versions.is_empty() {
                 policies.remove(purl);
             }
         } else {
-            return Err("Policy not found".into());
+            return Err(not_found(purl));
         }
 
         Ok(())
     }
+
+    async fn list_purls(&self) -> Result<Vec<String>, PolicyError> {
+        let policies = self.policies.read().await;
+        let mut purls: Vec<String> = policies.keys().cloned().collect();
+        purls.sort();
+        Ok(purls)
+    }
+}
+
+/// Policies persisted as a single JSON array, for tools such as git hooks that run
+/// without a long-lived server. Lookups are served by an in-memory repository loaded
+/// from the file; writes go through an atomic rename before they are applied to it.
+pub struct FilePolicyRepository {
+    path: PathBuf,
+    policies: RwLock<Vec<Policy>>,
+    inner: InMemoryPolicyRepository,
+}
+
+impl FilePolicyRepository {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, PolicyError> {
+        let path = path.as_ref().to_path_buf();
+        let policies: Vec<Policy> = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| StorageError::corrupt(path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(StorageError::from(e).into()),
+        };
+
+        let inner = InMemoryPolicyRepository::new();
+        for policy in &policies {
+            inner.add_policy(policy.clone()).await?;
+        }
+
+        Ok(Self {
+            path,
+            policies: RwLock::new(policies),
+            inner,
+        })
+    }
+
+    async fn persist(&self, policies: &[Policy]) -> Result<(), PolicyError> {
+        let json = serde_json::to_vec_pretty(policies).map_err(StorageError::from)?;
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, json).await.map_err(StorageError::from)?;
+        tokio::fs::rename(&tmp_path, &self.path).await.map_err(StorageError::from)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PolicyRepository for FilePolicyRepository {
+    async fn add_policy(&self, policy: Policy) -> Result<(), PolicyError> {
+        parse_version(&policy.version)?;
+
+        let mut policies = self.policies.write().await;
+        let mut next = policies.clone();
+        next.push(policy.clone());
+        self.persist(&next).await?;
+        *policies = next;
+
+        self.inner.add_policy(policy).await
+    }
+
+    async fn get_policy(&self, purl: &str, version: Option<&str>) -> Result<Arc<Policy>, PolicyError> {
+        self.inner.get_policy(purl, version).await
+    }
+
+    async fn list_policies(&self, purl: &str) -> Result<Vec<Arc<Policy>>, PolicyError> {
+        self.inner.list_policies(purl).await
+    }
+
+    async fn delete_policy(&self, purl: &str, version: &str) -> Result<(), PolicyError> {
+        let parsed = parse_version(version)?;
+
+        let mut policies = self.policies.write().await;
+        let mut next = policies.clone();
+        next.retain(|p| p.purl != purl || Version::parse(&p.version).ok().as_ref() != Some(&parsed));
+        if next.len() == policies.len() {
+            return Err(version_not_found(purl, version));
+        }
+        self.persist(&next).await?;
+        *policies = next;
+
+        self.inner.delete_policy(purl, version).await
+    }
+
+    async fn list_purls(&self) -> Result<Vec<String>, PolicyError> {
+        self.inner.list_purls().await
+    }
 }
 
 #[cfg(test)]
@@ -123,6 +251,7 @@ mod tests {
                 max_critical_vulnerabilities: 0,
                 max_high_medium_vulnerabilities: 5,
             },
+            selection: None,
         };
 
         let policy2 = Policy {
@@ -134,6 +263,7 @@ mod tests {
                 max_critical_vulnerabilities: 0,
                 max_high_medium_vulnerabilities: 3,
             },
+            selection: None,
         };
 
         // Test adding policies
@@ -152,12 +282,48 @@ mod tests {
         let all_policies = repo.list_policies("pkg:policy/test").await.unwrap();
         assert_eq!(all_policies.len(), 2);
 
+        // Test resolving policy references
+        let pinned = PolicyRef::matching("pkg:policy/test", "~1.0").unwrap();
+        assert_eq!(repo.resolve_policy(&pinned).await.unwrap().version, "1.0.0");
+        let latest = PolicyRef::latest("pkg:policy/test");
+        assert_eq!(repo.resolve_policy(&latest).await.unwrap().version, "1.1.0");
+        let unsatisfied = PolicyRef::matching("pkg:policy/test", ">=2.0.0").unwrap();
+        assert!(matches!(repo.resolve_policy(&unsatisfied).await, Err(PolicyError::NoMatchingVersion(_))));
+
         // Test deleting a policy
         repo.delete_policy("pkg:policy/test", "1.0.0").await.unwrap();
         assert!(repo.get_policy("pkg:policy/test", Some("1.0.0")).await.is_err());
 
         // Test error handling
-        assert!(repo.get_policy("non_existent", None).await.is_err());
-        assert!(repo.delete_policy("pkg:policy/test", "2.0.0").await.is_err());
+        assert!(matches!(repo.get_policy("non_existent", None).await, Err(PolicyError::NotFound { .. })));
+        assert!(matches!(repo.delete_policy("pkg:policy/test", "2.0.0").await, Err(PolicyError::VersionNotFound { .. })));
+        assert!(matches!(repo.get_policy("pkg:policy/test", Some("latest")).await, Err(PolicyError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_file_policy_repository_persists() {
+        let path = std::env::temp_dir().join(format!("sisyphus-policies-{}.json", uuid::Uuid::new_v4()));
+
+        let repo = FilePolicyRepository::open(&path).await.unwrap();
+        for version in ["1.0.0", "1.1.0"] {
+            repo.add_policy(Policy::new(
+                "pkg:policy/test".to_string(),
+                version.to_string(),
+                PolicyRules {
+                    allowed_issuers: vec!["issuer1".to_string()].into_iter().collect(),
+                    max_age_days: 7,
+                    max_critical_vulnerabilities: 0,
+                    max_high_medium_vulnerabilities: 5,
+                },
+            ).unwrap()).await.unwrap();
+        }
+        repo.delete_policy("pkg:policy/test", "1.1.0").await.unwrap();
+        drop(repo);
+
+        let reopened = FilePolicyRepository::open(&path).await.unwrap();
+        assert_eq!(reopened.get_policy("pkg:policy/test", None).await.unwrap().version, "1.0.0");
+        assert_eq!(reopened.list_policies("pkg:policy/test").await.unwrap().len(), 1);
+
+        tokio::fs::remove_file(&path).await.unwrap();
     }
 }
\ No newline at end of file
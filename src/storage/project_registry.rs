@@ -0,0 +1,441 @@
+use async_trait::async_trait;
+use semver::Version;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+use crate::models::events::{CDEvent, CDEventType, EventSubject, SubjectType};
+use crate::models::project::{Component, SDLCProject};
+use crate::storage::error::StorageError;
+
+#[async_trait]
+pub trait ProjectRegistry: Send + Sync {
+    async fn create_project(&self, project: SDLCProject) -> Result<(), StorageError>;
+    async fn get_project(&self, name: &str) -> Result<Arc<SDLCProject>, StorageError>;
+    async fn list_projects(&self) -> Result<Vec<Arc<SDLCProject>>, StorageError>;
+    async fn update_project(&self, project: SDLCProject) -> Result<(), StorageError>;
+    async fn delete_project(&self, name: &str) -> Result<(), StorageError>;
+
+    async fn add_component(&self, project_name: &str, component: Component) -> Result<(), StorageError>;
+    async fn get_component(&self, project_name: &str, component_name: &str) -> Result<Component, StorageError>;
+    async fn update_component(&self, project_name: &str, component: Component) -> Result<(), StorageError>;
+    async fn remove_component(&self, project_name: &str, component_name: &str) -> Result<(), StorageError>;
+    async fn bump_component_version(&self, project_name: &str, component_name: &str, version: &str) -> Result<(), StorageError>;
+}
+
+/// Project state shared by the registry backends. Every mutation returns the event
+/// describing it so the backend can emit it once the change is committed.
+#[derive(Debug, Clone, Default)]
+struct ProjectStore {
+    projects: HashMap<String, Arc<SDLCProject>>,
+}
+
+impl ProjectStore {
+    fn get(&self, name: &str) -> Result<&Arc<SDLCProject>, StorageError> {
+        self.projects.get(name).ok_or_else(|| StorageError::not_found("Project", name))
+    }
+
+    fn create_project(&mut self, project: SDLCProject) -> Result<CDEventType, StorageError> {
+        project.validate().map_err(StorageError::Invalid)?;
+        if self.projects.contains_key(&project.name) {
+            return Err(StorageError::already_exists("Project", &project.name));
+        }
+
+        let project_name = project.name.clone();
+        self.projects.insert(project_name.clone(), Arc::new(project));
+        Ok(CDEventType::ProjectCreated { project_name })
+    }
+
+    fn update_project(&mut self, project: SDLCProject) -> Result<CDEventType, StorageError> {
+        project.validate().map_err(StorageError::Invalid)?;
+        self.get(&project.name)?;
+
+        let project_name = project.name.clone();
+        self.projects.insert(project_name.clone(), Arc::new(project));
+        Ok(CDEventType::ProjectUpdated { project_name })
+    }
+
+    fn delete_project(&mut self, name: &str) -> Result<CDEventType, StorageError> {
+        self.projects.remove(name).ok_or_else(|| StorageError::not_found("Project", name))?;
+        Ok(CDEventType::ProjectDeleted { project_name: name.to_string() })
+    }
+
+    fn get_component(&self, project_name: &str, component_name: &str) -> Result<Component, StorageError> {
+        self.get(project_name)?
+            .component(component_name)
+            .cloned()
+            .ok_or_else(|| component_not_found(project_name, component_name))
+    }
+
+    fn add_component(&mut self, project_name: &str, component: Component) -> Result<CDEventType, StorageError> {
+        component.validate().map_err(StorageError::Invalid)?;
+        let mut project = SDLCProject::clone(self.get(project_name)?);
+        if project.component(&component.name).is_some() {
+            return Err(StorageError::already_exists("Component", format!("{}/{}", project_name, component.name)));
+        }
+
+        let event = CDEventType::ComponentAdded {
+            project_name: project_name.to_string(),
+            component_name: component.name.clone(),
+            version: component.version.clone(),
+        };
+        project.components.push(component);
+        self.projects.insert(project_name.to_string(), Arc::new(project));
+        Ok(event)
+    }
+
+    fn update_component(&mut self, project_name: &str, component: Component) -> Result<CDEventType, StorageError> {
+        component.validate().map_err(StorageError::Invalid)?;
+        let mut project = SDLCProject::clone(self.get(project_name)?);
+        let existing = project.components
+            .iter_mut()
+            .find(|c| c.name == component.name)
+            .ok_or_else(|| component_not_found(project_name, &component.name))?;
+
+        let event = CDEventType::ComponentUpdated {
+            project_name: project_name.to_string(),
+            component_name: component.name.clone(),
+            version: component.version.clone(),
+        };
+        *existing = component;
+        self.projects.insert(project_name.to_string(), Arc::new(project));
+        Ok(event)
+    }
+
+    fn remove_component(&mut self, project_name: &str, component_name: &str) -> Result<CDEventType, StorageError> {
+        let mut project = SDLCProject::clone(self.get(project_name)?);
+        let initial_len = project.components.len();
+        project.components.retain(|c| c.name != component_name);
+
+        if project.components.len() == initial_len {
+            return Err(component_not_found(project_name, component_name));
+        }
+
+        self.projects.insert(project_name.to_string(), Arc::new(project));
+        Ok(CDEventType::ComponentRemoved {
+            project_name: project_name.to_string(),
+            component_name: component_name.to_string(),
+        })
+    }
+
+    fn bump_component_version(&mut self, project_name: &str, component_name: &str, version: &str) -> Result<CDEventType, StorageError> {
+        let mut project = SDLCProject::clone(self.get(project_name)?);
+        let component = project.components
+            .iter_mut()
+            .find(|c| c.name == component_name)
+            .ok_or_else(|| component_not_found(project_name, component_name))?;
+
+        let previous = parse_version(&component.version)?;
+        let next = parse_version(version)?;
+        if next <= previous {
+            return Err(StorageError::Invalid(format!("Version {} is not newer than {}", next, previous)));
+        }
+
+        let event = CDEventType::ComponentVersionBumped {
+            project_name: project_name.to_string(),
+            component_name: component_name.to_string(),
+            previous_version: component.version.clone(),
+            version: version.to_string(),
+        };
+        component.version = version.to_string();
+        self.projects.insert(project_name.to_string(), Arc::new(project));
+        Ok(event)
+    }
+}
+
+fn component_not_found(project_name: &str, component_name: &str) -> StorageError {
+    StorageError::not_found("Component", format!("{}/{}", project_name, component_name))
+}
+
+fn parse_version(version: &str) -> Result<Version, StorageError> {
+    Version::parse(version).map_err(|e| StorageError::Invalid(format!("Invalid version {}: {}", version, e)))
+}
+
+fn event_for(event_type: CDEventType) -> CDEvent {
+    let subject = match &event_type {
+        CDEventType::ComponentAdded { project_name, component_name, .. }
+        | CDEventType::ComponentUpdated { project_name, component_name, .. }
+        | CDEventType::ComponentRemoved { project_name, component_name }
+        | CDEventType::ComponentVersionBumped { project_name, component_name, .. } => EventSubject {
+            id: format!("{}/{}", project_name, component_name),
+            subject_type: SubjectType::Component,
+        },
+        CDEventType::ProjectCreated { project_name }
+        | CDEventType::ProjectUpdated { project_name }
+        | CDEventType::ProjectDeleted { project_name } => EventSubject {
+            id: project_name.clone(),
+            subject_type: SubjectType::Project,
+        },
+        _ => unreachable!("project registry only emits project and component events"),
+    };
+    CDEvent::new(event_type, subject)
+}
+
+async fn emit(sender: &Option<mpsc::Sender<CDEvent>>, event_type: CDEventType) {
+    if let Some(sender) = sender {
+        // The mutation is already committed; a closed event channel must not undo it.
+        let _ = sender.send(event_for(event_type)).await;
+    }
+}
+
+pub struct InMemoryProjectRegistry {
+    store: RwLock<ProjectStore>,
+    event_sender: Option<mpsc::Sender<CDEvent>>,
+}
+
+impl InMemoryProjectRegistry {
+    pub fn new() -> Self {
+        Self {
+            store: RwLock::new(ProjectStore::default()),
+            event_sender: None,
+        }
+    }
+
+    pub fn with_event_sender(mut self, event_sender: mpsc::Sender<CDEvent>) -> Self {
+        self.event_sender = Some(event_sender);
+        self
+    }
+
+    async fn mutate<F>(&self, mutation: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut ProjectStore) -> Result<CDEventType, StorageError> + Send,
+    {
+        let event_type = mutation(&mut *self.store.write().await)?;
+        emit(&self.event_sender, event_type).await;
+        Ok(())
+    }
+}
+
+impl Default for InMemoryProjectRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A registry persisted as a single JSON document. Each mutation is applied to a copy
+/// of the state and written through an atomic rename before it becomes visible.
+pub struct FileProjectRegistry {
+    path: PathBuf,
+    store: RwLock<ProjectStore>,
+    event_sender: Option<mpsc::Sender<CDEvent>>,
+}
+
+impl FileProjectRegistry {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let path = path.as_ref().to_path_buf();
+        let mut store = ProjectStore::default();
+
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => {
+                let projects: Vec<SDLCProject> = serde_json::from_slice(&bytes).map_err(|e| StorageError::corrupt(path.display(), e))?;
+                for project in projects {
+                    store.projects.insert(project.name.clone(), Arc::new(project));
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(Self {
+            path,
+            store: RwLock::new(store),
+            event_sender: None,
+        })
+    }
+
+    pub fn with_event_sender(mut self, event_sender: mpsc::Sender<CDEvent>) -> Self {
+        self.event_sender = Some(event_sender);
+        self
+    }
+
+    async fn persist(&self, store: &ProjectStore) -> Result<(), StorageError> {
+        let mut projects: Vec<&SDLCProject> = store.projects.values().map(|p| p.as_ref()).collect();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        let json = serde_json::to_vec_pretty(&projects)?;
+
+        let tmp_path = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, json).await?;
+        tokio::fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+
+    async fn mutate<F>(&self, mutation: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&mut ProjectStore) -> Result<CDEventType, StorageError> + Send,
+    {
+        let mut store = self.store.write().await;
+        let mut next = store.clone();
+        let event_type = mutation(&mut next)?;
+        self.persist(&next).await?;
+        *store = next;
+        drop(store);
+
+        emit(&self.event_sender, event_type).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ProjectRegistry for InMemoryProjectRegistry {
+    async fn create_project(&self, project: SDLCProject) -> Result<(), StorageError> {
+        self.mutate(|store| store.create_project(project)).await
+    }
+
+    async fn get_project(&self, name: &str) -> Result<Arc<SDLCProject>, StorageError> {
+        self.store.read().await.get(name).cloned()
+    }
+
+    async fn list_projects(&self) -> Result<Vec<Arc<SDLCProject>>, StorageError> {
+        Ok(self.store.read().await.projects.values().cloned().collect())
+    }
+
+    async fn update_project(&self, project: SDLCProject) -> Result<(), StorageError> {
+        self.mutate(|store| store.update_project(project)).await
+    }
+
+    async fn delete_project(&self, name: &str) -> Result<(), StorageError> {
+        self.mutate(|store| store.delete_project(name)).await
+    }
+
+    async fn add_component(&self, project_name: &str, component: Component) -> Result<(), StorageError> {
+        self.mutate(|store| store.add_component(project_name, component)).await
+    }
+
+    async fn get_component(&self, project_name: &str, component_name: &str) -> Result<Component, StorageError> {
+        self.store.read().await.get_component(project_name, component_name)
+    }
+
+    async fn update_component(&self, project_name: &str, component: Component) -> Result<(), StorageError> {
+        self.mutate(|store| store.update_component(project_name, component)).await
+    }
+
+    async fn remove_component(&self, project_name: &str, component_name: &str) -> Result<(), StorageError> {
+        self.mutate(|store| store.remove_component(project_name, component_name)).await
+    }
+
+    async fn bump_component_version(&self, project_name: &str, component_name: &str, version: &str) -> Result<(), StorageError> {
+        self.mutate(|store| store.bump_component_version(project_name, component_name, version)).await
+    }
+}
+
+#[async_trait]
+impl ProjectRegistry for FileProjectRegistry {
+    async fn create_project(&self, project: SDLCProject) -> Result<(), StorageError> {
+        self.mutate(|store| store.create_project(project)).await
+    }
+
+    async fn get_project(&self, name: &str) -> Result<Arc<SDLCProject>, StorageError> {
+        self.store.read().await.get(name).cloned()
+    }
+
+    async fn list_projects(&self) -> Result<Vec<Arc<SDLCProject>>, StorageError> {
+        Ok(self.store.read().await.projects.values().cloned().collect())
+    }
+
+    async fn update_project(&self, project: SDLCProject) -> Result<(), StorageError> {
+        self.mutate(|store| store.update_project(project)).await
+    }
+
+    async fn delete_project(&self, name: &str) -> Result<(), StorageError> {
+        self.mutate(|store| store.delete_project(name)).await
+    }
+
+    async fn add_component(&self, project_name: &str, component: Component) -> Result<(), StorageError> {
+        self.mutate(|store| store.add_component(project_name, component)).await
+    }
+
+    async fn get_component(&self, project_name: &str, component_name: &str) -> Result<Component, StorageError> {
+        self.store.read().await.get_component(project_name, component_name)
+    }
+
+    async fn update_component(&self, project_name: &str, component: Component) -> Result<(), StorageError> {
+        self.mutate(|store| store.update_component(project_name, component)).await
+    }
+
+    async fn remove_component(&self, project_name: &str, component_name: &str) -> Result<(), StorageError> {
+        self.mutate(|store| store.remove_component(project_name, component_name)).await
+    }
+
+    async fn bump_component_version(&self, project_name: &str, component_name: &str, version: &str) -> Result<(), StorageError> {
+        self.mutate(|store| store.bump_component_version(project_name, component_name, version)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::policy::PolicyRef;
+
+    fn component(name: &str, version: &str) -> Component {
+        Component {
+            name: name.to_string(),
+            version: version.to_string(),
+            policy: PolicyRef::matching("pkg:policy/test", "^1.0").unwrap(),
+            digests: Default::default(),
+            selection: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_project_registry() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let registry = InMemoryProjectRegistry::new().with_event_sender(tx);
+
+        let project = SDLCProject {
+            name: "ACMEAppX".to_string(),
+            components: vec![component("frontend", "1.2.3")],
+        };
+
+        registry.create_project(project.clone()).await.unwrap();
+        assert!(matches!(registry.create_project(project).await, Err(StorageError::AlreadyExists { kind: "Project", .. })));
+
+        registry.add_component("ACMEAppX", component("backend", "2.3.4")).await.unwrap();
+        assert!(registry.add_component("ACMEAppX", component("backend", "2.3.4")).await.is_err());
+        assert_eq!(registry.get_project("ACMEAppX").await.unwrap().components.len(), 2);
+
+        registry.bump_component_version("ACMEAppX", "backend", "2.4.0").await.unwrap();
+        assert!(matches!(registry.bump_component_version("ACMEAppX", "backend", "2.3.9").await, Err(StorageError::Invalid(_))));
+        assert_eq!(registry.get_component("ACMEAppX", "backend").await.unwrap().version, "2.4.0");
+
+        registry.remove_component("ACMEAppX", "frontend").await.unwrap();
+        assert!(registry.get_component("ACMEAppX", "frontend").await.is_err());
+
+        registry.delete_project("ACMEAppX").await.unwrap();
+        let err = registry.get_project("ACMEAppX").await.unwrap_err();
+        assert!(matches!(err, StorageError::NotFound { kind: "Project", .. }));
+        assert!(!err.is_retryable());
+        assert!(registry.list_projects().await.unwrap().is_empty());
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event.event_type);
+        }
+        assert_eq!(events.len(), 5);
+        assert!(matches!(events[0], CDEventType::ProjectCreated { .. }));
+        assert!(matches!(
+            &events[2],
+            CDEventType::ComponentVersionBumped { previous_version, version, .. } if previous_version == "2.3.4" && version == "2.4.0"
+        ));
+        assert!(matches!(events[4], CDEventType::ProjectDeleted { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_file_project_registry_persists() {
+        let path = std::env::temp_dir().join(format!("sisyphus-projects-{}.json", uuid::Uuid::new_v4()));
+
+        let registry = FileProjectRegistry::open(&path).await.unwrap();
+        registry.create_project(SDLCProject {
+            name: "ACMEAppX".to_string(),
+            components: vec![component("frontend", "1.2.3")],
+        }).await.unwrap();
+        registry.bump_component_version("ACMEAppX", "frontend", "1.3.0").await.unwrap();
+        drop(registry);
+
+        let reopened = FileProjectRegistry::open(&path).await.unwrap();
+        let frontend = reopened.get_component("ACMEAppX", "frontend").await.unwrap();
+        assert_eq!(frontend.version, "1.3.0");
+        assert_eq!(frontend.policy, PolicyRef::matching("pkg:policy/test", "^1.0").unwrap());
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use crate::models::waiver::Waiver;
+use crate::storage::error::StorageError;
+
+#[async_trait]
+pub trait WaiverRepository: Send + Sync {
+    async fn add_waiver(&self, waiver: Waiver) -> Result<(), StorageError>;
+    async fn get_waiver(&self, id: &str) -> Result<Arc<Waiver>, StorageError>;
+    async fn list_waivers(&self) -> Result<Vec<Arc<Waiver>>, StorageError>;
+    async fn revoke_waiver(&self, id: &str) -> Result<(), StorageError>;
+
+    async fn active_waivers(&self, now: DateTime<Utc>) -> Result<Vec<Arc<Waiver>>, StorageError> {
+        let waivers = self.list_waivers().await?;
+        Ok(waivers.into_iter().filter(|w| w.is_active_at(now)).collect())
+    }
+}
+
+pub struct InMemoryWaiverRepository {
+    waivers: RwLock<HashMap<String, Arc<Waiver>>>,
+}
+
+impl InMemoryWaiverRepository {
+    pub fn new() -> Self {
+        Self {
+            waivers: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl Default for InMemoryWaiverRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WaiverRepository for InMemoryWaiverRepository {
+    async fn add_waiver(&self, waiver: Waiver) -> Result<(), StorageError> {
+        waiver.validate().map_err(StorageError::Invalid)?;
+
+        let mut waivers = self.waivers.write().await;
+        if waivers.contains_key(&waiver.id) {
+            return Err(StorageError::already_exists("Waiver", &waiver.id));
+        }
+        waivers.insert(waiver.id.clone(), Arc::new(waiver));
+        Ok(())
+    }
+
+    async fn get_waiver(&self, id: &str) -> Result<Arc<Waiver>, StorageError> {
+        let waivers = self.waivers.read().await;
+        waivers.get(id).cloned().ok_or_else(|| StorageError::not_found("Waiver", id))
+    }
+
+    async fn list_waivers(&self) -> Result<Vec<Arc<Waiver>>, StorageError> {
+        let waivers = self.waivers.read().await;
+        Ok(waivers.values().cloned().collect())
+    }
+
+    async fn revoke_waiver(&self, id: &str) -> Result<(), StorageError> {
+        let mut waivers = self.waivers.write().await;
+        waivers.remove(id).ok_or_else(|| StorageError::not_found("Waiver", id))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::models::policy::PolicyRule;
+    use crate::models::waiver::WaiverScope;
+
+    #[tokio::test]
+    async fn test_in_memory_waiver_repository() {
+        let repo = InMemoryWaiverRepository::new();
+
+        let waiver = Waiver::new(
+            WaiverScope::Name { name: "frontend".to_string() },
+            PolicyRule::MaxCriticalVulnerabilities,
+            "security-lead".to_string(),
+            "Vendor fix scheduled".to_string(),
+            Utc::now() + Duration::days(7),
+        ).unwrap();
+        let id = waiver.id.clone();
+
+        repo.add_waiver(waiver.clone()).await.unwrap();
+        assert!(repo.add_waiver(waiver).await.is_err());
+
+        assert_eq!(repo.get_waiver(&id).await.unwrap().approver, "security-lead");
+        assert_eq!(repo.active_waivers(Utc::now()).await.unwrap().len(), 1);
+        assert!(repo.active_waivers(Utc::now() + Duration::days(8)).await.unwrap().is_empty());
+
+        repo.revoke_waiver(&id).await.unwrap();
+        assert!(repo.get_waiver(&id).await.is_err());
+        assert!(repo.revoke_waiver(&id).await.is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::models::attestation::Attestation;
+use crate::models::dsse::{statement_time, Keyring};
+use crate::models::policy::{Policy, PolicyRule};
+use crate::verification::policy_verifier::{PolicyVerifier, RuleOutcome, VerificationError, VerificationResult};
+
+/// Checks the DSSE envelope of attestations against a keyring before trusting who
+/// signed them.
+///
+/// The `allowed_issuers` outcome is decided by the issuer of the trusted key the
+/// envelope verifies under, and fails for attestations without an envelope, without a
+/// valid signature, or whose signed statement differs from their content. The
+/// `max_age_days` outcome of a verified envelope is measured from the time its signed
+/// statement records, and fails if it records none. All other outcomes are left to the
+/// inner verifier. Wrap it in a `SigstorePolicyVerifier` to
+/// also accept Sigstore bundles, whose outcome then takes precedence.
+pub struct DssePolicyVerifier<V> {
+    keyring: Keyring,
+    inner: V,
+}
+
+impl<V: PolicyVerifier> DssePolicyVerifier<V> {
+    pub fn new(keyring: Keyring, inner: V) -> Self {
+        Self { keyring, inner }
+    }
+}
+
+#[async_trait]
+impl<V: PolicyVerifier> PolicyVerifier for DssePolicyVerifier<V> {
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        let mut result = self.inner.evaluate_attestation(attestation, policy).await?;
+
+        let outcomes = match attestation.envelope.as_ref().map(|envelope| self.keyring.verify(envelope)) {
+            None => vec![RuleOutcome::new(PolicyRule::AllowedIssuers, false, "attestation is not signed".to_string())],
+            Some(Ok(verified)) if verified.statement != attestation.content => vec![RuleOutcome::new(
+                PolicyRule::AllowedIssuers,
+                false,
+                "signed statement differs from the attestation's content".to_string(),
+            )],
+            Some(Ok(verified)) => {
+                // The stored timestamp is not covered by the signature, so age is taken
+                // from the time the statement itself records
+                let age = match statement_time(&verified.statement) {
+                    Some(signed_at) => {
+                        let age = Utc::now() - signed_at;
+                        RuleOutcome::new(
+                            PolicyRule::MaxAgeDays,
+                            age <= policy.rules.max_age(),
+                            format!("age {} days since signing, limit {}", age.num_days(), policy.rules.max_age_days),
+                        )
+                    }
+                    None => RuleOutcome::new(PolicyRule::MaxAgeDays, false, "signed statement records no time".to_string()),
+                };
+                vec![
+                    RuleOutcome::new(
+                        PolicyRule::AllowedIssuers,
+                        policy.rules.is_issuer_allowed(&verified.issuer),
+                        format!("issuer {} (key {})", verified.issuer, verified.key_id),
+                    ),
+                    age,
+                ]
+            }
+            Some(Err(e)) => vec![RuleOutcome::new(PolicyRule::AllowedIssuers, false, format!("DSSE envelope rejected: {}", e))],
+        };
+
+        for outcome in outcomes {
+            if let Some(existing) = result.outcomes.iter_mut().find(|o| o.rule == outcome.rule) {
+                *existing = outcome;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Duration};
+    use ed25519_dalek::SigningKey;
+    use serde_json::json;
+
+    use crate::models::dsse::{key_issuer, Envelope};
+    use crate::models::policy::PolicyRules;
+    use crate::verification::policy_verifier::SourcePolicyVerifier;
+
+    #[tokio::test]
+    async fn test_issuer_taken_from_verifying_key() {
+        let trusted = SigningKey::from_bytes(&[1; 32]);
+        let untrusted = SigningKey::from_bytes(&[2; 32]);
+        let issuer = key_issuer(&trusted.verifying_key());
+
+        let policy = Policy::new(
+            "pkg:github/acme/app".to_string(),
+            "1.0.0".to_string(),
+            PolicyRules::new(vec![issuer.clone()].into_iter().collect(), 7, 0, 0),
+        ).unwrap();
+        let verifier = DssePolicyVerifier::new(Keyring::new().with_key(trusted.verifying_key()), SourcePolicyVerifier);
+
+        let statement_at = |attested_at: DateTime<Utc>| json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "acme/app", "digest": { "gitCommit": "a".repeat(40) } }],
+            "predicate": { "attestedAt": attested_at }
+        });
+        let statement = statement_at(Utc::now());
+        let attestation = |key: &SigningKey| Attestation {
+            id: "commit".to_string(),
+            // Every attestation claims the trusted issuer; only the signature decides
+            issuer: issuer.clone(),
+            timestamp: Utc::now(),
+            content: statement.clone(),
+            envelope: Some(Envelope::sign(&statement, key).unwrap()),
+            verification_material: None,
+        };
+
+        assert!(verifier.verify_attestation(&attestation(&trusted), &policy).await.unwrap());
+
+        let forged = verifier.evaluate_attestation(&attestation(&untrusted), &policy).await.unwrap();
+        assert!(!forged.is_valid());
+        assert!(forged.outcomes[0].reason.contains("trusted key"));
+
+        let unsigned = Attestation { envelope: None, ..attestation(&trusted) };
+        assert!(!verifier.verify_attestation(&unsigned, &policy).await.unwrap());
+
+        let swapped = Attestation { content: json!({ "subject": [] }), ..attestation(&trusted) };
+        let result = verifier.evaluate_attestation(&swapped, &policy).await.unwrap();
+        assert!(result.outcomes[0].reason.contains("differs"));
+
+        // A fresh stored timestamp does not make an old signed statement current
+        let old = statement_at(Utc::now() - Duration::days(30));
+        let restamped = Attestation {
+            content: old.clone(),
+            envelope: Some(Envelope::sign(&old, &trusted).unwrap()),
+            ..attestation(&trusted)
+        };
+        let result = verifier.evaluate_attestation(&restamped, &policy).await.unwrap();
+        assert_eq!(result.failed_rules(), vec![PolicyRule::MaxAgeDays]);
+
+        let undated = json!({ "subject": statement["subject"].clone() });
+        let undated = Attestation {
+            content: undated.clone(),
+            envelope: Some(Envelope::sign(&undated, &trusted).unwrap()),
+            ..attestation(&trusted)
+        };
+        let result = verifier.evaluate_attestation(&undated, &policy).await.unwrap();
+        assert_eq!(result.failed_rules(), vec![PolicyRule::MaxAgeDays]);
+    }
+}
@@ -0,0 +1,249 @@
+use serde::{Deserialize, Serialize};
+use semver::Version;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::models::attestation::Attestation;
+use crate::models::policy::{Policy, PolicyError, PolicyRule};
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
+use crate::storage::policy_repository::PolicyRepository;
+use crate::verification::policy_verifier::PolicyVerifier;
+
+#[derive(Debug, Error)]
+pub enum AnalysisError {
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+impl AnalysisError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AnalysisError::Policy(e) => e.is_retryable(),
+            AnalysisError::Storage(e) => e.is_retryable(),
+        }
+    }
+}
+
+/// A subject whose verdict would change if the candidate policy were activated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubjectImpact {
+    pub subject: String,
+    pub attestation_id: String,
+}
+
+/// An attestation that could not be evaluated under one of the two policies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpactError {
+    pub attestation_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpactReport {
+    pub policy_purl: String,
+    pub active_version: String,
+    pub candidate_version: String,
+    pub attestations_evaluated: usize,
+    /// Subjects that pass today but would fail, keyed by the candidate rule that fails.
+    pub pass_to_fail: BTreeMap<PolicyRule, Vec<SubjectImpact>>,
+    /// Subjects that fail today but would pass, keyed by the active rule that stops failing.
+    pub fail_to_pass: BTreeMap<PolicyRule, Vec<SubjectImpact>>,
+    pub errors: Vec<ImpactError>,
+}
+
+impl ImpactReport {
+    pub fn has_changes(&self) -> bool {
+        !self.pass_to_fail.is_empty() || !self.fail_to_pass.is_empty()
+    }
+}
+
+/// Evaluates a candidate policy version against stored attestations and compares
+/// the outcome with the currently active version of the same policy.
+///
+/// An attestation is governed by a policy when its `purl` field names the policy,
+/// the same relation `CBPManager` uses when picking policies for an attestation.
+pub struct PolicyImpactAnalyzer<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier> {
+    policy_repo: Arc<P>,
+    attestation_storage: Arc<A>,
+    policy_verifier: Arc<V>,
+}
+
+impl<P: PolicyRepository, A: AttestationStorage, V: PolicyVerifier> PolicyImpactAnalyzer<P, A, V> {
+    pub fn new(policy_repo: Arc<P>, attestation_storage: Arc<A>, policy_verifier: Arc<V>) -> Self {
+        Self {
+            policy_repo,
+            attestation_storage,
+            policy_verifier,
+        }
+    }
+
+    pub async fn analyze(&self, candidate: &Policy) -> Result<ImpactReport, AnalysisError> {
+        candidate.validate().map_err(PolicyError::Invalid)?;
+        let active = self.active_policy(candidate).await?;
+
+        let mut report = ImpactReport {
+            policy_purl: candidate.purl.clone(),
+            active_version: active.version.clone(),
+            candidate_version: candidate.version.clone(),
+            attestations_evaluated: 0,
+            pass_to_fail: BTreeMap::new(),
+            fail_to_pass: BTreeMap::new(),
+            errors: Vec::new(),
+        };
+
+        let attestations = self.attestation_storage.list_attestations().await?;
+        for attestation in attestations.iter().filter(|att| att.content["purl"].as_str() == Some(&candidate.purl)) {
+            let subjects = subject_names(attestation);
+            if subjects.is_empty() {
+                continue;
+            }
+            report.attestations_evaluated += 1;
+
+            let (current, proposed) = match (
+                self.policy_verifier.evaluate_attestation(attestation, &active).await,
+                self.policy_verifier.evaluate_attestation(attestation, candidate).await,
+            ) {
+                (Ok(current), Ok(proposed)) => (current, proposed),
+                (Err(e), _) | (_, Err(e)) => {
+                    report.errors.push(ImpactError {
+                        attestation_id: attestation.id.clone(),
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let flipped = match (current.is_valid(), proposed.is_valid()) {
+                (true, false) => Some((&mut report.pass_to_fail, proposed.failed_rules())),
+                (false, true) => Some((&mut report.fail_to_pass, current.failed_rules())),
+                _ => None,
+            };
+
+            if let Some((group, rules)) = flipped {
+                for rule in rules {
+                    let impacts = group.entry(rule).or_default();
+                    impacts.extend(subjects.iter().map(|subject| SubjectImpact {
+                        subject: subject.clone(),
+                        attestation_id: attestation.id.clone(),
+                    }));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// The newest stored version of the candidate's policy, excluding the candidate itself.
+    async fn active_policy(&self, candidate: &Policy) -> Result<Arc<Policy>, PolicyError> {
+        let candidate_version = Version::parse(&candidate.version).map_err(|e| PolicyError::Invalid(e.to_string()))?;
+        let versions = self.policy_repo.list_policies(&candidate.purl).await?;
+
+        versions
+            .into_iter()
+            .filter_map(|p| Version::parse(&p.version).ok().map(|v| (v, p)))
+            .filter(|(v, _)| *v != candidate_version)
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, p)| p)
+            .ok_or_else(|| PolicyError::NotFound { purl: candidate.purl.clone() })
+    }
+}
+
+fn subject_names(attestation: &Attestation) -> Vec<String> {
+    attestation.content["subject"]
+        .as_array()
+        .map(|subjects| {
+            subjects
+                .iter()
+                .filter_map(|s| s["name"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    use crate::models::policy::PolicyRules;
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+    use crate::storage::policy_repository::InMemoryPolicyRepository;
+    use crate::verification::policy_verifier::SimplePolicyVerifier;
+
+    fn attestation(id: &str, subject: &str, critical: u32, high: u32) -> Arc<Attestation> {
+        Arc::new(Attestation {
+            id: id.to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "purl": "pkg:policy/test",
+                "subject": [{ "name": subject }],
+                "vulnerabilities": { "critical": critical, "high": high, "medium": 0 }
+            }),
+            envelope: None,
+            verification_material: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_policy_impact_analysis() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+
+        let active = Policy {
+            purl: "pkg:policy/test".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 7,
+                max_critical_vulnerabilities: 1,
+                max_high_medium_vulnerabilities: 2,
+            },
+            selection: None,
+        };
+        policy_repo.add_policy(active).await.unwrap();
+
+        attestation_storage.store_attestation(attestation("a", "frontend", 1, 0)).await.unwrap();
+        attestation_storage.store_attestation(attestation("b", "backend", 0, 4)).await.unwrap();
+        attestation_storage.store_attestation(attestation("c", "worker", 0, 0)).await.unwrap();
+
+        // Tighten critical vulnerabilities while relaxing high/medium
+        let candidate = Policy {
+            purl: "pkg:policy/test".to_string(),
+            version: "1.1.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 7,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: None,
+        };
+
+        let analyzer = PolicyImpactAnalyzer::new(policy_repo, attestation_storage, Arc::new(SimplePolicyVerifier));
+        let report = analyzer.analyze(&candidate).await.unwrap();
+
+        assert_eq!(report.active_version, "1.0.0");
+        assert_eq!(report.attestations_evaluated, 3);
+        assert!(report.has_changes());
+
+        let newly_failing = &report.pass_to_fail[&PolicyRule::MaxCriticalVulnerabilities];
+        assert_eq!(newly_failing, &vec![SubjectImpact { subject: "frontend".to_string(), attestation_id: "a".to_string() }]);
+        assert_eq!(report.pass_to_fail.len(), 1);
+
+        let newly_passing = &report.fail_to_pass[&PolicyRule::MaxHighMediumVulnerabilities];
+        assert_eq!(newly_passing, &vec![SubjectImpact { subject: "backend".to_string(), attestation_id: "b".to_string() }]);
+        assert_eq!(report.fail_to_pass.len(), 1);
+
+        // Without an active version there is nothing to compare, and retrying will not help
+        let unknown = Policy { purl: "pkg:policy/unknown".to_string(), ..candidate };
+        let error = analyzer.analyze(&unknown).await.unwrap_err();
+        assert!(matches!(error, AnalysisError::Policy(PolicyError::NotFound { .. })), "{}", error);
+        assert!(!error.is_retryable());
+    }
+}
@@ -1 +1,7 @@
-pub mod policy_verifier;
\ No newline at end of file
+pub mod policy_verifier;
+pub mod impact_analysis;
+pub mod waiver_verifier;
+pub mod sigstore_verifier;
+pub mod scai_verifier;
+pub mod offline;
+pub mod dsse_verifier;
\ No newline at end of file
@@ -0,0 +1,363 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::controlplane::summary::SCAI_PREDICATE_TYPE;
+use crate::models::attestation::Attestation;
+use crate::models::canonical::canonical_sha256;
+use crate::models::policy::{Policy, PolicyError};
+use crate::models::summary_scai::ResourceDescriptor;
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
+use crate::storage::policy_repository::PolicyRepository;
+use crate::verification::policy_verifier::PolicyVerifier;
+use crate::verification::scai_verifier::{
+    parse_summary, split_policy, EvidenceCheck, EvidenceError, EvidenceSource, ScaiVerifier, DEFAULT_MAX_DEPTH,
+};
+use crate::verification::sigstore_verifier::{SigstoreError, SigstorePolicyVerifier, TrustRoot};
+
+pub const VERIFICATION_BUNDLE_MEDIA_TYPE: &str = "application/vnd.sisyphus.verification-bundle.v1+json";
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Unsupported verification bundle media type {0}, expected {VERIFICATION_BUNDLE_MEDIA_TYPE}")]
+    UnsupportedMediaType(String),
+    #[error("Evidence for {attribute} has no URI to fetch it by")]
+    MissingEvidenceUri { attribute: String },
+    #[error("Summary signature does not verify: {0}")]
+    SummarySignature(#[from] SigstoreError),
+    #[error("Summary is signed but no trust root is configured")]
+    MissingTrustRoot,
+    #[error("Summary {0} differs from the statement it was signed with")]
+    SummaryTampered(String),
+    #[error("Summary {0} is not signed")]
+    UnsignedSummary(String),
+    #[error("The bundle's trust root is not the configured one")]
+    UntrustedTrustRoot,
+    #[error("Policy {0} in the bundle is not one of the configured policies")]
+    UntrustedPolicy(String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error(transparent)]
+    Evidence(#[from] EvidenceError),
+}
+
+/// An attestation together with the URI the summary refers to it by.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledAttestation {
+    pub uri: String,
+    pub attestation: Attestation,
+}
+
+/// Everything needed to re-check a summary attestation without access to the stores:
+/// the summary, every evidence attestation it references, directly or through nested
+/// summaries, the policies named in their conditions and the Sigstore trust root for
+/// signed attestations. The policies and trust root only say what the exporter used;
+/// an `OfflineVerifier` checks against its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationBundle {
+    pub media_type: String,
+    pub created_at: DateTime<Utc>,
+    pub summary: BundledAttestation,
+    pub evidence: Vec<BundledAttestation>,
+    pub policies: Vec<Policy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_root: Option<TrustRoot>,
+}
+
+impl VerificationBundle {
+    /// Collects the summary stored at `summary_uri` and everything it references.
+    pub async fn export<A, P>(attestations: &A, policies: &P, summary_uri: &str, trust_root: Option<TrustRoot>) -> Result<Self, BundleError>
+    where
+        A: AttestationStorage + ?Sized,
+        P: PolicyRepository + ?Sized,
+    {
+        let summary = attestations.get_attestation(summary_uri).await?;
+
+        let mut evidence: Vec<BundledAttestation> = Vec::new();
+        let mut bundled_policies: Vec<Policy> = Vec::new();
+        let mut seen = HashSet::from([summary_uri.to_string()]);
+        // Summaries still to collect, with how deep they are nested
+        let mut pending = vec![(summary.clone(), 1)];
+        while let Some((current, depth)) = pending.pop() {
+            let scai = parse_summary(&current)?;
+            for item in &scai.predicate.attributes {
+                let uri = item.evidence.uri.as_deref().ok_or_else(|| BundleError::MissingEvidenceUri {
+                    attribute: item.attribute.to_string(),
+                })?;
+                if seen.insert(uri.to_string()) {
+                    let attestation = attestations.get_attestation(uri).await?;
+                    if attestation.content["predicateType"].as_str() == Some(SCAI_PREDICATE_TYPE) && depth < DEFAULT_MAX_DEPTH {
+                        pending.push((attestation.clone(), depth + 1));
+                    }
+                    evidence.push(BundledAttestation {
+                        uri: uri.to_string(),
+                        attestation: (*attestation).clone(),
+                    });
+                }
+
+                let policy_ref = item.conditions.as_ref().and_then(|c| c.policy.as_deref()).and_then(split_policy);
+                if let Some((purl, version)) = policy_ref {
+                    if !bundled_policies.iter().any(|p| p.purl == purl && p.version == version) {
+                        bundled_policies.push((*policies.get_policy(purl, Some(version)).await?).clone());
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            media_type: VERIFICATION_BUNDLE_MEDIA_TYPE.to_string(),
+            created_at: Utc::now(),
+            summary: BundledAttestation {
+                uri: summary_uri.to_string(),
+                attestation: (*summary).clone(),
+            },
+            evidence,
+            policies: bundled_policies,
+            trust_root,
+        })
+    }
+}
+
+/// Attestations from a bundle, checked against policies configured by the verifier.
+struct PinnedBundle<'a> {
+    bundle: &'a VerificationBundle,
+    policies: &'a [Policy],
+}
+
+#[async_trait]
+impl EvidenceSource for PinnedBundle<'_> {
+    async fn attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+        std::iter::once(&self.bundle.summary)
+            .chain(&self.bundle.evidence)
+            .find(|bundled| bundled.uri == uri)
+            .map(|bundled| Arc::new(bundled.attestation.clone()))
+            .ok_or_else(|| StorageError::not_found("Attestation", uri))
+    }
+
+    async fn policy(&self, purl: &str, version: &str) -> Result<Arc<Policy>, PolicyError> {
+        self.policies
+            .iter()
+            .find(|p| p.purl == purl && p.version == version)
+            .map(|p| Arc::new(p.clone()))
+            .ok_or_else(|| PolicyError::VersionNotFound {
+                purl: purl.to_string(),
+                version: version.to_string(),
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineReport {
+    pub passed: bool,
+    /// The summary's issuer, or its verified Sigstore identity if it is signed.
+    pub summary_issuer: String,
+    pub summary_signed: bool,
+    pub subjects: Vec<ResourceDescriptor>,
+    /// Why the summary itself does not hold up; empty if it does.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+    pub evidence: Vec<EvidenceCheck>,
+}
+
+impl OfflineReport {
+    /// Whether the summary passed and is about the artifact with the given digest. Only a
+    /// passing summary has evidence known to be about each of its subjects.
+    pub fn covers(&self, algorithm: &str, digest: &str) -> bool {
+        self.passed && self.subjects.iter().any(|s| s.digest.get(algorithm).is_some_and(|d| d == digest))
+    }
+}
+
+/// Re-checks a `VerificationBundle` without access to the stores, against the trust
+/// root and policies configured here rather than those the bundle carries.
+///
+/// A bundle whose trust root or policies differ from the configured ones is rejected.
+/// The summary must be Sigstore-signed unless unsigned summaries are allowed; it is then
+/// checked against the summary policy and its evidence followed as `ScaiVerifier` does,
+/// with the configured trust root applied to signed attestations.
+pub struct OfflineVerifier<V> {
+    verifier: V,
+    summary_policy: Policy,
+    policies: Vec<Policy>,
+    trust_root: Option<TrustRoot>,
+    allow_unsigned: bool,
+}
+
+impl<V: PolicyVerifier> OfflineVerifier<V> {
+    /// `policies` are those the evidence may name in its conditions.
+    pub fn new(verifier: V, summary_policy: Policy, policies: Vec<Policy>) -> Self {
+        Self {
+            verifier,
+            summary_policy,
+            policies,
+            trust_root: None,
+            allow_unsigned: false,
+        }
+    }
+
+    pub fn with_trust_root(mut self, trust_root: TrustRoot) -> Self {
+        self.trust_root = Some(trust_root);
+        self
+    }
+
+    /// Accepts summaries without a Sigstore signature, trusting the issuer they claim.
+    pub fn allow_unsigned_summaries(mut self) -> Self {
+        self.allow_unsigned = true;
+        self
+    }
+
+    pub async fn verify(&self, bundle: &VerificationBundle) -> Result<OfflineReport, BundleError> {
+        if bundle.media_type != VERIFICATION_BUNDLE_MEDIA_TYPE {
+            return Err(BundleError::UnsupportedMediaType(bundle.media_type.clone()));
+        }
+        if let Some(bundled) = &bundle.trust_root {
+            if !self.trust_root.as_ref().is_some_and(|trust_root| same_trust_root(trust_root, bundled)) {
+                return Err(BundleError::UntrustedTrustRoot);
+            }
+        }
+        for bundled in &bundle.policies {
+            let configured = self.policies.iter().find(|p| p.purl == bundled.purl && p.version == bundled.version);
+            if configured != Some(bundled) {
+                return Err(BundleError::UntrustedPolicy(format!("{}@{}", bundled.purl, bundled.version)));
+            }
+        }
+
+        let summary = &bundle.summary.attestation;
+        let (summary_issuer, summary_signed) = match summary.bundle() {
+            Some(signed) => {
+                let trust_root = self.trust_root.as_ref().ok_or(BundleError::MissingTrustRoot)?;
+                let verified = trust_root.verify_bundle(&signed)?;
+                if verified.statement != summary.content {
+                    return Err(BundleError::SummaryTampered(summary.id.clone()));
+                }
+                (verified.identity.to_string(), true)
+            }
+            None if self.allow_unsigned => (summary.issuer.clone(), false),
+            None => return Err(BundleError::UnsignedSummary(summary.id.clone())),
+        };
+        let scai = parse_summary(summary)?;
+
+        let source = PinnedBundle {
+            bundle,
+            policies: &self.policies,
+        };
+        let report = match &self.trust_root {
+            Some(trust_root) => {
+                let verifier = SigstorePolicyVerifier::new(trust_root.clone(), &self.verifier);
+                ScaiVerifier::new(verifier).verify(&source, &bundle.summary.uri, &self.summary_policy).await?
+            }
+            None => ScaiVerifier::new(&self.verifier).verify(&source, &bundle.summary.uri, &self.summary_policy).await?,
+        };
+
+        Ok(OfflineReport {
+            passed: report.passed,
+            summary_issuer,
+            summary_signed,
+            subjects: scai.subject,
+            failures: report.failures,
+            evidence: report.evidence,
+        })
+    }
+}
+
+fn same_trust_root(a: &TrustRoot, b: &TrustRoot) -> bool {
+    matches!((canonical_sha256(a), canonical_sha256(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    use crate::controlplane::controlplane::{Component, ControlPlane, SDLCProject};
+    use crate::controlplane::summary::summary_statement;
+    use crate::models::policy::{PolicyRef, PolicyRules};
+    use crate::models::summary_scai::SummaryScaiPredicateAttributesItemAttribute;
+    use crate::storage::attestation_storage::InMemoryAttestationStorage;
+    use crate::storage::policy_repository::InMemoryPolicyRepository;
+    use crate::verification::policy_verifier::SourcePolicyVerifier;
+
+    #[tokio::test]
+    async fn test_export_and_verify_offline() {
+        let policy_repo = Arc::new(InMemoryPolicyRepository::new());
+        let attestation_storage = Arc::new(InMemoryAttestationStorage::new());
+        let control_plane = ControlPlane::new(policy_repo.clone(), attestation_storage.clone(), Arc::new(SourcePolicyVerifier));
+
+        policy_repo.add_policy(Policy {
+            purl: "pkg:github/acme/frontend".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules::new(["trusted_issuer".to_string()].into(), 30, 0, 0),
+            selection: None,
+        }).await.unwrap();
+        let project = SDLCProject {
+            name: "ACMEAppX".to_string(),
+            components: vec![Component {
+                name: "frontend".to_string(),
+                version: "1.2.3".to_string(),
+                policy: PolicyRef::latest("pkg:github/acme/frontend"),
+                digests: Default::default(),
+                selection: None,
+            }],
+        };
+        control_plane.add_project(project.clone()).await.unwrap();
+        attestation_storage.store_attestation(Arc::new(Attestation {
+            id: "att1".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({ "subject": [{ "name": "frontend", "version": "1.2.3", "digest": { "sha256": "ab".repeat(32) } }] }),
+            ..Default::default()
+        })).await.unwrap();
+
+        let report = control_plane.verify_project("ACMEAppX").await.unwrap();
+        let attestations = attestation_storage.list_attestation_entries().await.unwrap();
+        let statement = summary_statement(&project, &report, &attestations, SummaryScaiPredicateAttributesItemAttribute::PassedSource, "https://example.com/ci").unwrap();
+        let summary_uri = attestation_storage.store_attestation(Arc::new(Attestation {
+            id: "summary".to_string(),
+            issuer: "ci".to_string(),
+            timestamp: Utc::now(),
+            content: statement,
+            ..Default::default()
+        })).await.unwrap();
+
+        let bundle = VerificationBundle::export(attestation_storage.as_ref(), policy_repo.as_ref(), &summary_uri, None).await.unwrap();
+        assert_eq!(bundle.evidence.len(), 1);
+        assert_eq!(bundle.policies.len(), 1);
+
+        // Only the bundle travels to the air-gapped side
+        let bundle: VerificationBundle = serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap();
+        let summary_policy = Policy {
+            purl: "pkg:github/acme/summaries".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules::new(["ci".to_string()].into(), 30, 0, 0),
+            selection: None,
+        };
+        let policies = vec![(*policy_repo.get_policy("pkg:github/acme/frontend", Some("1.0.0")).await.unwrap()).clone()];
+
+        // The summary is unsigned, which is only accepted when asked for
+        let strict = OfflineVerifier::new(SourcePolicyVerifier, summary_policy.clone(), policies.clone());
+        assert!(matches!(strict.verify(&bundle).await, Err(BundleError::UnsignedSummary(_))));
+
+        let verifier = OfflineVerifier::new(SourcePolicyVerifier, summary_policy, policies).allow_unsigned_summaries();
+        let offline = verifier.verify(&bundle).await.unwrap();
+        assert!(offline.passed, "{:?}", offline.evidence);
+        assert!(offline.covers("sha256", &"ab".repeat(32)));
+
+        let mut tampered = bundle.clone();
+        tampered.evidence[0].attestation.issuer = "trusted_issuer ".to_string();
+        let offline = verifier.verify(&tampered).await.unwrap();
+        assert!(!offline.passed);
+        assert!(offline.evidence[0].failures[0].starts_with("evidence sha256 digest is"));
+
+        // A bundle cannot bring its own, looser policy
+        let mut loosened = bundle;
+        loosened.policies[0].rules.allowed_issuers.insert("mallory".to_string());
+        assert!(matches!(verifier.verify(&loosened).await, Err(BundleError::UntrustedPolicy(_))));
+    }
+}
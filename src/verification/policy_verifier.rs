@@ -1,34 +1,139 @@
 use async_trait::async_trait;
-use chrono::{Duration, Utc};
-use std::error::Error;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
 
-use crate::models::{attestation::Attestation, policy::Policy};
+use crate::models::{attestation::Attestation, policy::{Policy, PolicyRule}};
+use crate::storage::error::StorageError;
+
+/// The result of evaluating a single policy rule against an attestation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleOutcome {
+    pub rule: PolicyRule,
+    pub passed: bool,
+    pub reason: String,
+    /// IDs of the findings counted against the rule, when the attestation lists them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<String>,
+    /// How many counted findings exceed the rule's limit.
+    #[serde(default)]
+    pub excess: u32,
+}
+
+impl RuleOutcome {
+    pub fn new(rule: PolicyRule, passed: bool, reason: String) -> Self {
+        Self {
+            rule,
+            passed,
+            reason,
+            findings: Vec::new(),
+            excess: 0,
+        }
+    }
+
+    fn threshold(rule: PolicyRule, count: u32, limit: u32, findings: Vec<String>, label: &str) -> Self {
+        Self {
+            rule,
+            passed: count <= limit,
+            reason: format!("{} {}, limit {}", count, label, limit),
+            findings,
+            excess: count.saturating_sub(limit),
+        }
+    }
+}
+
+/// A waiver that turned a failing rule outcome into a passing one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedWaiver {
+    pub waiver_id: String,
+    pub rule: PolicyRule,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finding_id: Option<String>,
+    pub approver: String,
+    pub justification: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// The full result of evaluating an attestation against a policy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationResult {
+    pub attestation_id: String,
+    pub policy_purl: String,
+    pub policy_version: String,
+    pub outcomes: Vec<RuleOutcome>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub waivers_applied: Vec<AppliedWaiver>,
+}
+
+impl VerificationResult {
+    pub fn new(attestation: &Attestation, policy: &Policy, outcomes: Vec<RuleOutcome>) -> Self {
+        Self {
+            attestation_id: attestation.id.clone(),
+            policy_purl: policy.purl.clone(),
+            policy_version: policy.version.clone(),
+            outcomes,
+            waivers_applied: Vec::new(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.outcomes.iter().all(|o| o.passed)
+    }
+
+    pub fn failed_rules(&self) -> Vec<PolicyRule> {
+        self.outcomes.iter().filter(|o| !o.passed).map(|o| o.rule).collect()
+    }
+}
+
+/// Errors that keep an attestation from being evaluated at all. A policy the
+/// attestation does not satisfy is not an error but a failing `RuleOutcome`.
+#[derive(Error, Debug)]
+pub enum VerificationError {
+    #[error("Attestation {attestation_id} is malformed: {reason}")]
+    MalformedAttestation { attestation_id: String, reason: String },
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+impl VerificationError {
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, VerificationError::Storage(e) if e.is_retryable())
+    }
+}
 
 #[async_trait]
 pub trait PolicyVerifier: Send + Sync {
-    async fn verify_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<bool, Box<dyn Error + Send + Sync>>;
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError>;
+
+    async fn verify_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<bool, VerificationError> {
+        Ok(self.evaluate_attestation(attestation, policy).await?.is_valid())
+    }
+}
+
+/// Lets a borrowed verifier be wrapped, e.g. in a `SigstorePolicyVerifier` built per call.
+#[async_trait]
+impl<V: PolicyVerifier + ?Sized> PolicyVerifier for &V {
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        (**self).evaluate_attestation(attestation, policy).await
+    }
 }
 
 pub struct SimplePolicyVerifier;
 
 #[async_trait]
 impl PolicyVerifier for SimplePolicyVerifier {
-    async fn verify_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<bool, Box<dyn Error + Send + Sync>> {
-        // 1. Verify the identity
-        if !policy.rules.allowed_issuers.contains(&attestation.issuer) {
-            return Ok(false);
-        }
-
-        // 2. Ensure the attestation's timestamp is within the policy time frame
-        let age = Utc::now() - attestation.timestamp;
-        if age > Duration::days(policy.rules.max_age_days as i64) {
-            return Ok(false);
-        }
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        // 1. Verify the identity and 2. the age of the attestation
+        let mut outcomes = provenance_outcomes(attestation, policy);
 
         // 3. Verify the values in the JSON of the attestation
         let vulnerabilities = attestation.content.get("vulnerabilities")
             .and_then(|v| v.as_object())
-            .ok_or("Missing or invalid vulnerabilities data")?;
+            .ok_or_else(|| VerificationError::MalformedAttestation {
+                attestation_id: attestation.id.clone(),
+                reason: "missing or invalid vulnerabilities data".to_string(),
+            })?;
 
         let critical_vulns = vulnerabilities.get("critical")
             .and_then(|v| v.as_u64())
@@ -42,18 +147,72 @@ impl PolicyVerifier for SimplePolicyVerifier {
             .and_then(|v| v.as_u64())
             .unwrap_or(0) as u32;
 
-        if critical_vulns > policy.rules.max_critical_vulnerabilities {
-            return Ok(false);
-        }
+        outcomes.push(RuleOutcome::threshold(
+            PolicyRule::MaxCriticalVulnerabilities,
+            critical_vulns,
+            policy.rules.max_critical_vulnerabilities,
+            finding_ids(vulnerabilities, &["critical"]),
+            "critical",
+        ));
 
-        if high_vulns + medium_vulns > policy.rules.max_high_medium_vulnerabilities {
-            return Ok(false);
-        }
+        outcomes.push(RuleOutcome::threshold(
+            PolicyRule::MaxHighMediumVulnerabilities,
+            high_vulns + medium_vulns,
+            policy.rules.max_high_medium_vulnerabilities,
+            finding_ids(vulnerabilities, &["high", "medium"]),
+            "high/medium",
+        ));
+
+        Ok(VerificationResult::new(attestation, policy, outcomes))
+    }
+}
+
+/// Verifies only who issued an attestation and how old it is.
+///
+/// Meant for source attestations, such as those generated for git commits, which
+/// carry no vulnerability data for the remaining rules to evaluate.
+pub struct SourcePolicyVerifier;
 
-        Ok(true)
+#[async_trait]
+impl PolicyVerifier for SourcePolicyVerifier {
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        Ok(VerificationResult::new(attestation, policy, provenance_outcomes(attestation, policy)))
     }
 }
 
+fn provenance_outcomes(attestation: &Attestation, policy: &Policy) -> Vec<RuleOutcome> {
+    let age = Utc::now() - attestation.timestamp;
+
+    vec![
+        RuleOutcome::new(
+            PolicyRule::AllowedIssuers,
+            policy.rules.is_issuer_allowed(&attestation.issuer),
+            format!("issuer {}", attestation.issuer),
+        ),
+        // Ensure the attestation's timestamp is within the policy time frame
+        RuleOutcome::new(
+            PolicyRule::MaxAgeDays,
+            age <= Duration::days(policy.rules.max_age_days as i64),
+            format!("age {} days, limit {}", age.num_days(), policy.rules.max_age_days),
+        ),
+    ]
+}
+
+/// Collects the IDs of listed findings with one of the given severities, e.g.
+/// `"findings": [{ "id": "CVE-2024-1234", "severity": "critical" }]`.
+fn finding_ids(vulnerabilities: &Map<String, Value>, severities: &[&str]) -> Vec<String> {
+    vulnerabilities.get("findings")
+        .and_then(|f| f.as_array())
+        .map(|findings| {
+            findings
+                .iter()
+                .filter(|f| f["severity"].as_str().is_some_and(|s| severities.contains(&s)))
+                .filter_map(|f| f["id"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -73,6 +232,7 @@ mod tests {
                 max_critical_vulnerabilities: 0,
                 max_high_medium_vulnerabilities: 5,
             },
+            selection: None,
         };
 
         let valid_attestation = Attestation {
@@ -87,6 +247,8 @@ mod tests {
                     "low": 10
                 }
             }),
+            envelope: None,
+            verification_material: None,
         };
 
         let invalid_attestation = Attestation {
@@ -101,9 +263,33 @@ mod tests {
                     "low": 10
                 }
             }),
+            envelope: None,
+            verification_material: None,
         };
 
         assert!(verifier.verify_attestation(&valid_attestation, &policy).await.unwrap());
         assert!(!verifier.verify_attestation(&invalid_attestation, &policy).await.unwrap());
+
+        let result = verifier.evaluate_attestation(&invalid_attestation, &policy).await.unwrap();
+        assert_eq!(result.failed_rules(), vec![
+            PolicyRule::AllowedIssuers,
+            PolicyRule::MaxAgeDays,
+            PolicyRule::MaxCriticalVulnerabilities,
+            PolicyRule::MaxHighMediumVulnerabilities,
+        ]);
+
+        // Source attestations carry no vulnerability data; only identity and age apply to them
+        let commit_attestation = Attestation {
+            id: "commit1".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({ "subject": [{ "digest": { "gitCommit": "a".repeat(40) } }] }),
+            envelope: None,
+            verification_material: None,
+        };
+        assert!(verifier.evaluate_attestation(&commit_attestation, &policy).await.is_err());
+        let result = SourcePolicyVerifier.evaluate_attestation(&commit_attestation, &policy).await.unwrap();
+        assert!(result.is_valid());
+        assert_eq!(result.outcomes.len(), 2);
     }
 }
\ No newline at end of file
@@ -0,0 +1,477 @@
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::controlplane::summary::SCAI_PREDICATE_TYPE;
+use crate::models::attestation::Attestation;
+use crate::models::canonical::to_canonical_vec;
+use crate::models::policy::{Policy, PolicyError};
+use crate::models::resource_descriptor::DigestSet;
+use crate::models::summary_scai::{ResourceDescriptor, SummaryScai};
+use crate::storage::attestation_storage::AttestationStorage;
+use crate::storage::error::StorageError;
+use crate::storage::policy_repository::PolicyRepository;
+use crate::verification::policy_verifier::{PolicyVerifier, VerificationError, VerificationResult};
+
+/// How many summaries deep `ScaiVerifier` follows nested reports by default.
+pub const DEFAULT_MAX_DEPTH: usize = 8;
+
+/// Errors that keep a summary from being checked at all. Evidence that is missing,
+/// altered or fails its policy is reported in the `EvidenceCheck` instead.
+#[derive(Error, Debug)]
+pub enum EvidenceError {
+    #[error("Attestation {0} is not a SCAI summary: {1}")]
+    NotASummary(String, String),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+    #[error(transparent)]
+    Verification(#[from] VerificationError),
+}
+
+impl EvidenceError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            EvidenceError::NotASummary(..) => false,
+            EvidenceError::Storage(e) => e.is_retryable(),
+            EvidenceError::Policy(e) => e.is_retryable(),
+            EvidenceError::Verification(e) => e.is_retryable(),
+        }
+    }
+}
+
+/// Where the evidence and policies a summary refers to are looked up.
+#[async_trait]
+pub trait EvidenceSource: Send + Sync {
+    async fn attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError>;
+    async fn policy(&self, purl: &str, version: &str) -> Result<Arc<Policy>, PolicyError>;
+}
+
+/// Looks evidence up in the attestation storage and policies in the policy repository.
+pub struct StoredEvidence<A, P> {
+    attestations: Arc<A>,
+    policies: Arc<P>,
+}
+
+impl<A: AttestationStorage, P: PolicyRepository> StoredEvidence<A, P> {
+    pub fn new(attestations: Arc<A>, policies: Arc<P>) -> Self {
+        Self { attestations, policies }
+    }
+}
+
+#[async_trait]
+impl<A: AttestationStorage, P: PolicyRepository> EvidenceSource for StoredEvidence<A, P> {
+    async fn attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+        self.attestations.get_attestation(uri).await
+    }
+
+    async fn policy(&self, purl: &str, version: &str) -> Result<Arc<Policy>, PolicyError> {
+        self.policies.get_policy(purl, Some(version)).await
+    }
+}
+
+/// The outcome of checking one attribute of a summary against its evidence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceCheck {
+    pub attribute: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+    /// Why the attribute is not supported by its evidence; empty if it is.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationResult>,
+    /// The checks of a nested summary's own attributes, if the evidence is one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nested: Vec<EvidenceCheck>,
+}
+
+impl EvidenceCheck {
+    /// Whether the evidence, and any evidence nested below it, holds up.
+    pub fn is_supported(&self) -> bool {
+        self.failures.is_empty() && self.nested.iter().all(EvidenceCheck::is_supported)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaiReport {
+    pub summary: String,
+    pub passed: bool,
+    /// The summary's own evaluation against the summary policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationResult>,
+    /// Why the summary itself does not hold up; empty if it does.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+    pub evidence: Vec<EvidenceCheck>,
+}
+
+/// The checks of a summary's evidence and the failures of its subjects that no evidence
+/// is about.
+type SummaryChecks = (Vec<EvidenceCheck>, Vec<String>);
+
+/// Checks a SCAI summary and follows its evidence.
+///
+/// The summary itself is evaluated against the summary policy, so its issuer, age and,
+/// with a signature-checking verifier, its signature must hold up. Each evidence
+/// descriptor is then resolved through an `EvidenceSource`, its digest compared with the
+/// fetched attestation, its subjects matched against the summary's, and the attestation
+/// evaluated against the policy named in the attribute's conditions. Every subject of a
+/// summary must be named by some evidence. Evidence that is itself a SCAI summary is
+/// checked the same way, up to `max_depth` summaries deep; evidence leading back to a
+/// summary on the current path fails.
+pub struct ScaiVerifier<V> {
+    verifier: V,
+    max_depth: usize,
+}
+
+impl<V: PolicyVerifier> ScaiVerifier<V> {
+    pub fn new(verifier: V) -> Self {
+        Self {
+            verifier,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub async fn verify<S: EvidenceSource + ?Sized>(&self, source: &S, summary_uri: &str, summary_policy: &Policy) -> Result<ScaiReport, EvidenceError> {
+        let summary = source.attestation(summary_uri).await?;
+        let scai = parse_summary(&summary)?;
+
+        let mut failures = Vec::new();
+        let verification = match self.verifier.evaluate_attestation(&summary, summary_policy).await {
+            Ok(result) => {
+                for rule in result.failed_rules() {
+                    failures.push(format!("summary fails policy rule {}", rule));
+                }
+                Some(result)
+            }
+            Err(e @ VerificationError::MalformedAttestation { .. }) => {
+                failures.push(e.to_string());
+                None
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut path = vec![summary_uri.to_string()];
+        let (evidence, uncovered) = self.check_summary(source, &scai, &mut path).await?;
+        failures.extend(uncovered);
+
+        Ok(ScaiReport {
+            summary: summary_uri.to_string(),
+            passed: failures.is_empty() && !evidence.is_empty() && evidence.iter().all(EvidenceCheck::is_supported),
+            verification,
+            failures,
+            evidence,
+        })
+    }
+
+    /// Checks the evidence of every attribute, and returns the checks along with a
+    /// failure for each subject of the summary that no evidence names.
+    ///
+    /// `path` holds the URIs of the summaries being checked, outermost first.
+    fn check_summary<'a, S: EvidenceSource + ?Sized>(&'a self, source: &'a S, scai: &'a SummaryScai, path: &'a mut Vec<String>) -> BoxFuture<'a, Result<SummaryChecks, EvidenceError>> {
+        Box::pin(async move {
+            let mut checks = Vec::new();
+            let mut covered = vec![false; scai.subject.len()];
+            for item in &scai.predicate.attributes {
+                let mut check = EvidenceCheck {
+                    attribute: item.attribute.to_string(),
+                    uri: item.evidence.uri.clone(),
+                    policy: item.conditions.as_ref().and_then(|c| c.policy.clone()),
+                    failures: Vec::new(),
+                    verification: None,
+                    nested: Vec::new(),
+                };
+                if let Some(attestation) = self.check_evidence(source, &item.evidence, scai, path, &mut check).await? {
+                    for (subject, covered) in scai.subject.iter().zip(covered.iter_mut()) {
+                        *covered |= names_subject(&attestation, &subject.digest);
+                    }
+                }
+                checks.push(check);
+            }
+
+            let uncovered = scai
+                .subject
+                .iter()
+                .zip(covered)
+                .filter(|(_, covered)| !covered)
+                .map(|(subject, _)| format!("no evidence is about summary subject {}", describe(subject)))
+                .collect();
+            Ok((checks, uncovered))
+        })
+    }
+
+    /// Returns the evidence attestation, if it could be fetched.
+    async fn check_evidence<S: EvidenceSource + ?Sized>(
+        &self,
+        source: &S,
+        descriptor: &ResourceDescriptor,
+        scai: &SummaryScai,
+        path: &mut Vec<String>,
+        check: &mut EvidenceCheck,
+    ) -> Result<Option<Arc<Attestation>>, EvidenceError> {
+        let Some(uri) = check.uri.clone() else {
+            check.failures.push("the summary names no URI for the evidence".to_string());
+            return Ok(None);
+        };
+        if path.contains(&uri) {
+            check.failures.push(format!("evidence cycles back to summary {}", uri));
+            return Ok(None);
+        }
+
+        let attestation = match source.attestation(&uri).await {
+            Ok(attestation) => attestation,
+            Err(StorageError::NotFound { .. }) => {
+                check.failures.push("evidence not found".to_string());
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        check_digest(descriptor, &attestation, &mut check.failures);
+        // Evidence about another artifact says nothing about the summary's subjects
+        if !scai.subject.iter().any(|subject| names_subject(&attestation, &subject.digest)) {
+            check.failures.push("evidence is not about any subject of the summary".to_string());
+        }
+
+        match check.policy.as_deref().and_then(split_policy) {
+            Some((purl, version)) => match source.policy(purl, version).await {
+                Ok(policy) => match self.verifier.evaluate_attestation(&attestation, &policy).await {
+                    Ok(result) => {
+                        for rule in result.failed_rules() {
+                            check.failures.push(format!("evidence fails policy rule {}", rule));
+                        }
+                        check.verification = Some(result);
+                    }
+                    Err(e @ VerificationError::MalformedAttestation { .. }) => check.failures.push(e.to_string()),
+                    Err(e) => return Err(e.into()),
+                },
+                Err(PolicyError::Storage(e)) => return Err(e.into()),
+                Err(e) => check.failures.push(e.to_string()),
+            },
+            None => check.failures.push("the summary names no policy for the evidence".to_string()),
+        }
+
+        if attestation.content["predicateType"].as_str() == Some(SCAI_PREDICATE_TYPE) {
+            if path.len() >= self.max_depth {
+                check.failures.push(format!("nested summary exceeds the depth limit of {}", self.max_depth));
+                return Ok(Some(attestation));
+            }
+
+            match parse_summary(&attestation) {
+                Ok(nested) => {
+                    path.push(uri);
+                    let result = self.check_summary(source, &nested, path).await;
+                    path.pop();
+                    let (nested, uncovered) = result?;
+                    check.nested = nested;
+                    check.failures.extend(uncovered);
+                }
+                Err(e) => check.failures.push(e.to_string()),
+            }
+        }
+
+        Ok(Some(attestation))
+    }
+}
+
+pub(crate) fn parse_summary(attestation: &Attestation) -> Result<SummaryScai, EvidenceError> {
+    let not_a_summary = |reason: String| EvidenceError::NotASummary(attestation.id.clone(), reason);
+    let scai: SummaryScai = serde_json::from_value(attestation.content.clone()).map_err(|e| not_a_summary(e.to_string()))?;
+    scai.validate().map_err(not_a_summary)?;
+    Ok(scai)
+}
+
+/// Records a failure unless the descriptor names the attestation's digest in every
+/// algorithm it lists that can be computed here, and in at least one.
+fn check_digest(descriptor: &ResourceDescriptor, attestation: &Attestation, failures: &mut Vec<String>) {
+    let json = to_canonical_vec(attestation).unwrap_or_default();
+    let computed = [
+        ("sha256", hex::encode(Sha256::digest(&json))),
+        ("sha512", hex::encode(Sha512::digest(&json))),
+    ];
+
+    let mut checked = false;
+    for (algorithm, digest) in computed {
+        if let Some(expected) = descriptor.digest.get(algorithm) {
+            checked = true;
+            if *expected != digest {
+                failures.push(format!("evidence {} digest is {}, the summary names {}", algorithm, digest, expected));
+            }
+        }
+    }
+    if !checked {
+        failures.push("the summary names no sha256 or sha512 digest for the evidence".to_string());
+    }
+}
+
+/// Whether any subject of the attestation's statement has the given digests: they share
+/// at least one algorithm and agree on every algorithm they share.
+fn names_subject(attestation: &Attestation, digest: &DigestSet) -> bool {
+    let Some(subjects) = attestation.content["subject"].as_array() else {
+        return false;
+    };
+
+    subjects.iter().filter_map(|s| s["digest"].as_object()).any(|other| {
+        let mut shared = digest.iter().filter_map(|(algorithm, value)| Some((value, other.get(algorithm)?))).peekable();
+        shared.peek().is_some() && shared.all(|(value, other)| other.as_str() == Some(value.as_str()))
+    })
+}
+
+fn describe(subject: &ResourceDescriptor) -> String {
+    match (&subject.name, subject.digest.iter().next()) {
+        (Some(name), _) => name.clone(),
+        (None, Some((algorithm, value))) => format!("{}:{}", algorithm, value),
+        (None, None) => "without a name or digest".to_string(),
+    }
+}
+
+/// Splits a `purl@version` policy condition.
+pub(crate) fn split_policy(policy: &str) -> Option<(&str, &str)> {
+    policy.rsplit_once('@')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+
+    use crate::models::audit::AuditedAttestation;
+    use crate::models::policy::PolicyRules;
+    use crate::verification::policy_verifier::SourcePolicyVerifier;
+
+    struct MapSource {
+        attestations: HashMap<String, Arc<Attestation>>,
+        policy: Arc<Policy>,
+    }
+
+    #[async_trait]
+    impl EvidenceSource for MapSource {
+        async fn attestation(&self, uri: &str) -> Result<Arc<Attestation>, StorageError> {
+            self.attestations.get(uri).cloned().ok_or_else(|| StorageError::not_found("Attestation", uri))
+        }
+
+        async fn policy(&self, purl: &str, version: &str) -> Result<Arc<Policy>, PolicyError> {
+            if purl == self.policy.purl && version == self.policy.version {
+                Ok(self.policy.clone())
+            } else {
+                Err(PolicyError::VersionNotFound { purl: purl.to_string(), version: version.to_string() })
+            }
+        }
+    }
+
+    fn attestation(id: &str, content: Value) -> Attestation {
+        Attestation {
+            id: id.to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// A summary whose single attribute points at `uri` with the given digest.
+    fn summary(id: &str, uri: &str, sha256: &str) -> Attestation {
+        attestation(id, json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "app", "digest": { "sha256": "ab".repeat(32) } }],
+            "predicateType": SCAI_PREDICATE_TYPE,
+            "predicate": {
+                "attributes": [{
+                    "attribute": "PASSED_BUILD",
+                    "evidence": { "uri": uri, "digest": { "sha256": sha256 } },
+                    "conditions": { "policy": "pkg:github/acme/app@1.0.0" },
+                }],
+                "producer": { "uri": "https://example.com/ci" },
+            },
+        }))
+    }
+
+    fn digest(uri: &str, attestation: &Attestation) -> String {
+        AuditedAttestation::new(uri, attestation).sha256
+    }
+
+    #[tokio::test]
+    async fn test_nested_summaries() {
+        let build = attestation("build", json!({
+            "subject": [{ "name": "app", "digest": { "sha256": "ab".repeat(32) } }],
+            "predicateType": "https://slsa.dev/provenance/v1",
+        }));
+        let inner = summary("inner", "build", &digest("build", &build));
+        let outer = summary("outer", "inner", &digest("inner", &inner));
+        let tampered = summary("tampered", "build", &"00".repeat(32));
+        // Genuine evidence, but for another artifact than the summary claims
+        let mut forged = summary("forged", "build", &digest("build", &build));
+        forged.content["subject"][0]["digest"]["sha256"] = json!("cd".repeat(32));
+        let mut untrusted = summary("untrusted", "build", &digest("build", &build));
+        untrusted.issuer = "mallory".to_string();
+        let source = MapSource {
+            attestations: [("build", build), ("inner", inner), ("outer", outer), ("tampered", tampered), ("forged", forged), ("untrusted", untrusted)]
+                .into_iter()
+                .map(|(uri, att)| (uri.to_string(), Arc::new(att)))
+                .collect(),
+            policy: Arc::new(Policy {
+                purl: "pkg:github/acme/app".to_string(),
+                version: "1.0.0".to_string(),
+                rules: PolicyRules::new(["trusted_issuer".to_string()].into(), 30, 0, 0),
+                selection: None,
+            }),
+        };
+
+        let policy = source.policy.clone();
+        let report = ScaiVerifier::new(SourcePolicyVerifier).verify(&source, "outer", &policy).await.unwrap();
+        assert!(report.passed, "{:?}", report);
+        assert_eq!(report.evidence[0].nested[0].uri.as_deref(), Some("build"));
+
+        let report = ScaiVerifier::new(SourcePolicyVerifier).with_max_depth(1).verify(&source, "outer", &policy).await.unwrap();
+        assert!(!report.passed);
+        assert!(report.evidence[0].failures[0].contains("depth limit"));
+
+        let report = ScaiVerifier::new(SourcePolicyVerifier).verify(&source, "tampered", &policy).await.unwrap();
+        assert!(report.evidence[0].failures[0].starts_with("evidence sha256 digest is"));
+
+        let report = ScaiVerifier::new(SourcePolicyVerifier).verify(&source, "forged", &policy).await.unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.evidence[0].failures, vec!["evidence is not about any subject of the summary".to_string()]);
+        assert_eq!(report.failures, vec!["no evidence is about summary subject app".to_string()]);
+
+        let report = ScaiVerifier::new(SourcePolicyVerifier).verify(&source, "untrusted", &policy).await.unwrap();
+        assert!(!report.passed);
+        assert!(report.evidence[0].is_supported());
+        assert_eq!(report.failures, vec!["summary fails policy rule allowed_issuers".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_evidence_cycle() {
+        // b's digest of a cannot be right, as a's content depends on b's digest
+        let b = summary("b", "a", &"00".repeat(32));
+        let a = summary("a", "b", &digest("b", &b));
+        let source = MapSource {
+            attestations: [("a".to_string(), Arc::new(a)), ("b".to_string(), Arc::new(b))].into(),
+            policy: Arc::new(Policy {
+                purl: "pkg:github/acme/app".to_string(),
+                version: "1.0.0".to_string(),
+                rules: PolicyRules::new(["trusted_issuer".to_string()].into(), 30, 0, 0),
+                selection: None,
+            }),
+        };
+
+        let report = ScaiVerifier::new(SourcePolicyVerifier).verify(&source, "a", &source.policy).await.unwrap();
+        assert!(!report.passed);
+        // b's only evidence is the cycle, so nothing speaks for b's subject either
+        assert_eq!(report.evidence[0].failures, vec!["no evidence is about summary subject app".to_string()]);
+        assert_eq!(report.evidence[0].nested[0].failures, vec!["evidence cycles back to summary a".to_string()]);
+    }
+}
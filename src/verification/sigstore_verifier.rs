@@ -0,0 +1,820 @@
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use p256::pkcs8::DecodePublicKey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha384};
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+use thiserror::Error;
+use x509_cert::der::asn1::{ObjectIdentifier, Utf8StringRef};
+use x509_cert::der::oid::AssociatedOid;
+use x509_cert::der::{Decode, DecodePem, Encode};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{ExtendedKeyUsage, SubjectAltName};
+use x509_cert::Certificate;
+
+use crate::models::attestation::Attestation;
+use crate::models::dsse::{pae, IN_TOTO_PAYLOAD_TYPE};
+use crate::models::policy::{Policy, PolicyRule};
+use crate::models::sigstore::{Bundle, LogId, SigstoreIdentity, TransparencyLogEntry, X509Certificate, BUNDLE_MEDIA_TYPE};
+use crate::verification::policy_verifier::{PolicyVerifier, RuleOutcome, VerificationError, VerificationResult};
+
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const CODE_SIGNING: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.3.3");
+/// Fulcio's OIDC issuer extension, DER-encoded.
+const FULCIO_ISSUER: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.57264.1.8");
+/// Fulcio's deprecated OIDC issuer extension, holding the raw string.
+const FULCIO_ISSUER_V1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.6.1.4.1.57264.1.1");
+
+#[derive(Error, Debug)]
+pub enum SigstoreError {
+    #[error("Unsupported bundle media type {0}, expected {BUNDLE_MEDIA_TYPE}")]
+    UnsupportedMediaType(String),
+    #[error("Malformed {what}: {reason}")]
+    Malformed {
+        what: &'static str,
+        reason: String,
+    },
+    #[error("Bundle has no transparency log entry")]
+    MissingLogEntry,
+    #[error("Transparency log entry has no {0}")]
+    IncompleteLogEntry(&'static str),
+    #[error("Transparency log {0} is not in the trust root")]
+    UnknownLog(String),
+    #[error("Signed entry timestamp does not verify under the log's key")]
+    InvalidSignedEntryTimestamp,
+    #[error("Inclusion proof does not lead to the log's root hash")]
+    InvalidInclusionProof,
+    #[error("Invalid checkpoint: {0}")]
+    InvalidCheckpoint(String),
+    #[error("Transparency log entry does not match the bundle: {0}")]
+    EntryMismatch(String),
+    #[error("Certificate does not chain to a trusted certificate authority at {0}")]
+    UntrustedCertificate(DateTime<Utc>),
+    #[error("Certificate was not valid at {0}")]
+    CertificateNotValid(DateTime<Utc>),
+    #[error("Certificate is not issued for code signing")]
+    MissingCodeSigning,
+    #[error("Certificate has no {0}")]
+    MissingIdentity(&'static str),
+    #[error("No envelope signature verifies under the certificate's key")]
+    InvalidSignature,
+}
+
+fn malformed<E: Display>(what: &'static str) -> impl FnOnce(E) -> SigstoreError {
+    move |e| SigstoreError::Malformed { what, reason: e.to_string() }
+}
+
+/// The parts of a Sigstore `trusted_root.json` needed to verify bundles offline: the
+/// Fulcio certificate chains and the Rekor log keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrustRoot {
+    #[serde(default)]
+    pub tlogs: Vec<TransparencyLogInstance>,
+    #[serde(default)]
+    pub certificate_authorities: Vec<CertificateAuthority>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransparencyLogInstance {
+    #[serde(default)]
+    pub base_url: String,
+    pub public_key: PublicKeyMaterial,
+    pub log_id: LogId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyMaterial {
+    /// Base64-encoded DER SubjectPublicKeyInfo.
+    pub raw_bytes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_for: Option<ValidityPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateAuthority {
+    #[serde(default)]
+    pub uri: String,
+    pub cert_chain: CertificateChain,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_for: Option<ValidityPeriod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateChain {
+    /// The issuing certificate first, the root last.
+    pub certificates: Vec<X509Certificate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidityPeriod {
+    pub start: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl ValidityPeriod {
+    fn covers(period: &Option<Self>, at: DateTime<Utc>) -> bool {
+        period.as_ref().is_none_or(|p| p.start <= at && p.end.is_none_or(|end| at <= end))
+    }
+}
+
+/// What a verified bundle attests to, and who signed it when.
+#[derive(Debug, Clone)]
+pub struct VerifiedBundle {
+    pub identity: SigstoreIdentity,
+    pub statement: Value,
+    pub integrated_time: DateTime<Utc>,
+    pub log_index: i64,
+}
+
+impl TrustRoot {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Ok(Self::from_json(&tokio::fs::read_to_string(path).await?)?)
+    }
+
+    /// Verifies a bundle without network access: the Rekor entry's signed timestamp,
+    /// inclusion proof and checkpoint, the Fulcio certificate at the time the entry was
+    /// logged, and the envelope signature.
+    pub fn verify_bundle(&self, bundle: &Bundle) -> Result<VerifiedBundle, SigstoreError> {
+        if bundle.media_type != BUNDLE_MEDIA_TYPE {
+            return Err(SigstoreError::UnsupportedMediaType(bundle.media_type.clone()));
+        }
+
+        let material = &bundle.verification_material;
+        let entry = material.tlog_entries.first().ok_or(SigstoreError::MissingLogEntry)?;
+        let integrated_time = self.verify_log_entry(entry)?;
+
+        let leaf_der = STANDARD.decode(&material.certificate.raw_bytes).map_err(malformed("certificate"))?;
+        let leaf = Certificate::from_der(&leaf_der).map_err(malformed("certificate"))?;
+        let identity = self.verify_certificate(&leaf, integrated_time)?;
+
+        let envelope = &bundle.dsse_envelope;
+        if envelope.payload_type != IN_TOTO_PAYLOAD_TYPE {
+            return Err(SigstoreError::Malformed {
+                what: "envelope",
+                reason: format!("payload type {} is not an in-toto statement", envelope.payload_type),
+            });
+        }
+        let payload = STANDARD.decode(&envelope.payload).map_err(malformed("envelope payload"))?;
+        let leaf_key = PublicKey::from_spki(&leaf)?;
+        let message = pae(&envelope.payload_type, &payload);
+        let signature = envelope
+            .signatures
+            .iter()
+            .filter_map(|s| STANDARD.decode(&s.sig).ok())
+            .find(|sig| leaf_key.verify(&message, sig, None))
+            .ok_or(SigstoreError::InvalidSignature)?;
+
+        check_dsse_entry(entry, &payload, &signature, &leaf_der)?;
+
+        Ok(VerifiedBundle {
+            identity,
+            statement: serde_json::from_slice(&payload).map_err(malformed("statement"))?,
+            integrated_time,
+            log_index: entry.log_index,
+        })
+    }
+
+    /// Verifies a bundle and turns it into an attestation issued by the certificate's
+    /// identity at the time Rekor logged it.
+    pub fn import_bundle(&self, bundle: Bundle) -> Result<Attestation, SigstoreError> {
+        let verified = self.verify_bundle(&bundle)?;
+
+        Ok(Attestation {
+            id: uuid::Uuid::new_v4().to_string(),
+            issuer: verified.identity.to_string(),
+            timestamp: verified.integrated_time,
+            content: verified.statement,
+            envelope: Some(bundle.dsse_envelope),
+            verification_material: Some(bundle.verification_material),
+        })
+    }
+
+    /// Checks the entry against a log in the trust root and returns its integration time.
+    fn verify_log_entry(&self, entry: &TransparencyLogEntry) -> Result<DateTime<Utc>, SigstoreError> {
+        let log_id = STANDARD.decode(&entry.log_id.key_id).map_err(malformed("log ID"))?;
+        let integrated_time = DateTime::from_timestamp(entry.integrated_time, 0).ok_or_else(|| SigstoreError::Malformed {
+            what: "log entry",
+            reason: format!("integrated time {} is out of range", entry.integrated_time),
+        })?;
+
+        let log = self
+            .tlogs
+            .iter()
+            .find(|log| STANDARD.decode(&log.log_id.key_id).is_ok_and(|id| id == log_id))
+            .filter(|log| ValidityPeriod::covers(&log.public_key.valid_for, integrated_time))
+            .ok_or_else(|| SigstoreError::UnknownLog(hex::encode(&log_id)))?;
+        let log_key = PublicKey::from_der(&STANDARD.decode(&log.public_key.raw_bytes).map_err(malformed("log public key"))?)?;
+
+        // The signed entry timestamp covers the canonical JSON of these fields, in this order
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SignedEntry<'a> {
+            body: &'a str,
+            integrated_time: i64,
+            #[serde(rename = "logID")]
+            log_id: String,
+            log_index: i64,
+        }
+
+        let promise = entry.inclusion_promise.as_ref().ok_or(SigstoreError::IncompleteLogEntry("inclusion promise"))?;
+        let signed_entry = serde_json::to_vec(&SignedEntry {
+            body: &entry.canonicalized_body,
+            integrated_time: entry.integrated_time,
+            log_id: hex::encode(&log_id),
+            log_index: entry.log_index,
+        })
+        .map_err(malformed("log entry"))?;
+        let set = STANDARD.decode(&promise.signed_entry_timestamp).map_err(malformed("signed entry timestamp"))?;
+        if !log_key.verify(&signed_entry, &set, None) {
+            return Err(SigstoreError::InvalidSignedEntryTimestamp);
+        }
+
+        let proof = entry.inclusion_proof.as_ref().ok_or(SigstoreError::IncompleteLogEntry("inclusion proof"))?;
+        let body = STANDARD.decode(&entry.canonicalized_body).map_err(malformed("log entry body"))?;
+        let root_hash = STANDARD.decode(&proof.root_hash).map_err(malformed("root hash"))?;
+        let hashes = proof
+            .hashes
+            .iter()
+            .map(|h| STANDARD.decode(h).ok().and_then(|h| <[u8; 32]>::try_from(h).ok()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SigstoreError::InvalidInclusionProof)?;
+        let (Ok(index), Ok(tree_size)) = (u64::try_from(proof.log_index), u64::try_from(proof.tree_size)) else {
+            return Err(SigstoreError::InvalidInclusionProof);
+        };
+        if root_from_inclusion_proof(index, tree_size, leaf_hash(&body), &hashes).is_none_or(|root| root[..] != root_hash[..]) {
+            return Err(SigstoreError::InvalidInclusionProof);
+        }
+
+        let (checkpoint_size, checkpoint_root) = verify_checkpoint(&proof.checkpoint.envelope, &log_key, &log_id)?;
+        if checkpoint_size != tree_size || checkpoint_root != root_hash {
+            return Err(SigstoreError::InvalidCheckpoint(format!(
+                "commits to tree size {} rather than the proof's {}, or to another root",
+                checkpoint_size, tree_size
+            )));
+        }
+
+        Ok(integrated_time)
+    }
+
+    /// Checks the leaf certificate against the trusted chains at the given time and reads its identity.
+    fn verify_certificate(&self, leaf: &Certificate, at: DateTime<Utc>) -> Result<SigstoreIdentity, SigstoreError> {
+        if !valid_at(leaf, at) {
+            return Err(SigstoreError::CertificateNotValid(at));
+        }
+
+        let trusted = self
+            .certificate_authorities
+            .iter()
+            .filter(|ca| ValidityPeriod::covers(&ca.valid_for, at))
+            .any(|ca| {
+                let Some(chain) = ca
+                    .cert_chain
+                    .certificates
+                    .iter()
+                    .map(|c| STANDARD.decode(&c.raw_bytes).ok().and_then(|der| Certificate::from_der(&der).ok()))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return false;
+                };
+
+                let mut path = std::iter::once(leaf).chain(chain.iter()).peekable();
+                !chain.is_empty()
+                    && std::iter::from_fn(|| Some((path.next()?, *path.peek()?)))
+                        .all(|(subject, issuer)| valid_at(issuer, at) && issued_by(subject, issuer))
+            });
+        if !trusted {
+            return Err(SigstoreError::UntrustedCertificate(at));
+        }
+
+        let usage = extension(leaf, ExtendedKeyUsage::OID)
+            .and_then(|value| ExtendedKeyUsage::from_der(value).ok())
+            .ok_or(SigstoreError::MissingCodeSigning)?;
+        if !usage.0.contains(&CODE_SIGNING) {
+            return Err(SigstoreError::MissingCodeSigning);
+        }
+
+        let san = extension(leaf, SubjectAltName::OID)
+            .and_then(|value| SubjectAltName::from_der(value).ok())
+            .and_then(|names| {
+                names.0.iter().find_map(|name| match name {
+                    GeneralName::UniformResourceIdentifier(uri) => Some(uri.to_string()),
+                    GeneralName::Rfc822Name(email) => Some(email.to_string()),
+                    _ => None,
+                })
+            })
+            .ok_or(SigstoreError::MissingIdentity("subject alternative name"))?;
+
+        let issuer = extension(leaf, FULCIO_ISSUER)
+            .and_then(|value| Utf8StringRef::from_der(value).ok().map(|s| s.as_str().to_string()))
+            .or_else(|| extension(leaf, FULCIO_ISSUER_V1).and_then(|value| String::from_utf8(value.to_vec()).ok()))
+            .ok_or(SigstoreError::MissingIdentity("OIDC issuer"))?;
+
+        Ok(SigstoreIdentity { issuer, san })
+    }
+}
+
+/// Checks that the log entry records this envelope's payload, signed with this certificate.
+fn check_dsse_entry(entry: &TransparencyLogEntry, payload: &[u8], signature: &[u8], leaf_der: &[u8]) -> Result<(), SigstoreError> {
+    if entry.kind_version.kind != "dsse" {
+        return Err(SigstoreError::EntryMismatch(format!("unsupported entry kind {}", entry.kind_version.kind)));
+    }
+
+    let body: Value = STANDARD
+        .decode(&entry.canonicalized_body)
+        .map_err(malformed("log entry body"))
+        .and_then(|body| serde_json::from_slice(&body).map_err(malformed("log entry body")))?;
+    let spec = &body["spec"];
+
+    if spec["payloadHash"]["value"].as_str() != Some(hex::encode(Sha256::digest(payload)).as_str()) {
+        return Err(SigstoreError::EntryMismatch("payload hash differs".to_string()));
+    }
+
+    let recorded = spec["signatures"].as_array().into_iter().flatten().any(|s| {
+        let same_signature = s["signature"].as_str().and_then(|sig| STANDARD.decode(sig).ok()).is_some_and(|sig| sig == signature);
+        let same_certificate = s["verifier"]
+            .as_str()
+            .and_then(|pem| STANDARD.decode(pem).ok())
+            .and_then(|pem| Certificate::from_pem(&pem).ok())
+            .and_then(|cert| cert.to_der().ok())
+            .is_some_and(|der| der == leaf_der);
+        same_signature && same_certificate
+    });
+    if !recorded {
+        return Err(SigstoreError::EntryMismatch("signature or certificate differs".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Verifies a signed note checkpoint and returns the tree size and root hash it commits to.
+fn verify_checkpoint(note: &str, log_key: &PublicKey, log_id: &[u8]) -> Result<(u64, Vec<u8>), SigstoreError> {
+    let (text, signatures) = note.split_once("\n\n").ok_or_else(|| SigstoreError::InvalidCheckpoint("missing signature block".to_string()))?;
+    let signed = format!("{}\n", text);
+
+    let mut lines = text.lines().skip(1);
+    let size = lines.next().and_then(|l| l.parse::<u64>().ok());
+    let root = lines.next().and_then(|l| STANDARD.decode(l).ok());
+    let (Some(size), Some(root)) = (size, root) else {
+        return Err(SigstoreError::InvalidCheckpoint("expected origin, tree size and root hash lines".to_string()));
+    };
+
+    // Signature lines read `— <name> <base64 of 4-byte key hint || signature>`
+    let verified = signatures
+        .lines()
+        .filter_map(|line| line.strip_prefix("\u{2014} ")?.rsplit_once(' '))
+        .filter_map(|(_, sig)| STANDARD.decode(sig).ok())
+        .any(|sig| sig.len() > 4 && log_id.get(..4) == Some(&sig[..4]) && log_key.verify(signed.as_bytes(), &sig[4..], None));
+    if !verified {
+        return Err(SigstoreError::InvalidCheckpoint("no signature verifies under the log's key".to_string()));
+    }
+
+    Ok((size, root))
+}
+
+fn leaf_hash(data: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update([0]).chain_update(data).finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new().chain_update([1]).chain_update(left).chain_update(right).finalize().into()
+}
+
+/// Computes the root an RFC 9162 inclusion proof leads to.
+fn root_from_inclusion_proof(index: u64, tree_size: u64, leaf: [u8; 32], proof: &[[u8; 32]]) -> Option<[u8; 32]> {
+    if index >= tree_size {
+        return None;
+    }
+
+    let (mut fn_, mut sn) = (index, tree_size - 1);
+    let mut root = leaf;
+    for sibling in proof {
+        if sn == 0 {
+            return None;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            root = node_hash(sibling, &root);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            root = node_hash(&root, sibling);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    (sn == 0).then_some(root)
+}
+
+fn extension(cert: &Certificate, oid: ObjectIdentifier) -> Option<&[u8]> {
+    cert.tbs_certificate
+        .extensions
+        .iter()
+        .flatten()
+        .find(|ext| ext.extn_id == oid)
+        .map(|ext| ext.extn_value.as_bytes())
+}
+
+fn valid_at(cert: &Certificate, at: DateTime<Utc>) -> bool {
+    let validity = &cert.tbs_certificate.validity;
+    let not_before = validity.not_before.to_unix_duration().as_secs() as i64;
+    let not_after = validity.not_after.to_unix_duration().as_secs() as i64;
+    (not_before..=not_after).contains(&at.timestamp())
+}
+
+fn issued_by(cert: &Certificate, issuer: &Certificate) -> bool {
+    let hash = match cert.signature_algorithm.oid {
+        ECDSA_WITH_SHA256 => HashAlgorithm::Sha256,
+        ECDSA_WITH_SHA384 => HashAlgorithm::Sha384,
+        _ => return false,
+    };
+
+    let (Ok(tbs), Some(signature), Ok(key)) = (
+        cert.tbs_certificate.to_der(),
+        cert.signature.as_bytes(),
+        PublicKey::from_spki(issuer),
+    ) else {
+        return false;
+    };
+
+    cert.tbs_certificate.issuer == issuer.tbs_certificate.subject && key.verify(&tbs, signature, Some(hash))
+}
+
+#[derive(Clone, Copy)]
+enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+/// The ECDSA keys Fulcio and Rekor sign with.
+enum PublicKey {
+    P256(p256::ecdsa::VerifyingKey),
+    P384(p384::ecdsa::VerifyingKey),
+}
+
+impl PublicKey {
+    fn from_der(der: &[u8]) -> Result<Self, SigstoreError> {
+        p256::ecdsa::VerifyingKey::from_public_key_der(der)
+            .map(Self::P256)
+            .or_else(|_| p384::ecdsa::VerifyingKey::from_public_key_der(der).map(Self::P384))
+            .map_err(|_| SigstoreError::Malformed {
+                what: "public key",
+                reason: "expected an ECDSA P-256 or P-384 key".to_string(),
+            })
+    }
+
+    fn from_spki(cert: &Certificate) -> Result<Self, SigstoreError> {
+        Self::from_der(&cert.tbs_certificate.subject_public_key_info.to_der().map_err(malformed("public key"))?)
+    }
+
+    /// Verifies a DER-encoded signature over the message, hashed with the curve's own
+    /// hash unless another is given.
+    fn verify(&self, message: &[u8], signature: &[u8], hash: Option<HashAlgorithm>) -> bool {
+        let digest = |default| match hash.unwrap_or(default) {
+            HashAlgorithm::Sha256 => Sha256::digest(message).to_vec(),
+            HashAlgorithm::Sha384 => Sha384::digest(message).to_vec(),
+        };
+
+        match self {
+            PublicKey::P256(key) => p256::ecdsa::DerSignature::try_from(signature)
+                .is_ok_and(|sig| key.verify_prehash(&digest(HashAlgorithm::Sha256), &sig).is_ok()),
+            PublicKey::P384(key) => p384::ecdsa::DerSignature::try_from(signature)
+                .is_ok_and(|sig| key.verify_prehash(&digest(HashAlgorithm::Sha384), &sig).is_ok()),
+        }
+    }
+}
+
+/// Checks Sigstore-signed attestations against the trust root before trusting who
+/// signed them.
+///
+/// For attestations carrying verification material, the `allowed_issuers` outcome is
+/// decided by the certificate's identity and `max_age_days` by the time Rekor logged
+/// the entry. All other outcomes, and attestations without a bundle, are left to the
+/// inner verifier.
+pub struct SigstorePolicyVerifier<V> {
+    trust_root: TrustRoot,
+    inner: V,
+}
+
+impl<V: PolicyVerifier> SigstorePolicyVerifier<V> {
+    pub fn new(trust_root: TrustRoot, inner: V) -> Self {
+        Self { trust_root, inner }
+    }
+}
+
+#[async_trait]
+impl<V: PolicyVerifier> PolicyVerifier for SigstorePolicyVerifier<V> {
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        let mut result = self.inner.evaluate_attestation(attestation, policy).await?;
+        let Some(bundle) = attestation.bundle() else {
+            return Ok(result);
+        };
+
+        let outcomes = match self.trust_root.verify_bundle(&bundle) {
+            Ok(verified) if verified.statement != attestation.content => vec![RuleOutcome::new(
+                PolicyRule::AllowedIssuers,
+                false,
+                "signed statement differs from the attestation's content".to_string(),
+            )],
+            Ok(verified) => {
+                let age = Utc::now() - verified.integrated_time;
+                vec![
+                    RuleOutcome::new(
+                        PolicyRule::AllowedIssuers,
+                        policy.rules.is_identity_allowed(&verified.identity),
+                        format!("issuer {}", verified.identity),
+                    ),
+                    RuleOutcome::new(
+                        PolicyRule::MaxAgeDays,
+                        age <= policy.rules.max_age(),
+                        format!("age {} days since Rekor entry {}, limit {}", age.num_days(), verified.log_index, policy.rules.max_age_days),
+                    ),
+                ]
+            }
+            Err(e) => vec![RuleOutcome::new(PolicyRule::AllowedIssuers, false, format!("Sigstore bundle rejected: {}", e))],
+        };
+
+        for outcome in outcomes {
+            if let Some(existing) = result.outcomes.iter_mut().find(|o| o.rule == outcome.rule) {
+                *existing = outcome;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::dsse::{Envelope, EnvelopeSignature};
+    use crate::models::policy::PolicyRules;
+    use crate::models::sigstore::{Checkpoint, IdentityPattern, InclusionPromise, InclusionProof, KindVersion, VerificationMaterial};
+    use crate::verification::policy_verifier::SourcePolicyVerifier;
+    use p256::ecdsa::signature::Signer;
+    use p256::pkcs8::EncodePublicKey;
+    use serde_json::json;
+    use std::str::FromStr;
+    use std::time::Duration;
+    use x509_cert::builder::{Builder, CertificateBuilder, Profile};
+    use x509_cert::der::asn1::{Ia5String, UtcTime};
+    use x509_cert::der::pem::LineEnding;
+    use x509_cert::der::{EncodePem, Writer};
+    use x509_cert::ext::AsExtension;
+    use x509_cert::name::Name;
+    use x509_cert::serial_number::SerialNumber;
+    use x509_cert::spki::SubjectPublicKeyInfoOwned;
+    use x509_cert::time::{Time, Validity};
+
+    const WORKFLOW: &str = "https://github.com/acme/app/.github/workflows/release.yml@refs/tags/v1.0.0";
+
+    struct FulcioIssuer(&'static str);
+
+    impl AssociatedOid for FulcioIssuer {
+        const OID: ObjectIdentifier = FULCIO_ISSUER;
+    }
+
+    impl Encode for FulcioIssuer {
+        fn encoded_len(&self) -> x509_cert::der::Result<x509_cert::der::Length> {
+            Utf8StringRef::new(self.0)?.encoded_len()
+        }
+
+        fn encode(&self, writer: &mut impl Writer) -> x509_cert::der::Result<()> {
+            Utf8StringRef::new(self.0)?.encode(writer)
+        }
+    }
+
+    impl AsExtension for FulcioIssuer {
+        fn critical(&self, _: &Name, _: &[x509_cert::ext::Extension]) -> bool {
+            false
+        }
+    }
+
+    fn validity(now: i64) -> Validity {
+        let at = |secs: i64| Time::UtcTime(UtcTime::from_unix_duration(Duration::from_secs(secs as u64)).unwrap());
+        Validity {
+            not_before: at(now - 600),
+            not_after: at(now + 600),
+        }
+    }
+
+    fn tree_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        if leaves.len() == 1 {
+            return leaves[0];
+        }
+        let split = 1 << (leaves.len() - 1).ilog2();
+        node_hash(&tree_root(&leaves[..split]), &tree_root(&leaves[split..]))
+    }
+
+    fn audit_path(index: usize, leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+        if leaves.len() == 1 {
+            return Vec::new();
+        }
+        let split = 1 << (leaves.len() - 1).ilog2();
+        let (mut path, sibling) = if index < split {
+            (audit_path(index, &leaves[..split]), tree_root(&leaves[split..]))
+        } else {
+            (audit_path(index - split, &leaves[split..]), tree_root(&leaves[..split]))
+        };
+        path.push(sibling);
+        path
+    }
+
+    /// Signs a statement the way a keyless GitHub Actions workflow would, and logs it in a
+    /// five-entry Rekor tree.
+    fn fixture(statement: &Value) -> (TrustRoot, Bundle) {
+        let now = Utc::now().timestamp();
+        let ca_key = p384::ecdsa::SigningKey::from_slice(&[1; 48]).unwrap();
+        let leaf_key = p256::ecdsa::SigningKey::from_slice(&[2; 32]).unwrap();
+        let rekor_key = p256::ecdsa::SigningKey::from_slice(&[3; 32]).unwrap();
+
+        let ca_name = Name::from_str("CN=sigstore,O=sigstore.dev").unwrap();
+        let ca_spki = SubjectPublicKeyInfoOwned::from_key(*ca_key.verifying_key()).unwrap();
+        let ca_cert = CertificateBuilder::new(Profile::Root, SerialNumber::from(1u32), validity(now), ca_name.clone(), ca_spki, &ca_key)
+            .unwrap()
+            .build::<p384::ecdsa::DerSignature>()
+            .unwrap();
+
+        let leaf_spki = SubjectPublicKeyInfoOwned::from_key(*leaf_key.verifying_key()).unwrap();
+        let profile = Profile::Leaf {
+            issuer: ca_name,
+            enable_key_agreement: false,
+            enable_key_encipherment: false,
+        };
+        let mut builder = CertificateBuilder::new(profile, SerialNumber::from(2u32), validity(now), Name::default(), leaf_spki, &ca_key).unwrap();
+        builder.add_extension(&ExtendedKeyUsage(vec![CODE_SIGNING])).unwrap();
+        builder
+            .add_extension(&SubjectAltName(vec![GeneralName::UniformResourceIdentifier(Ia5String::new(WORKFLOW).unwrap())]))
+            .unwrap();
+        builder.add_extension(&FulcioIssuer("https://token.actions.githubusercontent.com")).unwrap();
+        let leaf_cert = builder.build::<p384::ecdsa::DerSignature>().unwrap();
+
+        let payload = serde_json::to_vec(statement).unwrap();
+        let signature: p256::ecdsa::DerSignature = leaf_key.sign(&pae(IN_TOTO_PAYLOAD_TYPE, &payload));
+        let envelope = Envelope {
+            payload_type: IN_TOTO_PAYLOAD_TYPE.to_string(),
+            payload: STANDARD.encode(&payload),
+            signatures: vec![EnvelopeSignature { keyid: String::new(), sig: STANDARD.encode(signature.as_bytes()) }],
+        };
+
+        let body = serde_json::to_vec(&json!({
+            "apiVersion": "0.0.1",
+            "kind": "dsse",
+            "spec": {
+                "envelopeHash": { "algorithm": "sha256", "value": hex::encode(Sha256::digest(serde_json::to_vec(&envelope).unwrap())) },
+                "payloadHash": { "algorithm": "sha256", "value": hex::encode(Sha256::digest(&payload)) },
+                "signatures": [{
+                    "signature": STANDARD.encode(signature.as_bytes()),
+                    "verifier": STANDARD.encode(leaf_cert.to_pem(LineEnding::LF).unwrap()),
+                }]
+            }
+        }))
+        .unwrap();
+        let body = STANDARD.encode(body);
+
+        let rekor_spki = rekor_key.verifying_key().to_public_key_der().unwrap();
+        let log_id = Sha256::digest(rekor_spki.as_bytes());
+        let log_index = 9_000;
+
+        let leaves: Vec<[u8; 32]> = [b"a".to_vec(), b"b".to_vec(), STANDARD.decode(&body).unwrap(), b"d".to_vec(), b"e".to_vec()]
+            .iter()
+            .map(|data| leaf_hash(data))
+            .collect();
+        let root = tree_root(&leaves);
+
+        let signed_entry = format!(r#"{{"body":"{}","integratedTime":{},"logID":"{}","logIndex":{}}}"#, body, now, hex::encode(log_id), log_index);
+        let set: p256::ecdsa::DerSignature = rekor_key.sign(signed_entry.as_bytes());
+
+        let note = format!("rekor.test - 42\n{}\n{}\n", leaves.len(), STANDARD.encode(root));
+        let note_signature: p256::ecdsa::DerSignature = rekor_key.sign(note.as_bytes());
+        let mut hinted = log_id[..4].to_vec();
+        hinted.extend_from_slice(note_signature.as_bytes());
+        let checkpoint = format!("{}\n\u{2014} rekor.test {}\n", note, STANDARD.encode(hinted));
+
+        let bundle = Bundle {
+            media_type: BUNDLE_MEDIA_TYPE.to_string(),
+            verification_material: VerificationMaterial {
+                certificate: X509Certificate { raw_bytes: STANDARD.encode(leaf_cert.to_der().unwrap()) },
+                tlog_entries: vec![TransparencyLogEntry {
+                    log_index,
+                    log_id: LogId { key_id: STANDARD.encode(log_id) },
+                    kind_version: KindVersion { kind: "dsse".to_string(), version: "0.0.1".to_string() },
+                    integrated_time: now,
+                    inclusion_promise: Some(InclusionPromise { signed_entry_timestamp: STANDARD.encode(set.as_bytes()) }),
+                    inclusion_proof: Some(InclusionProof {
+                        log_index: 2,
+                        root_hash: STANDARD.encode(root),
+                        tree_size: leaves.len() as i64,
+                        hashes: audit_path(2, &leaves).iter().map(|h| STANDARD.encode(h)).collect(),
+                        checkpoint: Checkpoint { envelope: checkpoint },
+                    }),
+                    canonicalized_body: body,
+                }],
+            },
+            dsse_envelope: envelope,
+        };
+
+        let trust_root = TrustRoot::from_json(
+            &json!({
+                "mediaType": "application/vnd.dev.sigstore.trustedroot+json;version=0.1",
+                "tlogs": [{
+                    "baseUrl": "https://rekor.test",
+                    "hashAlgorithm": "SHA2_256",
+                    "publicKey": { "rawBytes": STANDARD.encode(rekor_spki.as_bytes()), "keyDetails": "PKIX_ECDSA_P256_SHA_256" },
+                    "logId": { "keyId": STANDARD.encode(log_id) }
+                }],
+                "certificateAuthorities": [{
+                    "uri": "https://fulcio.test",
+                    "certChain": { "certificates": [{ "rawBytes": STANDARD.encode(ca_cert.to_der().unwrap()) }] },
+                    "validFor": { "start": "2020-01-01T00:00:00Z" }
+                }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        (trust_root, bundle)
+    }
+
+    #[tokio::test]
+    async fn test_verify_sigstore_bundle() {
+        let statement = json!({
+            "_type": "https://in-toto.io/Statement/v1",
+            "subject": [{ "name": "app", "digest": { "sha256": "a".repeat(64) } }]
+        });
+        let (trust_root, bundle) = fixture(&statement);
+
+        // Protobuf JSON writes 64-bit integers as strings
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(json.contains(r#""logIndex":"9000""#));
+        assert_eq!(serde_json::from_str::<Bundle>(&json).unwrap(), bundle);
+
+        let verified = trust_root.verify_bundle(&bundle).unwrap();
+        assert_eq!(verified.statement, statement);
+        assert_eq!(verified.identity.issuer, "https://token.actions.githubusercontent.com");
+        assert_eq!(verified.identity.san, WORKFLOW);
+        assert_eq!(verified.log_index, 9_000);
+
+        // Tampering with any part of the bundle is caught
+        let mut tampered = bundle.clone();
+        tampered.dsse_envelope.payload = STANDARD.encode(b"{}");
+        assert!(matches!(trust_root.verify_bundle(&tampered), Err(SigstoreError::InvalidSignature)));
+
+        let mut tampered = bundle.clone();
+        tampered.verification_material.tlog_entries[0].integrated_time -= 60;
+        assert!(matches!(trust_root.verify_bundle(&tampered), Err(SigstoreError::InvalidSignedEntryTimestamp)));
+
+        let mut tampered = bundle.clone();
+        tampered.verification_material.tlog_entries[0].inclusion_proof.as_mut().unwrap().hashes.swap(0, 1);
+        assert!(matches!(trust_root.verify_bundle(&tampered), Err(SigstoreError::InvalidInclusionProof)));
+
+        let (other_root, _) = fixture(&statement);
+        let mut untrusted = other_root.clone();
+        untrusted.certificate_authorities[0].cert_chain.certificates[0] = X509Certificate {
+            raw_bytes: bundle.verification_material.certificate.raw_bytes.clone(),
+        };
+        assert!(matches!(untrusted.verify_bundle(&bundle), Err(SigstoreError::UntrustedCertificate(_))));
+
+        // Policies name the workflow rather than an opaque issuer string
+        let attestation = trust_root.import_bundle(bundle).unwrap();
+        assert_eq!(attestation.issuer, format!("sigstore:https://token.actions.githubusercontent.com|{}", WORKFLOW));
+
+        let policy = |pattern: IdentityPattern| Policy {
+            purl: "pkg:policy/release".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules::new([pattern.to_string()].into_iter().collect(), 7, 0, 0),
+            selection: None,
+        };
+        let release = policy(IdentityPattern::github_workflow("acme/app", "release.yml", "refs/tags/v*"));
+        let ci = policy(IdentityPattern::github_workflow("acme/app", "ci.yml", "refs/heads/*"));
+
+        let verifier = SigstorePolicyVerifier::new(trust_root, SourcePolicyVerifier);
+        assert!(verifier.verify_attestation(&attestation, &release).await.unwrap());
+        let result = verifier.evaluate_attestation(&attestation, &ci).await.unwrap();
+        assert_eq!(result.failed_rules(), vec![PolicyRule::AllowedIssuers]);
+
+        // Claiming the identity without the verification material does not pass
+        let unsigned = Attestation { verification_material: None, ..attestation.clone() };
+        assert!(!verifier.verify_attestation(&unsigned, &release).await.unwrap());
+
+        let swapped = Attestation { content: json!({ "subject": [] }), ..attestation };
+        assert!(!verifier.verify_attestation(&swapped, &release).await.unwrap());
+    }
+}
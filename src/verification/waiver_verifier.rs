@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::models::{attestation::Attestation, policy::Policy, waiver::Waiver};
+use crate::storage::waiver_repository::WaiverRepository;
+use crate::verification::policy_verifier::{AppliedWaiver, PolicyVerifier, VerificationError, VerificationResult};
+
+/// Wraps another verifier and applies active waivers to its failing rule outcomes.
+///
+/// Only vulnerability thresholds are waived; failing issuer and age rules stand even if
+/// a stored waiver names them. A waiver without a finding ID covers its rule outright.
+/// Finding-level waivers only pass a rule once enough of its counted findings are waived
+/// to bring it within the limit. Expired waivers are ignored.
+pub struct WaiverAwarePolicyVerifier<V: PolicyVerifier, W: WaiverRepository> {
+    inner: Arc<V>,
+    waiver_repo: Arc<W>,
+}
+
+impl<V: PolicyVerifier, W: WaiverRepository> WaiverAwarePolicyVerifier<V, W> {
+    pub fn new(inner: Arc<V>, waiver_repo: Arc<W>) -> Self {
+        Self { inner, waiver_repo }
+    }
+}
+
+fn applied(waiver: &Waiver) -> AppliedWaiver {
+    AppliedWaiver {
+        waiver_id: waiver.id.clone(),
+        rule: waiver.rule,
+        finding_id: waiver.finding_id.clone(),
+        approver: waiver.approver.clone(),
+        justification: waiver.justification.clone(),
+        expires_at: waiver.expires_at,
+    }
+}
+
+#[async_trait]
+impl<V: PolicyVerifier, W: WaiverRepository> PolicyVerifier for WaiverAwarePolicyVerifier<V, W> {
+    async fn evaluate_attestation(&self, attestation: &Attestation, policy: &Policy) -> Result<VerificationResult, VerificationError> {
+        let mut result = self.inner.evaluate_attestation(attestation, policy).await?;
+        if result.is_valid() {
+            return Ok(result);
+        }
+
+        let waivers: Vec<_> = self.waiver_repo
+            .active_waivers(Utc::now())
+            .await?
+            .into_iter()
+            .filter(|w| w.applies_to(&attestation.content))
+            .collect();
+
+        let mut waivers_applied = Vec::new();
+        for outcome in result.outcomes.iter_mut().filter(|o| !o.passed && o.rule.is_vulnerability_threshold()) {
+            let relevant: Vec<_> = waivers.iter().filter(|w| w.rule == outcome.rule).collect();
+
+            if let Some(waiver) = relevant.iter().find(|w| w.finding_id.is_none()) {
+                outcome.passed = true;
+                outcome.reason = format!("{} (waived by {})", outcome.reason, waiver.id);
+                waivers_applied.push(applied(waiver));
+                continue;
+            }
+
+            let mut waived_findings = HashSet::new();
+            let finding_waivers: Vec<_> = relevant
+                .into_iter()
+                .filter(|w| {
+                    w.finding_id
+                        .as_ref()
+                        .is_some_and(|id| outcome.findings.contains(id) && waived_findings.insert(id.clone()))
+                })
+                .collect();
+
+            if outcome.excess > 0 && waived_findings.len() >= outcome.excess as usize {
+                outcome.passed = true;
+                outcome.reason = format!("{} ({} findings waived)", outcome.reason, waived_findings.len());
+                waivers_applied.extend(finding_waivers.into_iter().map(|w| applied(w)));
+            }
+        }
+
+        result.waivers_applied = waivers_applied;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use serde_json::json;
+
+    use crate::models::policy::{PolicyRule, PolicyRules};
+    use crate::models::waiver::WaiverScope;
+    use crate::storage::waiver_repository::InMemoryWaiverRepository;
+    use crate::verification::policy_verifier::SimplePolicyVerifier;
+
+    #[tokio::test]
+    async fn test_waivers_applied_to_failing_rules() {
+        let waiver_repo = Arc::new(InMemoryWaiverRepository::new());
+        let verifier = WaiverAwarePolicyVerifier::new(Arc::new(SimplePolicyVerifier), waiver_repo.clone());
+
+        let policy = Policy {
+            purl: "pkg:policy/test".to_string(),
+            version: "1.0.0".to_string(),
+            rules: PolicyRules {
+                allowed_issuers: vec!["trusted_issuer".to_string()].into_iter().collect(),
+                max_age_days: 7,
+                max_critical_vulnerabilities: 0,
+                max_high_medium_vulnerabilities: 5,
+            },
+            selection: None,
+        };
+
+        let attestation = Attestation {
+            id: "frontend-att".to_string(),
+            issuer: "trusted_issuer".to_string(),
+            timestamp: Utc::now(),
+            content: json!({
+                "subject": [{ "name": "frontend", "version": "1.2.3" }],
+                "vulnerabilities": {
+                    "critical": 1,
+                    "high": 0,
+                    "medium": 0,
+                    "findings": [{ "id": "CVE-2024-0001", "severity": "critical" }]
+                }
+            }),
+            envelope: None,
+            verification_material: None,
+        };
+
+        assert!(!verifier.verify_attestation(&attestation, &policy).await.unwrap());
+
+        // An expired waiver is ignored
+        let mut expired = Waiver::new(
+            WaiverScope::Version { name: "frontend".to_string(), version: "1.2.3".to_string() },
+            PolicyRule::MaxCriticalVulnerabilities,
+            "security-lead".to_string(),
+            "Accepted until the vendor patch".to_string(),
+            Utc::now() + Duration::days(1),
+        ).unwrap().with_finding("CVE-2024-0001".to_string());
+        expired.created_at = Utc::now() - Duration::days(30);
+        expired.expires_at = Utc::now() - Duration::days(1);
+        waiver_repo.add_waiver(expired).await.unwrap();
+        assert!(!verifier.verify_attestation(&attestation, &policy).await.unwrap());
+
+        // A waiver for a different finding does not cover the rule
+        let unrelated = Waiver::new(
+            WaiverScope::Name { name: "frontend".to_string() },
+            PolicyRule::MaxCriticalVulnerabilities,
+            "security-lead".to_string(),
+            "False positive".to_string(),
+            Utc::now() + Duration::days(7),
+        ).unwrap().with_finding("CVE-2024-9999".to_string());
+        waiver_repo.add_waiver(unrelated).await.unwrap();
+        assert!(!verifier.verify_attestation(&attestation, &policy).await.unwrap());
+
+        let active = Waiver::new(
+            WaiverScope::Name { name: "frontend".to_string() },
+            PolicyRule::MaxCriticalVulnerabilities,
+            "security-lead".to_string(),
+            "No fix available, mitigated by WAF rule".to_string(),
+            Utc::now() + Duration::days(7),
+        ).unwrap().with_finding("CVE-2024-0001".to_string());
+        let active_id = active.id.clone();
+        waiver_repo.add_waiver(active).await.unwrap();
+
+        let result = verifier.evaluate_attestation(&attestation, &policy).await.unwrap();
+        assert!(result.is_valid());
+        assert_eq!(result.waivers_applied.len(), 1);
+        assert_eq!(result.waivers_applied[0].waiver_id, active_id);
+        assert_eq!(result.waivers_applied[0].finding_id.as_deref(), Some("CVE-2024-0001"));
+    }
+}